@@ -1,12 +1,12 @@
 use anchor_client::Cluster;
 use anchor_syn::idl::Idl;
-use anyhow::{anyhow, Error, Result};
+use anyhow::{anyhow, Context, Error, Result};
 use clap::{ArgEnum, Parser};
 use heck::SnakeCase;
 use serde::{Deserialize, Serialize};
 use solana_sdk::pubkey::Pubkey;
 use solana_sdk::signature::{Keypair, Signer};
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashSet};
 use std::convert::TryFrom;
 use std::fs::{self, File};
 use std::io::prelude::*;
@@ -89,6 +89,46 @@ impl Manifest {
         }
     }
 
+    // Root files of the path dependencies of the crate, and of theirs in turn,
+    // given the directory containing its manifest.
+    pub fn path_dependencies(&self, dir: impl AsRef<Path>) -> Result<Vec<PathBuf>> {
+        let mut visited = HashSet::new();
+        let mut roots = vec![];
+        self.add_path_dependencies(dir.as_ref(), &mut visited, &mut roots)?;
+        Ok(roots)
+    }
+
+    fn add_path_dependencies(
+        &self,
+        dir: &Path,
+        visited: &mut HashSet<PathBuf>,
+        roots: &mut Vec<PathBuf>,
+    ) -> Result<()> {
+        let paths = self
+            .dependencies
+            .values()
+            .filter_map(|dep| dep.detail().and_then(|d| d.path.as_ref()));
+        for path in paths {
+            let dep_dir = dir
+                .join(path)
+                .canonicalize()
+                .with_context(|| format!("Path dependency {} not found", path))?;
+            // Crates depended on by several others are only added once.
+            if !visited.insert(dep_dir.clone()) {
+                continue;
+            }
+            let dep_cargo = Manifest::from_path(dep_dir.join("Cargo.toml"))?;
+            let lib_path = dep_cargo
+                .lib
+                .as_ref()
+                .and_then(|lib| lib.path.clone())
+                .unwrap_or_else(|| "src/lib.rs".to_string());
+            roots.push(dep_dir.join(lib_path));
+            dep_cargo.add_path_dependencies(&dep_dir, visited, roots)?;
+        }
+        Ok(())
+    }
+
     // Climbs each parent directory from the current dir until we find a Cargo.toml
     pub fn discover() -> Result<Option<WithPath<Manifest>>> {
         Manifest::discover_from_path(std::env::current_dir()?)
@@ -165,8 +205,8 @@ impl WithPath<Config> {
             let idl = anchor_syn::idl::file::parse(
                 path.join("src/lib.rs"),
                 version,
-                self.features.seeds,
                 false,
+                &cargo.path_dependencies(&path)?,
             )?;
             r.push(Program {
                 lib_name,
@@ -248,7 +288,6 @@ impl<T> std::ops::DerefMut for WithPath<T> {
 pub struct Config {
     pub anchor_version: Option<String>,
     pub solana_version: Option<String>,
-    pub features: FeaturesConfig,
     pub registry: RegistryConfig,
     pub provider: ProviderConfig,
     pub programs: ProgramsConfig,
@@ -257,12 +296,6 @@ pub struct Config {
     pub test: Option<Test>,
 }
 
-#[derive(Default, Clone, Debug, Serialize, Deserialize)]
-pub struct FeaturesConfig {
-    #[serde(default)]
-    pub seeds: bool,
-}
-
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct RegistryConfig {
     pub url: String,
@@ -374,7 +407,6 @@ impl Config {
 struct _Config {
     anchor_version: Option<String>,
     solana_version: Option<String>,
-    features: Option<FeaturesConfig>,
     programs: Option<BTreeMap<String, BTreeMap<String, serde_json::Value>>>,
     registry: Option<RegistryConfig>,
     provider: Provider,
@@ -402,7 +434,6 @@ impl ToString for Config {
         let cfg = _Config {
             anchor_version: self.anchor_version.clone(),
             solana_version: self.solana_version.clone(),
-            features: Some(self.features.clone()),
             registry: Some(self.registry.clone()),
             provider: Provider {
                 cluster: format!("{}", self.provider.cluster),
@@ -431,7 +462,6 @@ impl FromStr for Config {
         Ok(Config {
             anchor_version: cfg.anchor_version,
             solana_version: cfg.solana_version,
-            features: cfg.features.unwrap_or_default(),
             registry: cfg.registry.unwrap_or_default(),
             provider: ProviderConfig {
                 cluster: cfg.provider.cluster.parse()?,
@@ -700,3 +730,38 @@ impl AnchorPackage {
 }
 
 serum_common::home_path!(WalletPath, ".config/solana/id.json");
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolves_path_dependencies_recursively() {
+        let workspace =
+            Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/path-dependencies");
+        let program = workspace.join("programs/example");
+        let cargo = Manifest::from_path(program.join("Cargo.toml")).unwrap();
+        let dependencies = cargo.path_dependencies(&program).unwrap();
+
+        let crates = workspace.join("crates").canonicalize().unwrap();
+        assert_eq!(
+            dependencies,
+            vec![
+                crates.join("direct/src/lib.rs"),
+                crates.join("transitive/src/transitive.rs"),
+            ]
+        );
+
+        // Types of both the direct and the transitive dependency are defined.
+        let idl = anchor_syn::idl::file::parse(
+            program.join("src/lib.rs"),
+            cargo.version(),
+            false,
+            &dependencies,
+        )
+        .unwrap()
+        .unwrap();
+        let types: Vec<&str> = idl.types.iter().map(|ty| ty.name.as_str()).collect();
+        assert_eq!(types, vec!["Data", "Amount"]);
+    }
+}
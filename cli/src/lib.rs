@@ -4,8 +4,10 @@ use crate::config::{
 };
 use anchor_client::Cluster;
 use anchor_lang::idl::{IdlAccount, IdlInstruction};
-use anchor_lang::{AccountDeserialize, AnchorDeserialize, AnchorSerialize};
-use anchor_syn::idl::Idl;
+use anchor_lang::{AccountDeserialize, AnchorDeserialize, AnchorSerialize, Discriminator};
+use anchor_syn::idl::build::{complete, from_fragments, IDL_BUILD_TEST_PREFIX};
+use anchor_syn::idl::{spec, Idl};
+use anchor_syn::parser::context::CrateContext;
 use anyhow::{anyhow, Context, Result};
 use clap::Parser;
 use flate2::read::GzDecoder;
@@ -13,13 +15,19 @@ use flate2::read::ZlibDecoder;
 use flate2::write::{GzEncoder, ZlibEncoder};
 use flate2::Compression;
 use heck::SnakeCase;
+use jsonschema::{Draft, JSONSchema};
 use rand::rngs::OsRng;
 use reqwest::blocking::multipart::{Form, Part};
 use reqwest::blocking::Client;
 use semver::{Version, VersionReq};
 use serde::{Deserialize, Serialize};
+use solana_account_decoder::{UiAccountEncoding, UiDataSliceConfig};
 use solana_client::rpc_client::RpcClient;
-use solana_client::rpc_config::RpcSendTransactionConfig;
+use solana_client::rpc_config::{
+    RpcAccountInfoConfig, RpcProgramAccountsConfig, RpcSendTransactionConfig,
+};
+use solana_client::rpc_filter::{Memcmp, RpcFilterType};
+use solana_program::entrypoint::MAX_PERMITTED_DATA_INCREASE;
 use solana_program::instruction::{AccountMeta, Instruction};
 use solana_sdk::account_utils::StateMut;
 use solana_sdk::bpf_loader;
@@ -307,6 +315,22 @@ pub enum IdlCommand {
         #[clap(short, long)]
         filepath: String,
     },
+    /// Resizes the program's IDL account to hold the given number of bytes
+    /// of (compressed) IDL data.
+    Resize {
+        program_id: Pubkey,
+        #[clap(short, long)]
+        data_len: u64,
+    },
+    /// Closes the program's IDL account and all IDL buffers belonging to the
+    /// wallet, e.g. the ones left behind by failed upgrades, returning their
+    /// rent to the wallet.
+    Close {
+        program_id: Pubkey,
+        /// Only close the IDL buffers, keeping the program's IDL account.
+        #[clap(long)]
+        buffers_only: bool,
+    },
     /// Sets a new authority on the IDL account.
     SetAuthority {
         /// The IDL account buffer to set the authority of. If none is given,
@@ -351,6 +375,29 @@ pub enum IdlCommand {
         #[clap(short, long)]
         out: Option<String>,
     },
+    /// Upgrades an IDL file of an older spec to the current one.
+    Convert {
+        file: String,
+        /// Output file for the idl (stdout if not specified).
+        #[clap(short, long)]
+        out: Option<String>,
+    },
+    /// Outputs the JSON Schema of the current IDL spec.
+    Schema {
+        /// Output file for the schema (stdout if not specified).
+        #[clap(short, long)]
+        out: Option<String>,
+    },
+    /// Compares two IDLs and lists the changes between them, failing if any
+    /// breaks existing clients or the data stored by the program.
+    Diff {
+        /// The old IDL. Either a file, or the address of a program, IDL
+        /// account or IDL buffer to fetch it from the cluster.
+        old: String,
+        /// The new IDL file. Defaults to the IDL of the program in the current
+        /// directory, built like `anchor build` does.
+        new: Option<String>,
+    },
 }
 
 #[derive(Debug, Parser)]
@@ -900,7 +947,7 @@ fn build_cwd_verifiable(
         Ok(_) => {
             // Build the idl.
             println!("Extracting the IDL");
-            if let Ok(Some(idl)) = extract_idl(cfg, "src/lib.rs", skip_lint) {
+            if let Ok(Some(idl)) = extract_idl("src/lib.rs", skip_lint) {
                 // Write out the JSON file.
                 println!("Writing the IDL file");
                 let out_file = workspace_dir.join(format!("target/idl/{}.json", idl.name));
@@ -1175,7 +1222,7 @@ fn _build_cwd(
     }
 
     // Always assume idl is located at src/lib.rs.
-    if let Some(idl) = extract_idl(cfg, "src/lib.rs", skip_lint)? {
+    if let Some(idl) = build_idl(skip_lint)? {
         // JSON out path.
         let out = match idl_out {
             None => PathBuf::from(".").join(&idl.name).with_extension("json"),
@@ -1260,7 +1307,7 @@ fn verify(
     }
 
     // Verify IDL (only if it's not a buffer account).
-    if let Some(local_idl) = extract_idl(&cfg, "src/lib.rs", true)? {
+    if let Some(local_idl) = extract_idl("src/lib.rs", true)? {
         if bin_ver.state != BinVerificationState::Buffer {
             let deployed_idl = fetch_idl(cfg_override, program_id)?;
             if local_idl != deployed_idl {
@@ -1325,7 +1372,7 @@ pub fn verify_bin(program_id: Pubkey, bin_path: &Path, cluster: &str) -> Result<
                         .value
                         .map_or(Err(anyhow!("Account not found")), Ok)?;
                     let bin = account.data
-                        [UpgradeableLoaderState::programdata_data_offset().unwrap_or(0)..]
+                        [UpgradeableLoaderState::size_of_programdata_metadata()..]
                         .to_vec();
 
                     if let UpgradeableLoaderState::ProgramData {
@@ -1343,7 +1390,7 @@ pub fn verify_bin(program_id: Pubkey, bin_path: &Path, cluster: &str) -> Result<
                     }
                 }
                 UpgradeableLoaderState::Buffer { .. } => {
-                    let offset = UpgradeableLoaderState::buffer_data_offset().unwrap_or(0);
+                    let offset = UpgradeableLoaderState::size_of_buffer_metadata();
                     (
                         account.data[offset..].to_vec(),
                         BinVerificationState::Buffer,
@@ -1421,15 +1468,98 @@ fn fetch_idl(cfg_override: &ConfigOverride, idl_addr: Pubkey) -> Result<Idl> {
     let mut z = ZlibDecoder::new(&idl_account.data[..]);
     let mut s = Vec::new();
     z.read_to_end(&mut s)?;
-    serde_json::from_slice(&s[..]).map_err(Into::into)
+    let idl = spec::upgrade(serde_json::from_slice(&s[..])?)?;
+    serde_json::from_value(idl).map_err(Into::into)
+}
+
+// Reads an IDL file, which must conform to the current spec.
+fn read_idl(file: &str) -> Result<Idl> {
+    let bytes = fs::read(file).with_context(|| format!("Failed to read {}", file))?;
+    let idl: serde_json::Value = serde_json::from_slice(&bytes)?;
+    let idl_spec = idl.get("spec").and_then(|spec| spec.as_str()).unwrap_or("");
+    if idl_spec != spec::IDL_SPEC {
+        return Err(anyhow!(
+            "{} isn't of the current IDL spec {}, upgrade it with `anchor idl convert`",
+            file,
+            spec::IDL_SPEC
+        ));
+    }
+    validate_idl(&idl)?;
+    serde_json::from_value(idl).map_err(Into::into)
+}
+
+// Validates an IDL against the schema of the current spec, listing every
+// violation in the error.
+fn validate_idl(idl: &serde_json::Value) -> Result<()> {
+    let schema = spec::schema();
+    let schema = JSONSchema::options()
+        .with_draft(Draft::Draft7)
+        .compile(&schema)
+        .map_err(|err| anyhow!("Invalid IDL schema: {}", err))?;
+    let violations = match schema.validate(idl) {
+        Ok(()) => return Ok(()),
+        Err(errors) => errors
+            .map(|err| format!("{}: {}", err.instance_path, err))
+            .collect::<Vec<_>>(),
+    };
+    Err(anyhow!(
+        "IDL doesn't conform to spec {}:\n  {}",
+        spec::IDL_SPEC,
+        violations.join("\n  ")
+    ))
 }
 
-fn extract_idl(cfg: &WithPath<Config>, file: &str, skip_lint: bool) -> Result<Option<Idl>> {
+fn extract_idl(file: &str, skip_lint: bool) -> Result<Option<Idl>> {
     let file = shellexpand::tilde(file);
     let manifest_from_path = std::env::current_dir()?.join(PathBuf::from(&*file).parent().unwrap());
     let cargo = Manifest::discover_from_path(manifest_from_path)?
         .ok_or_else(|| anyhow!("Cargo.toml not found"))?;
-    anchor_syn::idl::file::parse(&*file, cargo.version(), cfg.features.seeds, !skip_lint)
+    let dependencies = cargo.path_dependencies(cargo.path().parent().unwrap())?;
+    anchor_syn::idl::file::parse(&*file, cargo.version(), !skip_lint, &dependencies)
+}
+
+// Builds the IDL of the program in the current directory. If the program has
+// an `idl-build` feature, the IDL is built from the fragments printed by the
+// tests generated with the feature, completed by the source parser. Otherwise
+// it's parsed from source.
+fn build_idl(skip_lint: bool) -> Result<Option<Idl>> {
+    let cargo = Manifest::discover()?.ok_or_else(|| anyhow!("Cargo.toml not found"))?;
+    if !cargo.features.contains_key("idl-build") {
+        return extract_idl("src/lib.rs", skip_lint);
+    }
+    let dependencies = cargo.path_dependencies(cargo.path().parent().unwrap())?;
+    let ctx = CrateContext::parse_with_dependencies("src/lib.rs", &dependencies)?;
+    if !skip_lint {
+        ctx.safety_checks()?;
+    }
+
+    let exit = std::process::Command::new("cargo")
+        .args(["test", "--lib", "--features", "idl-build"])
+        .arg(IDL_BUILD_TEST_PREFIX)
+        .args(["--", "--show-output", "--quiet"])
+        .stderr(Stdio::inherit())
+        .output()
+        .map_err(|e| anyhow::format_err!("{}", e))?;
+    if !exit.status.success() {
+        std::io::stdout().write_all(&exit.stdout)?;
+        return Err(anyhow!("Failed to build the IDL"));
+    }
+    let mut idl = match from_fragments(std::str::from_utf8(&exit.stdout)?)? {
+        None => return Ok(None),
+        Some(idl) => idl,
+    };
+    idl.version = cargo.version();
+
+    let parsed = extract_idl("src/lib.rs", true).unwrap_or_else(|e| {
+        eprintln!(
+            "Warning: failed to parse the program, the state, constants and PDAs are omitted from the IDL: {}",
+            e
+        );
+        None
+    });
+    complete(&mut idl, &ctx, parsed)?;
+
+    Ok(Some(idl))
 }
 
 fn idl(cfg_override: &ConfigOverride, subcmd: IdlCommand) -> Result<()> {
@@ -1449,6 +1579,14 @@ fn idl(cfg_override: &ConfigOverride, subcmd: IdlCommand) -> Result<()> {
             program_id,
             filepath,
         } => idl_upgrade(cfg_override, program_id, filepath),
+        IdlCommand::Resize {
+            program_id,
+            data_len,
+        } => idl_resize(cfg_override, program_id, data_len),
+        IdlCommand::Close {
+            program_id,
+            buffers_only,
+        } => idl_close(cfg_override, program_id, buffers_only),
         IdlCommand::SetAuthority {
             program_id,
             address,
@@ -1456,8 +1594,11 @@ fn idl(cfg_override: &ConfigOverride, subcmd: IdlCommand) -> Result<()> {
         } => idl_set_authority(cfg_override, program_id, address, new_authority),
         IdlCommand::EraseAuthority { program_id } => idl_erase_authority(cfg_override, program_id),
         IdlCommand::Authority { program_id } => idl_authority(cfg_override, program_id),
-        IdlCommand::Parse { file, out, out_ts } => idl_parse(cfg_override, file, out, out_ts),
+        IdlCommand::Parse { file, out, out_ts } => idl_parse(file, out, out_ts),
         IdlCommand::Fetch { address, out } => idl_fetch(cfg_override, address, out),
+        IdlCommand::Convert { file, out } => idl_convert(file, out),
+        IdlCommand::Schema { out } => idl_schema(out),
+        IdlCommand::Diff { old, new } => idl_diff(cfg_override, old, new),
     }
 }
 
@@ -1465,8 +1606,7 @@ fn idl_init(cfg_override: &ConfigOverride, program_id: Pubkey, idl_filepath: Str
     with_workspace(cfg_override, |cfg| {
         let keypair = cfg.provider.wallet.to_string();
 
-        let bytes = fs::read(idl_filepath)?;
-        let idl: Idl = serde_json::from_reader(&*bytes)?;
+        let idl = read_idl(&idl_filepath)?;
 
         let idl_address = create_idl_account(cfg, &keypair, &program_id, &idl)?;
 
@@ -1483,8 +1623,7 @@ fn idl_write_buffer(
     with_workspace(cfg_override, |cfg| {
         let keypair = cfg.provider.wallet.to_string();
 
-        let bytes = fs::read(idl_filepath)?;
-        let idl: Idl = serde_json::from_reader(&*bytes)?;
+        let idl = read_idl(&idl_filepath)?;
 
         let idl_buffer = create_idl_buffer(cfg, &keypair, &program_id, &idl)?;
         idl_write(cfg, &program_id, &idl, idl_buffer)?;
@@ -1502,11 +1641,23 @@ fn idl_set_buffer(cfg_override: &ConfigOverride, program_id: Pubkey, buffer: Pub
         let url = cluster_url(cfg);
         let client = RpcClient::new(url);
 
+        // Grow the IdlAccount if the new idl doesn't fit.
+        let idl_address = IdlAccount::address(&program_id);
+        let idl_len = {
+            let account = client.get_account(&buffer)?;
+            let idl_account: IdlAccount = AccountDeserialize::try_deserialize(&mut &*account.data)?;
+            idl_account.data.len()
+        };
+        if IdlAccount::space(idl_len) > client.get_account(&idl_address)?.data.len() {
+            // Double for future growth.
+            resize_idl_account(&client, &keypair, &program_id, idl_address, idl_len * 2)?;
+        }
+
         // Instruction to set the buffer onto the IdlAccount.
         let set_buffer_ix = {
             let accounts = vec![
                 AccountMeta::new(buffer, false),
-                AccountMeta::new(IdlAccount::address(&program_id), false),
+                AccountMeta::new(idl_address, false),
                 AccountMeta::new(keypair.pubkey(), true),
             ];
             let mut data = anchor_lang::idl::IDL_IX_TAG.to_le_bytes().to_vec();
@@ -1519,7 +1670,7 @@ fn idl_set_buffer(cfg_override: &ConfigOverride, program_id: Pubkey, buffer: Pub
         };
 
         // Build the transaction.
-        let (recent_hash, _fee_calc) = client.get_recent_blockhash()?;
+        let recent_hash = client.get_latest_blockhash()?;
         let tx = Transaction::new_signed_with_payer(
             &[set_buffer_ix],
             Some(&keypair.pubkey()),
@@ -1550,6 +1701,179 @@ fn idl_upgrade(
     idl_set_buffer(cfg_override, program_id, buffer)
 }
 
+fn idl_resize(cfg_override: &ConfigOverride, program_id: Pubkey, data_len: u64) -> Result<()> {
+    with_workspace(cfg_override, |cfg| {
+        let keypair = solana_sdk::signature::read_keypair_file(cfg.provider.wallet.to_string())
+            .map_err(|_| anyhow!("Unable to read keypair file"))?;
+        let url = cluster_url(cfg);
+        let client = RpcClient::new(url);
+
+        let idl_address = IdlAccount::address(&program_id);
+        resize_idl_account(
+            &client,
+            &keypair,
+            &program_id,
+            idl_address,
+            data_len as usize,
+        )?;
+
+        println!("Idl account resized: {:?}", idl_address);
+
+        Ok(())
+    })
+}
+
+// Resizes the IDL account to hold `data_len` bytes of idl data, growing it in
+// steps of at most `MAX_PERMITTED_DATA_INCREASE` bytes, the most a single
+// instruction can grow an account by.
+fn resize_idl_account(
+    client: &RpcClient,
+    keypair: &Keypair,
+    program_id: &Pubkey,
+    idl_address: Pubkey,
+    data_len: usize,
+) -> Result<()> {
+    let space = IdlAccount::space(data_len);
+    let mut current_space = client.get_account(&idl_address)?.data.len();
+    while current_space != space {
+        let next_space = match space > current_space {
+            true => std::cmp::min(space, current_space + MAX_PERMITTED_DATA_INCREASE),
+            false => space,
+        };
+        // Instruction data.
+        let data = serialize_idl_ix(anchor_lang::idl::IdlInstruction::Resize {
+            data_len: (next_space - IdlAccount::space(0)) as u64,
+        })?;
+        // Instruction accounts.
+        let accounts = vec![
+            AccountMeta::new(idl_address, false),
+            AccountMeta::new(keypair.pubkey(), true),
+            AccountMeta::new_readonly(solana_program::system_program::ID, false),
+        ];
+        // Instruction.
+        let ix = Instruction {
+            program_id: *program_id,
+            accounts,
+            data,
+        };
+        // Send transaction.
+        let recent_hash = client.get_latest_blockhash()?;
+        let tx = Transaction::new_signed_with_payer(
+            &[ix],
+            Some(&keypair.pubkey()),
+            &[keypair],
+            recent_hash,
+        );
+        client.send_and_confirm_transaction_with_spinner_and_config(
+            &tx,
+            CommitmentConfig::confirmed(),
+            RpcSendTransactionConfig {
+                skip_preflight: true,
+                ..RpcSendTransactionConfig::default()
+            },
+        )?;
+        current_space = next_space;
+    }
+    Ok(())
+}
+
+fn idl_close(cfg_override: &ConfigOverride, program_id: Pubkey, buffers_only: bool) -> Result<()> {
+    with_workspace(cfg_override, |cfg| {
+        let keypair = solana_sdk::signature::read_keypair_file(cfg.provider.wallet.to_string())
+            .map_err(|_| anyhow!("Unable to read keypair file"))?;
+        let url = cluster_url(cfg);
+        let client = RpcClient::new(url);
+
+        // The IDL account and buffers are the program's IdlAccounts, so find
+        // the ones the wallet has authority over.
+        let idl_address = IdlAccount::address(&program_id);
+        let config = RpcProgramAccountsConfig {
+            filters: Some(vec![
+                RpcFilterType::Memcmp(Memcmp::new_base58_encoded(0, IdlAccount::DISCRIMINATOR)),
+                RpcFilterType::Memcmp(Memcmp::new_base58_encoded(
+                    IdlAccount::DISCRIMINATOR.len(),
+                    keypair.pubkey().as_ref(),
+                )),
+            ]),
+            account_config: RpcAccountInfoConfig {
+                encoding: Some(UiAccountEncoding::Base64),
+                data_slice: Some(UiDataSliceConfig {
+                    offset: 0,
+                    length: 0,
+                }),
+                commitment: None,
+                min_context_slot: None,
+            },
+            with_context: None,
+        };
+        let mut addresses: Vec<Pubkey> = client
+            .get_program_accounts_with_config(&program_id, config)?
+            .into_iter()
+            .map(|(address, _account)| address)
+            .filter(|address| !buffers_only || *address != idl_address)
+            .collect();
+
+        if addresses.is_empty() {
+            println!("No IDL accounts to close.");
+            return Ok(());
+        }
+        if addresses.contains(&idl_address) {
+            println!("Are you sure you want to close the IDL account: [y/n]");
+
+            let input = std::io::stdin()
+                .lock()
+                .lines()
+                .next()
+                .ok_or_else(|| anyhow!("No answer given"))??;
+            if input != "y" {
+                // Buffers are closed regardless.
+                println!("Not closing the IDL account.");
+                addresses.retain(|address| *address != idl_address);
+            }
+        }
+
+        for address in addresses {
+            // Instruction data.
+            let data = serialize_idl_ix(anchor_lang::idl::IdlInstruction::Close)?;
+            // Instruction accounts.
+            let accounts = vec![
+                AccountMeta::new(address, false),
+                AccountMeta::new_readonly(keypair.pubkey(), true),
+                AccountMeta::new(keypair.pubkey(), false),
+            ];
+            // Instruction.
+            let ix = Instruction {
+                program_id,
+                accounts,
+                data,
+            };
+            // Send transaction.
+            let recent_hash = client.get_latest_blockhash()?;
+            let tx = Transaction::new_signed_with_payer(
+                &[ix],
+                Some(&keypair.pubkey()),
+                &[&keypair],
+                recent_hash,
+            );
+            client.send_and_confirm_transaction_with_spinner_and_config(
+                &tx,
+                CommitmentConfig::confirmed(),
+                RpcSendTransactionConfig {
+                    skip_preflight: true,
+                    ..RpcSendTransactionConfig::default()
+                },
+            )?;
+
+            match address == idl_address {
+                true => println!("Idl account closed: {:?}", address),
+                false => println!("Idl buffer closed: {:?}", address),
+            }
+        }
+
+        Ok(())
+    })
+}
+
 fn idl_authority(cfg_override: &ConfigOverride, program_id: Pubkey) -> Result<()> {
     with_workspace(cfg_override, |cfg| {
         let url = cluster_url(cfg);
@@ -1610,7 +1934,7 @@ fn idl_set_authority(
             data,
         };
         // Send transaction.
-        let (recent_hash, _fee_calc) = client.get_recent_blockhash()?;
+        let recent_hash = client.get_latest_blockhash()?;
         let tx = Transaction::new_signed_with_payer(
             &[ix],
             Some(&keypair.pubkey()),
@@ -1695,7 +2019,7 @@ fn idl_write(cfg: &Config, program_id: &Pubkey, idl: &Idl, idl_address: Pubkey)
             data,
         };
         // Send transaction.
-        let (recent_hash, _fee_calc) = client.get_recent_blockhash()?;
+        let recent_hash = client.get_latest_blockhash()?;
         let tx = Transaction::new_signed_with_payer(
             &[ix],
             Some(&keypair.pubkey()),
@@ -1715,14 +2039,9 @@ fn idl_write(cfg: &Config, program_id: &Pubkey, idl: &Idl, idl_address: Pubkey)
     Ok(())
 }
 
-fn idl_parse(
-    cfg_override: &ConfigOverride,
-    file: String,
-    out: Option<String>,
-    out_ts: Option<String>,
-) -> Result<()> {
-    let cfg = Config::discover(cfg_override)?.expect("Not in workspace.");
-    let idl = extract_idl(&cfg, &file, true)?.ok_or_else(|| anyhow!("IDL not parsed"))?;
+fn idl_parse(file: String, out: Option<String>, out_ts: Option<String>) -> Result<()> {
+    let idl = extract_idl(&file, true)?.ok_or_else(|| anyhow!("IDL not parsed"))?;
+    validate_idl(&serde_json::to_value(&idl)?)?;
     let out = match out {
         None => OutFile::Stdout,
         Some(out) => OutFile::File(PathBuf::from(out)),
@@ -1746,6 +2065,66 @@ fn idl_fetch(cfg_override: &ConfigOverride, address: Pubkey, out: Option<String>
     write_idl(&idl, out)
 }
 
+fn idl_convert(file: String, out: Option<String>) -> Result<()> {
+    let bytes = fs::read(&file).with_context(|| format!("Failed to read {}", file))?;
+    let idl = spec::upgrade(serde_json::from_slice(&bytes)?)?;
+    validate_idl(&idl)?;
+    let idl: Idl = serde_json::from_value(idl)?;
+    let out = match out {
+        None => OutFile::Stdout,
+        Some(out) => OutFile::File(PathBuf::from(out)),
+    };
+    write_idl(&idl, out)
+}
+
+fn idl_schema(out: Option<String>) -> Result<()> {
+    let schema = serde_json::to_string_pretty(&spec::schema())?;
+    match out {
+        None => println!("{}", schema),
+        Some(out) => fs::write(out, schema)?,
+    };
+
+    Ok(())
+}
+
+fn idl_diff(cfg_override: &ConfigOverride, old: String, new: Option<String>) -> Result<()> {
+    // IDLs of older specs are compared once upgraded.
+    let read_upgraded_idl = |file: &str| -> Result<Idl> {
+        let bytes = fs::read(file).with_context(|| format!("Failed to read {}", file))?;
+        let idl = spec::upgrade(serde_json::from_slice(&bytes)?)?;
+        serde_json::from_value(idl).map_err(Into::into)
+    };
+    let old = match old.parse::<Pubkey>() {
+        Ok(address) if !Path::new(&old).exists() => fetch_idl(cfg_override, address)?,
+        _ => read_upgraded_idl(&old)?,
+    };
+    let new = match new {
+        Some(new) => read_upgraded_idl(&new)?,
+        None => build_idl(true)?.ok_or_else(|| anyhow!("IDL not built"))?,
+    };
+
+    let changes = anchor_syn::idl::diff::diff(&old, &new);
+    if changes.is_empty() {
+        println!("No changes");
+        return Ok(());
+    }
+    let (breaking, compatible): (Vec<_>, Vec<_>) =
+        changes.iter().partition(|change| change.is_breaking());
+    for (title, changes) in [("Breaking", &breaking), ("Compatible", &compatible)] {
+        if !changes.is_empty() {
+            println!("{} changes:", title);
+            for change in changes {
+                println!("  {}", change);
+            }
+        }
+    }
+    if !breaking.is_empty() {
+        return Err(anyhow!("Found {} breaking change(s)", breaking.len()));
+    }
+
+    Ok(())
+}
+
 fn write_idl(idl: &Idl, out: OutFile) -> Result<()> {
     let idl_json = serde_json::to_string_pretty(idl)?;
     match out {
@@ -2070,7 +2449,7 @@ fn start_test_validator(
         .and_then(|test| test.startup_wait)
         .unwrap_or(5_000);
     while count < ms_wait {
-        let r = client.get_recent_blockhash();
+        let r = client.get_latest_blockhash();
         if r.is_ok() {
             break;
         }
@@ -2271,7 +2650,7 @@ fn create_idl_account(
             accounts,
             data,
         };
-        let (recent_hash, _fee_calc) = client.get_recent_blockhash()?;
+        let recent_hash = client.get_latest_blockhash()?;
         let tx = Transaction::new_signed_with_payer(
             &[ix],
             Some(&keypair.pubkey()),
@@ -2309,7 +2688,7 @@ fn create_idl_buffer(
 
     // Creates the new buffer account with the system program.
     let create_account_ix = {
-        let space = 8 + 32 + 4 + serialize_idl(idl)?.len() as usize;
+        let space = IdlAccount::space(serialize_idl(idl)?.len());
         let lamports = client.get_minimum_balance_for_rent_exemption(space)?;
         solana_sdk::system_instruction::create_account(
             &keypair.pubkey(),
@@ -2337,7 +2716,7 @@ fn create_idl_buffer(
     };
 
     // Build the transaction.
-    let (recent_hash, _fee_calc) = client.get_recent_blockhash()?;
+    let recent_hash = client.get_latest_blockhash()?;
     let tx = Transaction::new_signed_with_payer(
         &[create_account_ix, create_buffer_ix],
         Some(&keypair.pubkey()),
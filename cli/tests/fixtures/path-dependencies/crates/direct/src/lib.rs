@@ -0,0 +1,6 @@
+use anchor_lang::prelude::*;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct Data {
+    pub amount: transitive::Amount,
+}
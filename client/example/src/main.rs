@@ -3,7 +3,7 @@ use anchor_client::solana_sdk::pubkey::Pubkey;
 use anchor_client::solana_sdk::signature::read_keypair_file;
 use anchor_client::solana_sdk::signature::{Keypair, Signer};
 use anchor_client::solana_sdk::system_instruction;
-use anchor_client::{Client, Cluster, EventContext};
+use anchor_client::{Client, ClientError, Cluster, EventContext};
 use anyhow::Result;
 use solana_sdk::system_program;
 // The `accounts` and `instructions` modules are generated by the framework.
@@ -171,7 +171,7 @@ fn events(client: &Client, pid: Pubkey) -> Result<()> {
     let program = client.program(pid);
 
     let (sender, receiver) = std::sync::mpsc::channel();
-    let handle = program.on(move |_ctx: &EventContext, event: MyEvent| {
+    let handle = program.on(move |event: Result<(EventContext, MyEvent), ClientError>| {
         sender.send(event).unwrap();
     })?;
 
@@ -182,17 +182,11 @@ fn events(client: &Client, pid: Pubkey) -> Result<()> {
         .args(events_instruction::Initialize {})
         .send()?;
 
-    let event = receiver.recv().unwrap();
+    let (_ctx, event) = receiver.recv().unwrap()?;
     assert_eq!(event.data, 5);
     assert_eq!(event.label, "hello".to_string());
 
-    // TODO: remove once https://github.com/solana-labs/solana/issues/16102
-    //       is addressed. Until then, drop the subscription handle in another
-    //       thread so that we deadlock in the other thread as to not block
-    //       this thread.
-    std::thread::spawn(move || {
-        drop(handle);
-    });
+    handle.unsubscribe();
 
     println!("Events success!");
 
@@ -0,0 +1,258 @@
+//! Sending the instructions of a request in as few transactions as fit, for
+//! requests too large for a single transaction.
+
+use crate::compute_budget::ComputeBudget;
+use crate::ClientError;
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::pubkey::Pubkey;
+use solana_client::rpc_client::RpcClient;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::hash::Hash;
+use solana_sdk::message::Message;
+use solana_sdk::packet::PACKET_DATA_SIZE;
+use solana_sdk::signature::{Signature, Signer, SignerError};
+use solana_sdk::transaction::Transaction;
+use std::ops::Range;
+
+// Maximum number of transactions in flight when sending in parallel.
+pub(crate) const MAX_PARALLEL_TRANSACTIONS: usize = 16;
+
+/// How the transactions of a batched request are sent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BatchMode {
+    /// One after another, each confirmed before the next is sent, stopping at
+    /// the first failure, for instructions depending on the previous ones.
+    Sequential,
+    /// Several at a time, for independent instructions. Every transaction is
+    /// sent, even if others fail.
+    Parallel,
+}
+
+/// The result of one of the transactions of a batched request.
+#[derive(Debug)]
+pub struct BatchResult {
+    /// Indices of the request's instructions sent in the transaction, not
+    /// counting the compute budget instructions each transaction starts with.
+    pub instructions: Range<usize>,
+    pub result: Result<Signature, ClientError>,
+}
+
+/// Splits instructions, in order, into consecutive ranges that each fit in a
+/// single transaction paid by `payer`.
+pub fn pack_instructions(
+    instructions: &[Instruction],
+    payer: &Pubkey,
+) -> Result<Vec<Range<usize>>, ClientError> {
+    pack_after(&[], instructions, payer)
+}
+
+// Packs instructions into transactions that each start with `prefix`.
+pub(crate) fn pack_after(
+    prefix: &[Instruction],
+    instructions: &[Instruction],
+    payer: &Pubkey,
+) -> Result<Vec<Range<usize>>, ClientError> {
+    let fits = |batch: &[Instruction]| {
+        transaction_size(&[prefix, batch].concat(), payer) <= PACKET_DATA_SIZE
+    };
+    let mut batches = Vec::new();
+    let mut start = 0;
+    for end in 1..=instructions.len() {
+        if fits(&instructions[start..end]) {
+            continue;
+        }
+        if end - start == 1 {
+            return Err(ClientError::InstructionTooLarge(start));
+        }
+        batches.push(start..end - 1);
+        start = end - 1;
+        if !fits(&instructions[start..end]) {
+            return Err(ClientError::InstructionTooLarge(start));
+        }
+    }
+    if start < instructions.len() {
+        batches.push(start..instructions.len());
+    }
+    Ok(batches)
+}
+
+// Size of the serialized transaction of the instructions, once signed.
+fn transaction_size(instructions: &[Instruction], payer: &Pubkey) -> usize {
+    let message = Message::new(instructions, Some(payer));
+    let signatures = message.header.num_required_signatures as usize;
+    short_vec_len(signatures) + signatures * 64 + message.serialize().len()
+}
+
+// Size of the compact encoding of a length, 7 bits per byte.
+fn short_vec_len(mut len: usize) -> usize {
+    let mut size = 1;
+    while len >= 0x80 {
+        len >>= 7;
+        size += 1;
+    }
+    size
+}
+
+// The signers among `signers` required by the message, without duplicates.
+fn required_signers<'s>(
+    message: &Message,
+    signers: &[&'s dyn Signer],
+) -> Result<Vec<&'s dyn Signer>, ClientError> {
+    let num_required = message.header.num_required_signatures as usize;
+    message.account_keys[..num_required]
+        .iter()
+        .map(|key| {
+            signers
+                .iter()
+                .find(|signer| signer.pubkey() == *key)
+                .copied()
+                .ok_or_else(|| SignerError::NotEnoughSigners.into())
+        })
+        .collect()
+}
+
+/// Checks that every instruction can be signed before anything is sent.
+pub(crate) fn check_signers(
+    instructions: &[Instruction],
+    payer: &Pubkey,
+    signers: &[&dyn Signer],
+) -> Result<(), ClientError> {
+    required_signers(&Message::new(instructions, Some(payer)), signers).map(|_| ())
+}
+
+/// Signs the transaction of the instructions, by the signers it requires.
+pub(crate) fn sign_transaction(
+    instructions: &[Instruction],
+    payer: &Pubkey,
+    signers: &[&dyn Signer],
+    recent_hash: Hash,
+) -> Result<Transaction, ClientError> {
+    let message = Message::new(instructions, Some(payer));
+    let signers = required_signers(&message, signers)?;
+    let mut tx = Transaction::new_unsigned(message);
+    tx.try_sign(&signers, recent_hash)?;
+    Ok(tx)
+}
+
+// Sends the instructions in as few transactions as fit, each starting with
+// the instructions of the compute budget. `signers` include the payer, last.
+pub(crate) fn send_batched(
+    cluster: &str,
+    options: CommitmentConfig,
+    program_id: Pubkey,
+    compute_budget: &ComputeBudget,
+    instructions: &[Instruction],
+    signers: &[&dyn Signer],
+    mode: BatchMode,
+) -> Result<Vec<BatchResult>, ClientError> {
+    let payer = &signers[signers.len() - 1].pubkey();
+    let batches = pack_after(&compute_budget.instructions(None), instructions, payer)?;
+    check_signers(instructions, payer, signers)?;
+
+    let rpc_client = RpcClient::new_with_commitment(cluster.to_string(), options);
+    let sign = |batch: &Range<usize>| -> Result<Transaction, ClientError> {
+        let instructions =
+            compute_budget.prepend(&rpc_client, program_id, payer, &instructions[batch.clone()])?;
+        let recent_hash = rpc_client.get_latest_blockhash()?;
+        sign_transaction(&instructions, payer, signers, recent_hash)
+    };
+
+    let mut results = Vec::with_capacity(batches.len());
+    match mode {
+        BatchMode::Sequential => {
+            for batch in batches {
+                let result = sign(&batch).and_then(|tx| {
+                    rpc_client
+                        .send_and_confirm_transaction(&tx)
+                        .map_err(Into::into)
+                });
+                let failed = result.is_err();
+                results.push(BatchResult {
+                    instructions: batch,
+                    result,
+                });
+                if failed {
+                    break;
+                }
+            }
+        }
+        BatchMode::Parallel => {
+            for wave in batches.chunks(MAX_PARALLEL_TRANSACTIONS) {
+                let handles: Vec<_> = wave
+                    .iter()
+                    .map(|batch| {
+                        let tx = sign(batch);
+                        let cluster = cluster.to_string();
+                        std::thread::spawn(move || {
+                            let rpc_client = RpcClient::new_with_commitment(cluster, options);
+                            rpc_client
+                                .send_and_confirm_transaction(&tx?)
+                                .map_err(Into::into)
+                        })
+                    })
+                    .collect();
+                for (batch, handle) in wave.iter().zip(handles) {
+                    let result = match handle.join() {
+                        Ok(result) => result,
+                        Err(err) => std::panic::resume_unwind(err),
+                    };
+                    results.push(BatchResult {
+                        instructions: batch.clone(),
+                        result,
+                    });
+                }
+            }
+        }
+    }
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::solana_program::instruction::AccountMeta;
+    use solana_sdk::signature::Keypair;
+
+    fn instruction(size: usize, signer: Option<Pubkey>) -> Instruction {
+        let mut accounts = vec![AccountMeta::new(Pubkey::new_unique(), false)];
+        if let Some(signer) = signer {
+            accounts.push(AccountMeta::new_readonly(signer, true));
+        }
+        Instruction::new_with_bytes(Pubkey::default(), &vec![0; size], accounts)
+    }
+
+    #[test]
+    fn pack_instructions_into_transactions() {
+        let payer = Pubkey::new_unique();
+        let instructions: Vec<Instruction> = (0..10).map(|_| instruction(300, None)).collect();
+        let batches = pack_instructions(&instructions, &payer).unwrap();
+        assert_eq!(batches, vec![0..3, 3..6, 6..9, 9..10]);
+        for batch in batches {
+            assert!(transaction_size(&instructions[batch], &payer) <= PACKET_DATA_SIZE);
+        }
+
+        assert!(pack_instructions(&[], &payer).unwrap().is_empty());
+        let too_large = vec![instruction(10, None), instruction(PACKET_DATA_SIZE, None)];
+        assert!(matches!(
+            pack_instructions(&too_large, &payer),
+            Err(ClientError::InstructionTooLarge(1))
+        ));
+    }
+
+    #[test]
+    fn sign_with_required_signers() {
+        let payer = Keypair::new();
+        let (signer, other) = (Keypair::new(), Keypair::new());
+        let signers: Vec<&dyn Signer> = vec![&signer, &other, &payer];
+
+        // Signers not required by the instructions don't sign.
+        let instructions = vec![instruction(10, Some(signer.pubkey()))];
+        let tx =
+            sign_transaction(&instructions, &payer.pubkey(), &signers, Hash::default()).unwrap();
+        assert_eq!(tx.signatures.len(), 2);
+        assert!(tx.verify().is_ok());
+
+        let instructions = vec![instruction(10, Some(Pubkey::new_unique()))];
+        assert!(check_signers(&instructions, &payer.pubkey(), &signers).is_err());
+    }
+}
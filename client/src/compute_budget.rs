@@ -0,0 +1,184 @@
+//! Instructions of the compute budget program, to request more compute units
+//! or a larger heap for a transaction, or to pay a priority fee.
+//!
+//! They're built by hand so that they're available with any version of the
+//! Solana SDK.
+
+use crate::{ClientError, Simulation, SolanaClientError};
+use anchor_lang::solana_program::instruction::Instruction;
+use anchor_lang::solana_program::pubkey::Pubkey;
+use solana_client::rpc_client::RpcClient;
+use solana_client::rpc_response::RpcSimulateTransactionResult;
+use solana_sdk::hash::Hash;
+use solana_sdk::message::Message;
+use solana_sdk::transaction::Transaction;
+
+solana_sdk::declare_id!("ComputeBudget111111111111111111111111111111");
+
+/// Maximum number of compute units a transaction can request.
+pub const MAX_COMPUTE_UNIT_LIMIT: u32 = 1_400_000;
+
+// Tags of the compute budget instructions.
+const REQUEST_HEAP_FRAME: u8 = 1;
+const SET_COMPUTE_UNIT_LIMIT: u8 = 2;
+const SET_COMPUTE_UNIT_PRICE: u8 = 3;
+
+/// Requests a heap of `bytes`, a multiple of 1024, for each program invoked by
+/// the transaction.
+pub fn request_heap_frame(bytes: u32) -> Instruction {
+    instruction(REQUEST_HEAP_FRAME, &bytes.to_le_bytes())
+}
+
+/// Sets the maximum number of compute units the transaction can consume.
+pub fn set_compute_unit_limit(units: u32) -> Instruction {
+    instruction(SET_COMPUTE_UNIT_LIMIT, &units.to_le_bytes())
+}
+
+/// Sets the price of a compute unit, in micro-lamports, paid as a priority
+/// fee on top of the base fee of the transaction.
+pub fn set_compute_unit_price(micro_lamports: u64) -> Instruction {
+    instruction(SET_COMPUTE_UNIT_PRICE, &micro_lamports.to_le_bytes())
+}
+
+fn instruction(tag: u8, args: &[u8]) -> Instruction {
+    Instruction::new_with_bytes(ID, &[&[tag], args].concat(), Vec::new())
+}
+
+// The compute budget requested by a `RequestBuilder`.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct ComputeBudget {
+    pub unit_limit: Option<u32>,
+    pub unit_price: Option<u64>,
+    pub heap_frame: Option<u32>,
+    // Margin, in percent of the units consumed by a simulation, of the limit
+    // set automatically.
+    pub auto_margin: Option<u32>,
+}
+
+impl ComputeBudget {
+    // The instructions to prepend to a transaction. The limit set
+    // automatically is the maximum until resolved by `prepend`.
+    pub fn instructions(&self, auto_unit_limit: Option<u32>) -> Vec<Instruction> {
+        let unit_limit = match self.auto_margin {
+            Some(_) => Some(auto_unit_limit.unwrap_or(MAX_COMPUTE_UNIT_LIMIT)),
+            None => self.unit_limit,
+        };
+        let mut instructions = Vec::new();
+        if let Some(bytes) = self.heap_frame {
+            instructions.push(request_heap_frame(bytes));
+        }
+        if let Some(units) = unit_limit {
+            instructions.push(set_compute_unit_limit(units));
+        }
+        if let Some(micro_lamports) = self.unit_price {
+            instructions.push(set_compute_unit_price(micro_lamports));
+        }
+        instructions
+    }
+
+    // Prepends the instructions of the compute budget to `instructions`,
+    // resolving the limit of the automatic mode by simulating them, unsigned,
+    // and adding the margin to the units they consumed.
+    pub fn prepend(
+        &self,
+        rpc_client: &RpcClient,
+        program_id: Pubkey,
+        payer: &Pubkey,
+        instructions: &[Instruction],
+    ) -> Result<Vec<Instruction>, ClientError> {
+        if self.auto_margin.is_none() {
+            return Ok([self.instructions(None), instructions.to_vec()].concat());
+        }
+        let recent_hash = rpc_client.get_latest_blockhash()?;
+        let tx = self.simulation_transaction(payer, instructions, recent_hash);
+        let result = rpc_client.simulate_transaction(&tx)?.value;
+        self.resolve(program_id, instructions, result)
+    }
+
+    // The unsigned transaction simulated to resolve the limit of the
+    // automatic mode.
+    pub fn simulation_transaction(
+        &self,
+        payer: &Pubkey,
+        instructions: &[Instruction],
+        recent_hash: Hash,
+    ) -> Transaction {
+        let budgeted = [self.instructions(None), instructions.to_vec()].concat();
+        let mut tx = Transaction::new_unsigned(Message::new(&budgeted, Some(payer)));
+        tx.message.recent_blockhash = recent_hash;
+        tx
+    }
+
+    // Prepends the instructions of the compute budget to `instructions`, with
+    // the limit of the automatic mode set from the result of simulating the
+    // `simulation_transaction`.
+    pub fn resolve(
+        &self,
+        program_id: Pubkey,
+        instructions: &[Instruction],
+        result: RpcSimulateTransactionResult,
+    ) -> Result<Vec<Instruction>, ClientError> {
+        let simulation = Simulation::new(program_id, result);
+        if let Some(err) = simulation.err {
+            return Err(SolanaClientError::from(err).into());
+        }
+        let unit_limit = match (self.auto_margin, simulation.units_consumed) {
+            (Some(margin), Some(units)) => Some(with_margin(units, margin)),
+            _ => None,
+        };
+        Ok([self.instructions(unit_limit), instructions.to_vec()].concat())
+    }
+}
+
+fn with_margin(units: u64, margin: u32) -> u32 {
+    let units = units.saturating_mul(100 + margin as u64) / 100;
+    units.min(MAX_COMPUTE_UNIT_LIMIT as u64) as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compute_budget_instructions() {
+        let budget = ComputeBudget {
+            unit_limit: Some(300_000),
+            unit_price: Some(10),
+            heap_frame: Some(64 * 1024),
+            auto_margin: None,
+        };
+        let instructions = budget.instructions(None);
+        assert!(instructions.iter().all(|ix| ix.program_id == ID));
+        assert!(instructions.iter().all(|ix| ix.accounts.is_empty()));
+        let data: Vec<_> = instructions.into_iter().map(|ix| ix.data).collect();
+        assert_eq!(
+            data,
+            vec![
+                vec![1, 0, 0, 1, 0],
+                vec![2, 0xe0, 0x93, 4, 0],
+                vec![3, 10, 0, 0, 0, 0, 0, 0, 0],
+            ]
+        );
+
+        // The automatic limit overrides the given one.
+        let budget = ComputeBudget {
+            auto_margin: Some(10),
+            ..budget
+        };
+        assert_eq!(
+            budget.instructions(None)[1],
+            set_compute_unit_limit(MAX_COMPUTE_UNIT_LIMIT)
+        );
+        assert_eq!(
+            budget.instructions(Some(1000))[1],
+            set_compute_unit_limit(1000)
+        );
+        assert!(ComputeBudget::default().instructions(None).is_empty());
+    }
+
+    #[test]
+    fn auto_limit_margin() {
+        assert_eq!(with_margin(100_000, 10), 110_000);
+        assert_eq!(with_margin(1_300_000, 20), MAX_COMPUTE_UNIT_LIMIT);
+    }
+}
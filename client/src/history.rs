@@ -0,0 +1,263 @@
+//! Events and errors of the confirmed transactions of a program, to replay
+//! its history, e.g. to backfill an indexer.
+
+use crate::{parse_instruction_logs, AnchorErrorLog, ClientError, EventSet, ParsedEvent};
+use anchor_lang::solana_program::pubkey::Pubkey;
+use serde::Deserialize;
+use solana_client::rpc_client::{GetConfirmedSignaturesForAddress2Config, RpcClient};
+use solana_client::rpc_request::RpcRequest;
+use solana_client::rpc_response::RpcConfirmedTransactionStatusWithSignature;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::signature::Signature;
+use solana_sdk::transaction::TransactionError;
+use std::marker::PhantomData;
+use std::vec::IntoIter;
+
+// Maximum number of signatures returned by a `getSignaturesForAddress`
+// request.
+const SIGNATURES_PAGE_SIZE: usize = 1000;
+
+/// The events of type `T` a program emitted in a confirmed transaction, and
+/// the error the transaction failed with, if any.
+#[derive(Debug, Clone)]
+pub struct TransactionEvents<T> {
+    pub signature: Signature,
+    pub slot: u64,
+    pub events: Vec<ParsedEvent<T>>,
+    /// Error of the transaction, if it failed, in which case the effects of
+    /// its events were discarded.
+    pub err: Option<TransactionError>,
+    /// The last Anchor error logged, i.e. the one the transaction failed with.
+    pub error: Option<AnchorErrorLog>,
+}
+
+impl<T: EventSet> TransactionEvents<T> {
+    /// Parses the events of a program from the JSON result of a
+    /// `getTransaction` request.
+    pub fn from_transaction(
+        program_id: &Pubkey,
+        signature: Signature,
+        transaction: serde_json::Value,
+    ) -> Result<Self, ClientError> {
+        let transaction: RpcTransaction = serde_json::from_value(transaction)
+            .map_err(|e| ClientError::UnexpectedRpcResponse(e.to_string()))?;
+        let meta = transaction.meta.unwrap_or_default();
+        let logs = meta.log_messages.unwrap_or_default();
+        let mut events = vec![];
+        parse_instruction_logs(
+            &program_id.to_string(),
+            &logs,
+            transaction.transaction.instruction_programs().as_deref(),
+            &mut |e| events.push(e),
+        )?;
+        Ok(Self {
+            signature,
+            slot: transaction.slot,
+            events,
+            err: meta.err,
+            error: logs.iter().rev().find_map(|l| AnchorErrorLog::parse(l)),
+        })
+    }
+}
+
+// The fields of a `getTransaction` result needed to parse events.
+#[derive(Deserialize)]
+struct RpcTransaction {
+    slot: u64,
+    #[serde(default)]
+    transaction: RpcTransactionBody,
+    meta: Option<RpcTransactionMeta>,
+}
+
+#[derive(Default, Deserialize)]
+struct RpcTransactionBody {
+    message: Option<RpcMessage>,
+}
+
+impl RpcTransactionBody {
+    // Programs of the top level instructions, which index the instructions
+    // including those of precompiled programs, which don't log. Programs are
+    // never loaded from lookup tables, so they're all in the account keys.
+    fn instruction_programs(&self) -> Option<Vec<String>> {
+        let message = self.message.as_ref()?;
+        message
+            .instructions
+            .iter()
+            .map(|ix| message.account_keys.get(ix.program_id_index).cloned())
+            .collect()
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RpcMessage {
+    account_keys: Vec<String>,
+    instructions: Vec<RpcInstruction>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RpcInstruction {
+    program_id_index: usize,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RpcTransactionMeta {
+    err: Option<TransactionError>,
+    log_messages: Option<Vec<String>>,
+}
+
+pub(crate) fn get_transaction_events<T: EventSet>(
+    rpc_client: &RpcClient,
+    program_id: &Pubkey,
+    signature: &Signature,
+) -> Result<TransactionEvents<T>, ClientError> {
+    let transaction = rpc_client.send(
+        RpcRequest::GetTransaction,
+        get_transaction_params(rpc_client.commitment(), signature),
+    )?;
+    transaction_events(program_id, signature, transaction)
+}
+
+// Parameters of a `getTransaction` request, sent raw to get the logs of
+// transactions of any version.
+pub(crate) fn get_transaction_params(
+    commitment: CommitmentConfig,
+    signature: &Signature,
+) -> serde_json::Value {
+    let config = serde_json::json!({
+        "encoding": "json",
+        "commitment": history_commitment(commitment).commitment,
+        "maxSupportedTransactionVersion": 0,
+    });
+    serde_json::json!([signature.to_string(), config])
+}
+
+// Parses the events of a `getTransaction` result, null if not found.
+pub(crate) fn transaction_events<T: EventSet>(
+    program_id: &Pubkey,
+    signature: &Signature,
+    transaction: serde_json::Value,
+) -> Result<TransactionEvents<T>, ClientError> {
+    if transaction.is_null() {
+        return Err(ClientError::TransactionNotFound);
+    }
+    TransactionEvents::from_transaction(program_id, *signature, transaction)
+}
+
+// History is only available for confirmed transactions.
+fn history_commitment(commitment: CommitmentConfig) -> CommitmentConfig {
+    match commitment.is_at_least_confirmed() {
+        true => commitment,
+        false => CommitmentConfig::confirmed(),
+    }
+}
+
+/// Iterator over the signatures of the confirmed transactions mentioning an
+/// address, newest first, fetched a page at a time by
+/// `getSignaturesForAddress`.
+pub struct SignaturesIterator {
+    rpc_client: RpcClient,
+    address: Pubkey,
+    before: Option<Signature>,
+    until: Option<Signature>,
+    page: IntoIter<RpcConfirmedTransactionStatusWithSignature>,
+    done: bool,
+}
+
+impl SignaturesIterator {
+    pub(crate) fn new(
+        rpc_client: RpcClient,
+        address: Pubkey,
+        before: Option<Signature>,
+        until: Option<Signature>,
+    ) -> Self {
+        Self {
+            rpc_client,
+            address,
+            before,
+            until,
+            page: Vec::new().into_iter(),
+            done: false,
+        }
+    }
+}
+
+impl Iterator for SignaturesIterator {
+    type Item = Result<RpcConfirmedTransactionStatusWithSignature, ClientError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(status) = self.page.next() {
+            return Some(Ok(status));
+        }
+        if self.done {
+            return None;
+        }
+
+        let config = GetConfirmedSignaturesForAddress2Config {
+            before: self.before,
+            until: self.until,
+            limit: Some(SIGNATURES_PAGE_SIZE),
+            commitment: Some(history_commitment(self.rpc_client.commitment())),
+        };
+        let page = match self
+            .rpc_client
+            .get_signatures_for_address_with_config(&self.address, config)
+        {
+            Ok(page) => page,
+            Err(err) => {
+                self.done = true;
+                return Some(Err(err.into()));
+            }
+        };
+        self.done = page.len() < SIGNATURES_PAGE_SIZE;
+        match page.last().map(|status| status.signature.parse()) {
+            Some(Ok(signature)) => self.before = Some(signature),
+            _ => self.done = true,
+        }
+        self.page = page.into_iter();
+        self.page.next().map(Ok)
+    }
+}
+
+/// Iterator over the events of type `T` emitted by a program, a confirmed
+/// transaction at a time, newest first.
+pub struct EventHistory<T> {
+    signatures: SignaturesIterator,
+    event: PhantomData<fn() -> T>,
+}
+
+impl<T> EventHistory<T> {
+    pub(crate) fn new(signatures: SignaturesIterator) -> Self {
+        Self {
+            signatures,
+            event: PhantomData,
+        }
+    }
+}
+
+impl<T: EventSet> Iterator for EventHistory<T> {
+    type Item = Result<TransactionEvents<T>, ClientError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let status = match self.signatures.next()? {
+            Ok(status) => status,
+            Err(err) => return Some(Err(err)),
+        };
+        let signature = match status.signature.parse() {
+            Ok(signature) => signature,
+            Err(_) => {
+                return Some(Err(ClientError::UnexpectedRpcResponse(format!(
+                    "invalid signature {}",
+                    status.signature
+                ))))
+            }
+        };
+        Some(get_transaction_events(
+            &self.signatures.rpc_client,
+            &self.signatures.address,
+            &signature,
+        ))
+    }
+}
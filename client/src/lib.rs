@@ -1,42 +1,77 @@
 //! `anchor_client` provides an RPC client to send transactions and fetch
 //! deserialized accounts from Solana programs written in `anchor_lang`.
 
-use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use crate::compute_budget::ComputeBudget;
+use anchor_lang::error::{AnchorError, ErrorCode, FromErrorCode};
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction, InstructionError};
 use anchor_lang::solana_program::program_error::ProgramError;
 use anchor_lang::solana_program::pubkey::Pubkey;
 use anchor_lang::solana_program::system_program;
-use anchor_lang::{AccountDeserialize, Discriminator, InstructionData, ToAccountMetas};
+use anchor_lang::{
+    AccountDeserialize, AccountFilter, Discriminator, InstructionData, ToAccountMetas,
+};
 use regex::Regex;
-use solana_account_decoder::UiAccountEncoding;
+use solana_account_decoder::{UiAccount, UiAccountEncoding};
 use solana_client::client_error::ClientError as SolanaClientError;
-use solana_client::pubsub_client::{PubsubClient, PubsubClientError, PubsubClientSubscription};
+use solana_client::pubsub_client::{PubsubClient, PubsubClientError};
 use solana_client::rpc_client::RpcClient;
 use solana_client::rpc_config::{
     RpcAccountInfoConfig, RpcProgramAccountsConfig, RpcTransactionLogsConfig,
     RpcTransactionLogsFilter,
 };
-use solana_client::rpc_filter::{Memcmp, MemcmpEncodedBytes, RpcFilterType};
+use solana_client::rpc_filter::{Memcmp, RpcFilterType};
 use solana_client::rpc_response::{Response as RpcResponse, RpcLogsResponse};
 use solana_sdk::account::Account;
-use solana_sdk::bs58;
 use solana_sdk::commitment_config::CommitmentConfig;
-use solana_sdk::signature::{Signature, Signer};
-use solana_sdk::transaction::Transaction;
+use solana_sdk::signature::{Signature, Signer, SignerError};
+use solana_sdk::transaction::{Transaction, TransactionError};
 use std::convert::Into;
 use std::iter::Map;
 use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 use std::vec::IntoIter;
 use thiserror::Error;
 
 pub use anchor_lang;
+#[cfg(feature = "idl")]
+pub use anchor_syn::idl::Idl;
+pub use batch::{pack_instructions, BatchMode, BatchResult};
 pub use cluster::Cluster;
+pub use history::{EventHistory, SignaturesIterator, TransactionEvents};
+pub use simulation::{AnchorErrorLog, ErrorSource, Simulation};
 pub use solana_client;
 pub use solana_sdk;
 
+mod batch;
 mod cluster;
+pub mod compute_budget;
+mod history;
+#[cfg(feature = "async")]
+pub mod nonblocking;
+mod simulation;
+
+/// EventHandle unsubscribes from a stream of program events or account updates
+/// on drop, or by [`EventHandle::unsubscribe`].
+pub struct EventHandle {
+    stop: Arc<AtomicBool>,
+}
+
+impl EventHandle {
+    /// Ends the subscription. The websocket is closed by the subscription's
+    /// thread, so this doesn't block.
+    pub fn unsubscribe(self) {
+        drop(self)
+    }
+}
 
-/// EventHandle unsubscribes from a program event stream on drop.
-pub type EventHandle = PubsubClientSubscription<RpcResponse<RpcLogsResponse>>;
+impl Drop for EventHandle {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
 
 /// Client defines the base configuration for building RPC clients to
 /// communitcate with Anchor programs running on a Solana cluster. It's
@@ -126,16 +161,7 @@ impl Program {
 
     /// Returns the account at the given address.
     pub fn account<T: AccountDeserialize>(&self, address: Pubkey) -> Result<T, ClientError> {
-        let rpc_client = RpcClient::new_with_commitment(
-            self.cfg.cluster.url().to_string(),
-            self.cfg.options.unwrap_or_default(),
-        );
-        let account = rpc_client
-            .get_account_with_commitment(&address, CommitmentConfig::processed())?
-            .value
-            .ok_or(ClientError::AccountNotFound)?;
-        let mut data: &[u8] = &account.data;
-        T::try_deserialize(&mut data).map_err(Into::into)
+        get_account(&self.rpc(), &address)
     }
 
     /// Returns all program accounts of the given type matching the given filters
@@ -152,29 +178,28 @@ impl Program {
         &self,
         filters: Vec<RpcFilterType>,
     ) -> Result<ProgramAccountsIterator<T>, ClientError> {
-        let account_type_filter = RpcFilterType::Memcmp(Memcmp {
-            offset: 0,
-            bytes: MemcmpEncodedBytes::Base58(bs58::encode(T::discriminator()).into_string()),
-            encoding: None,
-        });
-        let config = RpcProgramAccountsConfig {
-            filters: Some([vec![account_type_filter], filters].concat()),
-            account_config: RpcAccountInfoConfig {
-                encoding: Some(UiAccountEncoding::Base64),
-                data_slice: None,
-                commitment: None,
-            },
-            with_context: None,
-        };
-        Ok(ProgramAccountsIterator {
-            inner: self
-                .rpc()
-                .get_program_accounts_with_config(&self.id(), config)?
-                .into_iter()
-                .map(|(key, account)| {
-                    Ok((key, T::try_deserialize(&mut (&account.data as &[u8]))?))
-                }),
-        })
+        get_program_accounts(&self.rpc(), &self.program_id, filters)
+    }
+
+    /// Returns the accounts of type `T` at the given addresses, in order,
+    /// with `None` for the addresses without an account. Accounts are fetched
+    /// with one `getMultipleAccounts` request per 100 addresses, so accounts
+    /// of different requests may have been read at different slots.
+    pub fn accounts_by_address<T: AccountDeserialize>(
+        &self,
+        addresses: &[Pubkey],
+    ) -> Result<Vec<Option<T>>, ClientError> {
+        get_accounts_by_address(&self.rpc(), addresses)
+    }
+
+    /// Returns the raw accounts at the given addresses, which may be of
+    /// different types, like [`Program::accounts_by_address`]. Accounts can be
+    /// decoded by discriminator with [`try_decode_account`].
+    pub fn raw_accounts_by_address(
+        &self,
+        addresses: &[Pubkey],
+    ) -> Result<Vec<Option<Account>>, ClientError> {
+        get_multiple_accounts(&self.rpc(), addresses)
     }
 
     pub fn state<T: AccountDeserialize>(&self) -> Result<T, ClientError> {
@@ -192,69 +217,567 @@ impl Program {
         self.program_id
     }
 
-    pub fn on<T: anchor_lang::Event + anchor_lang::AnchorDeserialize>(
+    /// Returns the events of type `T` emitted by the program in a confirmed
+    /// transaction.
+    pub fn transaction_events<T: EventSet>(
+        &self,
+        signature: &Signature,
+    ) -> Result<TransactionEvents<T>, ClientError> {
+        history::get_transaction_events(&self.rpc(), &self.program_id, signature)
+    }
+
+    /// Returns the signatures of the confirmed transactions of the program,
+    /// newest first, after `until` and before `before`, if given.
+    pub fn signatures(
+        &self,
+        before: Option<Signature>,
+        until: Option<Signature>,
+    ) -> SignaturesIterator {
+        SignaturesIterator::new(self.rpc(), self.program_id, before, until)
+    }
+
+    /// Returns the events of type `T` emitted by the program in its confirmed
+    /// transactions, newest first, after `until` and before `before`, if
+    /// given.
+    pub fn event_history<T: EventSet>(
+        &self,
+        before: Option<Signature>,
+        until: Option<Signature>,
+    ) -> EventHistory<T> {
+        EventHistory::new(self.signatures(before, until))
+    }
+
+    /// Subscribes to the events of type `T`, an `#[event]` or an
+    /// [`event_enum!`] of several, emitted by the program. `f` is called on a
+    /// separate thread with each event, or with the error of a log that can't
+    /// be parsed or of a failed attempt to reconnect. The subscription
+    /// reconnects with backoff when the websocket disconnects, and ends when
+    /// the returned handle is dropped.
+    pub fn on<T: EventSet + 'static>(
         &self,
-        f: impl Fn(&EventContext, T) + Send + 'static,
+        f: impl FnMut(Result<(EventContext, T), ClientError>) + Send + 'static,
     ) -> Result<EventHandle, ClientError> {
-        let addresses = vec![self.program_id.to_string()];
-        let filter = RpcTransactionLogsFilter::Mentions(addresses);
-        let ws_url = self.cfg.cluster.ws_url().to_string();
-        let cfg = RpcTransactionLogsConfig {
-            commitment: self.cfg.options,
-        };
-        let self_program_str = self.program_id.to_string();
-        let (client, receiver) = PubsubClient::logs_subscribe(&ws_url, filter, cfg)?;
-        std::thread::spawn(move || {
-            loop {
-                match receiver.recv() {
-                    Ok(logs) => {
-                        let ctx = EventContext {
-                            signature: logs.value.signature.parse().unwrap(),
-                            slot: logs.context.slot,
-                        };
-                        let mut logs = &logs.value.logs[..];
-                        if !logs.is_empty() {
-                            if let Ok(mut execution) = Execution::new(&mut logs) {
-                                for l in logs {
-                                    // Parse the log.
-                                    let (event, new_program, did_pop) = {
-                                        if self_program_str == execution.program() {
-                                            handle_program_log(&self_program_str, l).unwrap_or_else(
-                                                |e| {
-                                                    println!("Unable to parse log: {}", e);
-                                                    std::process::exit(1);
-                                                },
-                                            )
-                                        } else {
-                                            let (program, did_pop) =
-                                                handle_system_log(&self_program_str, l);
-                                            (None, program, did_pop)
-                                        }
-                                    };
-                                    // Emit the event.
-                                    if let Some(e) = event {
-                                        f(&ctx, e);
-                                    }
-                                    // Switch program context on CPI.
-                                    if let Some(new_program) = new_program {
-                                        execution.push(new_program);
-                                    }
-                                    // Program returned.
-                                    if did_pop {
-                                        execution.pop();
-                                    }
-                                }
-                            }
+        subscribe_events(
+            self.cfg.cluster.ws_url(),
+            self.program_id,
+            self.cfg.options,
+            f,
+        )
+    }
+
+    /// Subscribes to the changes of the account of type `T` at the given
+    /// address. `f` is called on a separate thread with each update, or with
+    /// the error of an account that can't be deserialized or of a failed
+    /// attempt to reconnect. The subscription ends when the returned handle is
+    /// dropped.
+    pub fn subscribe_account<T: AccountDeserialize + 'static>(
+        &self,
+        address: Pubkey,
+        f: impl FnMut(Result<AccountUpdate<T>, ClientError>) + Send + 'static,
+    ) -> Result<EventHandle, ClientError> {
+        subscribe_account(self.cfg.cluster.ws_url(), address, self.cfg.options, f)
+    }
+
+    /// Subscribes to the changes of all program accounts of type `T` matching
+    /// the given filters, like [`Program::subscribe_account`].
+    pub fn subscribe_program_accounts<T: AccountDeserialize + Discriminator + 'static>(
+        &self,
+        filters: Vec<RpcFilterType>,
+        f: impl FnMut(Result<AccountUpdate<T>, ClientError>) + Send + 'static,
+    ) -> Result<EventHandle, ClientError> {
+        subscribe_program_accounts(
+            self.cfg.cluster.ws_url(),
+            self.program_id,
+            self.cfg.options,
+            filters,
+            f,
+        )
+    }
+}
+
+/// An update of an account, along with the slot it was made at.
+#[derive(Debug, Clone)]
+pub struct AccountUpdate<T> {
+    pub address: Pubkey,
+    pub slot: u64,
+    pub account: T,
+}
+
+// How often the thread of a subscription checks whether it was unsubscribed.
+const EVENT_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+// Bounds of the delay before reconnecting a subscription, doubled on each
+// attempt until events are received again.
+const MIN_RECONNECT_DELAY: Duration = Duration::from_millis(500);
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(30);
+
+fn subscribe_events<T: EventSet + 'static>(
+    ws_url: &str,
+    program_id: Pubkey,
+    commitment: Option<CommitmentConfig>,
+    mut f: impl FnMut(Result<(EventContext, T), ClientError>) + Send + 'static,
+) -> Result<EventHandle, ClientError> {
+    let ws_url = ws_url.to_string();
+    let subscribe = move || {
+        let filter = RpcTransactionLogsFilter::Mentions(vec![program_id.to_string()]);
+        let cfg = RpcTransactionLogsConfig { commitment };
+        PubsubClient::logs_subscribe(&ws_url, filter, cfg)
+    };
+    let self_program_str = program_id.to_string();
+    spawn_subscription(subscribe, move |logs| {
+        let result = logs.and_then(|logs| {
+            handle_logs(&self_program_str, logs, &mut |ctx, e| {
+                f(Ok((ctx.clone(), e)))
+            })
+        });
+        if let Err(err) = result {
+            f(Err(err));
+        }
+    })
+}
+
+fn subscribe_account<T: AccountDeserialize + 'static>(
+    ws_url: &str,
+    address: Pubkey,
+    commitment: Option<CommitmentConfig>,
+    mut f: impl FnMut(Result<AccountUpdate<T>, ClientError>) + Send + 'static,
+) -> Result<EventHandle, ClientError> {
+    let ws_url = ws_url.to_string();
+    let config = RpcAccountInfoConfig {
+        encoding: Some(UiAccountEncoding::Base64),
+        data_slice: None,
+        commitment,
+        min_context_slot: None,
+    };
+    let subscribe =
+        move || PubsubClient::account_subscribe(&ws_url, &address, Some(config.clone()));
+    spawn_subscription(subscribe, move |update| {
+        f(update.and_then(|update| {
+            Ok(AccountUpdate {
+                address,
+                slot: update.context.slot,
+                account: decode_account(&update.value)?,
+            })
+        }))
+    })
+}
+
+fn subscribe_program_accounts<T: AccountDeserialize + Discriminator + 'static>(
+    ws_url: &str,
+    program_id: Pubkey,
+    commitment: Option<CommitmentConfig>,
+    filters: Vec<RpcFilterType>,
+    mut f: impl FnMut(Result<AccountUpdate<T>, ClientError>) + Send + 'static,
+) -> Result<EventHandle, ClientError> {
+    let ws_url = ws_url.to_string();
+    let config = program_accounts_config::<T>(filters, commitment);
+    let subscribe =
+        move || PubsubClient::program_subscribe(&ws_url, &program_id, Some(config.clone()));
+    spawn_subscription(subscribe, move |update| {
+        f(update.and_then(|update| {
+            Ok(AccountUpdate {
+                address: update
+                    .value
+                    .pubkey
+                    .parse()
+                    .map_err(|_| ProgramError::InvalidAccountData)?,
+                slot: update.context.slot,
+                account: decode_account(&update.value.account)?,
+            })
+        }))
+    })
+}
+
+fn decode_account<T: AccountDeserialize>(account: &UiAccount) -> Result<T, ClientError> {
+    let account: Account = account.decode().ok_or(ProgramError::InvalidAccountData)?;
+    T::try_deserialize(&mut (&account.data as &[u8])).map_err(Into::into)
+}
+
+// Calls `f` on a separate thread with the messages of the subscription made
+// by `subscribe`, resubscribing with backoff when the websocket disconnects,
+// until the returned handle is dropped.
+fn spawn_subscription<S, R, M>(
+    subscribe: impl Fn() -> Result<(S, R), PubsubClientError> + Send + 'static,
+    mut f: impl FnMut(Result<M, ClientError>) + Send + 'static,
+) -> Result<EventHandle, ClientError>
+where
+    S: Send + 'static,
+    R: IntoIterator<Item = M> + Send + 'static,
+    M: Send + 'static,
+{
+    let mut subscription = Some(subscribe()?);
+    let stop = Arc::new(AtomicBool::new(false));
+    let handle = EventHandle { stop: stop.clone() };
+
+    std::thread::spawn(move || {
+        let mut delay = MIN_RECONNECT_DELAY;
+        while !stop.load(Ordering::Relaxed) {
+            // Unsubscribes when dropped, on reconnecting or returning.
+            let (_subscription, messages) = match subscription.take() {
+                Some(subscription) => subscription,
+                None => {
+                    // Unsubscribed while waiting to reconnect.
+                    if !sleep_unless_stopped(&stop, delay) {
+                        break;
+                    }
+                    delay = (delay * 2).min(MAX_RECONNECT_DELAY);
+                    match subscribe() {
+                        Ok(subscription) => subscription,
+                        Err(err) => {
+                            f(Err(err.into()));
+                            continue;
                         }
                     }
-                    Err(_err) => {
-                        return;
+                }
+            };
+            let receiver = forward(messages);
+            while !stop.load(Ordering::Relaxed) {
+                let message = match receiver.recv_timeout(EVENT_POLL_INTERVAL) {
+                    Ok(message) => message,
+                    Err(RecvTimeoutError::Timeout) => continue,
+                    // The websocket disconnected.
+                    Err(RecvTimeoutError::Disconnected) => break,
+                };
+                delay = MIN_RECONNECT_DELAY;
+                f(Ok(message));
+            }
+        }
+    });
+    Ok(handle)
+}
+
+// Forwards the messages of a subscription to a channel that can be received
+// from with a timeout, whichever channel the pubsub client uses.
+fn forward<M: Send + 'static>(
+    messages: impl IntoIterator<Item = M> + Send + 'static,
+) -> mpsc::Receiver<M> {
+    let (sender, receiver) = mpsc::channel();
+    std::thread::spawn(move || {
+        for message in messages {
+            if sender.send(message).is_err() {
+                return;
+            }
+        }
+    });
+    receiver
+}
+
+// Sleeps for the given duration, returning early once `stop` is set. Returns
+// whether the whole duration passed without `stop` being set.
+fn sleep_unless_stopped(stop: &AtomicBool, duration: Duration) -> bool {
+    let until = Instant::now() + duration;
+    while !stop.load(Ordering::Relaxed) {
+        let now = Instant::now();
+        if now >= until {
+            return true;
+        }
+        std::thread::sleep((until - now).min(EVENT_POLL_INTERVAL));
+    }
+    false
+}
+
+/// Events decoded from the logs of a program: an `#[event]`, or an enum of
+/// several declared by [`event_enum!`], dispatched by discriminator.
+pub trait EventSet: Sized {
+    /// Decodes the data of a log, i.e. a discriminator followed by the event,
+    /// if it's an event of the set.
+    fn decode(data: &[u8]) -> Result<Option<Self>, ClientError>;
+}
+
+impl<T: anchor_lang::Event> EventSet for T {
+    fn decode(data: &[u8]) -> Result<Option<Self>, ClientError> {
+        if !data.starts_with(T::DISCRIMINATOR) {
+            return Ok(None);
+        }
+        let mut slice: &[u8] = &data[T::DISCRIMINATOR.len()..];
+        T::deserialize(&mut slice)
+            .map(Some)
+            .map_err(|e| ClientError::LogParseError(e.to_string()))
+    }
+}
+
+/// Declares an enum of events, with a variant wrapping each `#[event]`, to
+/// subscribe to several events at once.
+///
+/// # Example
+///
+/// ```ignore
+/// anchor_client::event_enum! {
+///     #[derive(Debug)]
+///     pub enum VaultEvent {
+///         Deposit(DepositEvent),
+///         Withdraw(WithdrawEvent),
+///     }
+/// }
+///
+/// let handle = program.on(|event: Result<(EventContext, VaultEvent), ClientError>| {
+///     println!("{:?}", event);
+/// })?;
+/// ```
+#[macro_export]
+macro_rules! event_enum {
+    (
+        $(#[$attr:meta])*
+        $vis:vis enum $name:ident {
+            $($variant:ident($event:ty)),* $(,)?
+        }
+    ) => {
+        $(#[$attr])*
+        $vis enum $name {
+            $($variant($event)),*
+        }
+
+        impl $crate::EventSet for $name {
+            fn decode(data: &[u8]) -> Result<Option<Self>, $crate::ClientError> {
+                $(
+                    if let Some(event) = <$event as $crate::EventSet>::decode(data)? {
+                        return Ok(Some($name::$variant(event)));
+                    }
+                )*
+                Ok(None)
+            }
+        }
+    };
+}
+
+fn get_account<T: AccountDeserialize>(
+    rpc_client: &RpcClient,
+    address: &Pubkey,
+) -> Result<T, ClientError> {
+    let account = rpc_client
+        .get_account_with_commitment(address, CommitmentConfig::processed())?
+        .value
+        .ok_or(ClientError::AccountNotFound)?;
+    let mut data: &[u8] = &account.data;
+    T::try_deserialize(&mut data).map_err(Into::into)
+}
+
+// Maximum number of accounts returned by a `getMultipleAccounts` request.
+const MULTIPLE_ACCOUNTS_CHUNK_SIZE: usize = 100;
+
+fn get_multiple_accounts(
+    rpc_client: &RpcClient,
+    addresses: &[Pubkey],
+) -> Result<Vec<Option<Account>>, ClientError> {
+    get_chunked(addresses, |chunk| {
+        rpc_client
+            .get_multiple_accounts(chunk)
+            .map_err(ClientError::from)
+    })
+}
+
+// Fetches the accounts at the given addresses in chunks of at most
+// `MULTIPLE_ACCOUNTS_CHUNK_SIZE`, in order.
+fn get_chunked(
+    addresses: &[Pubkey],
+    mut get: impl FnMut(&[Pubkey]) -> Result<Vec<Option<Account>>, ClientError>,
+) -> Result<Vec<Option<Account>>, ClientError> {
+    let mut accounts = Vec::with_capacity(addresses.len());
+    for chunk in addresses.chunks(MULTIPLE_ACCOUNTS_CHUNK_SIZE) {
+        let mut chunk_accounts = get(chunk)?;
+        check_chunk(chunk, &chunk_accounts)?;
+        accounts.append(&mut chunk_accounts);
+    }
+    Ok(accounts)
+}
+
+// Checks that a `getMultipleAccounts` response has an entry per address.
+fn check_chunk(chunk: &[Pubkey], accounts: &[Option<Account>]) -> Result<(), ClientError> {
+    match accounts.len() == chunk.len() {
+        true => Ok(()),
+        false => Err(ClientError::UnexpectedRpcResponse(format!(
+            "expected {} accounts, got {}",
+            chunk.len(),
+            accounts.len()
+        ))),
+    }
+}
+
+fn get_accounts_by_address<T: AccountDeserialize>(
+    rpc_client: &RpcClient,
+    addresses: &[Pubkey],
+) -> Result<Vec<Option<T>>, ClientError> {
+    decode_accounts(get_multiple_accounts(rpc_client, addresses)?)
+}
+
+fn decode_accounts<T: AccountDeserialize>(
+    accounts: Vec<Option<Account>>,
+) -> Result<Vec<Option<T>>, ClientError> {
+    accounts
+        .into_iter()
+        .map(|account| {
+            account
+                .map(|account| T::try_deserialize(&mut (&account.data as &[u8])))
+                .transpose()
+                .map_err(Into::into)
+        })
+        .collect()
+}
+
+/// Deserializes account data of type `T`, returning `None` if it starts with
+/// the discriminator of another type, e.g. to sort out the accounts returned
+/// by [`Program::raw_accounts_by_address`].
+pub fn try_decode_account<T: AccountDeserialize + Discriminator>(
+    data: &[u8],
+) -> Result<Option<T>, ClientError> {
+    if !data.starts_with(T::DISCRIMINATOR) {
+        return Ok(None);
+    }
+    let mut data = data;
+    T::try_deserialize(&mut data).map(Some).map_err(Into::into)
+}
+
+fn get_program_accounts<T: AccountDeserialize + Discriminator>(
+    rpc_client: &RpcClient,
+    program_id: &Pubkey,
+    filters: Vec<RpcFilterType>,
+) -> Result<ProgramAccountsIterator<T>, ClientError> {
+    let config = program_accounts_config::<T>(filters, None);
+    Ok(decode_program_accounts(
+        rpc_client.get_program_accounts_with_config(program_id, config)?,
+    ))
+}
+
+fn decode_program_accounts<T: AccountDeserialize>(
+    accounts: Vec<(Pubkey, Account)>,
+) -> ProgramAccountsIterator<T> {
+    ProgramAccountsIterator {
+        inner: accounts
+            .into_iter()
+            .map(|(key, account)| Ok((key, T::try_deserialize(&mut (&account.data as &[u8]))?))),
+    }
+}
+
+// Calls `f` with the events of type `T` emitted by the program in the logs of
+// a transaction received from a logs subscription.
+fn handle_logs<T: EventSet>(
+    self_program_str: &str,
+    logs: RpcResponse<RpcLogsResponse>,
+    f: &mut impl FnMut(&EventContext, T),
+) -> Result<(), ClientError> {
+    let ctx = EventContext {
+        signature: logs.value.signature.parse().unwrap(),
+        slot: logs.context.slot,
+    };
+    parse_logs(self_program_str, &logs.value.logs, &mut |e| {
+        f(&ctx, e.event)
+    })
+}
+
+/// An event emitted by a program, along with where it was emitted in the
+/// transaction.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParsedEvent<T> {
+    pub event: T,
+    /// Index of the top level instruction emitting the event.
+    pub instruction_index: usize,
+    /// Depth of the invocation of the program emitting the event, i.e. 1 when
+    /// invoked by the instruction and greater for CPIs.
+    pub depth: usize,
+}
+
+/// Parses the events of type `T` emitted by a program in the logs of a
+/// transaction, skipping those of other programs it invokes.
+///
+/// Instructions are indexed by their invocations in the logs, which
+/// precompiled programs, e.g. the Ed25519 program, don't log.
+/// [`TransactionEvents`] indexes them by the transaction's instructions
+/// instead.
+pub fn parse_events<T: EventSet>(
+    program_id: &Pubkey,
+    logs: &[String],
+) -> Result<Vec<ParsedEvent<T>>, ClientError> {
+    let mut events = vec![];
+    parse_logs(&program_id.to_string(), logs, &mut |e| events.push(e))?;
+    Ok(events)
+}
+
+// Calls `f` with the events of type `T` emitted by the program in the logs of
+// a transaction, skipping those of other programs it invokes.
+fn parse_logs<T: EventSet>(
+    self_program_str: &str,
+    logs: &[String],
+    f: &mut impl FnMut(ParsedEvent<T>),
+) -> Result<(), ClientError> {
+    parse_instruction_logs(self_program_str, logs, None, f)
+}
+
+// As `parse_logs`, given the programs of the top level instructions of the
+// transaction, if known, to index the instructions.
+pub(crate) fn parse_instruction_logs<T: EventSet>(
+    self_program_str: &str,
+    logs: &[String],
+    instruction_programs: Option<&[String]>,
+    f: &mut impl FnMut(ParsedEvent<T>),
+) -> Result<(), ClientError> {
+    let mut logs = logs;
+    if !logs.is_empty() {
+        if let Ok(mut execution) = Execution::new(&mut logs, instruction_programs) {
+            for l in logs {
+                // Parse the log.
+                let (event, new_program, did_pop) = {
+                    if execution.program() == Some(self_program_str) {
+                        handle_program_log(self_program_str, l)?
+                    } else {
+                        let (program, did_pop) = handle_system_log(self_program_str, l);
+                        (None, program, did_pop)
                     }
+                };
+                // Emit the event.
+                if let Some(event) = event {
+                    f(ParsedEvent {
+                        event,
+                        instruction_index: execution.instruction_index,
+                        depth: execution.stack.len(),
+                    });
+                }
+                // Switch program context on CPI.
+                if let Some(new_program) = new_program {
+                    execution.push(new_program);
+                }
+                // Program returned.
+                if did_pop {
+                    execution.pop();
                 }
             }
-        });
-        Ok(client)
+        }
     }
+    Ok(())
+}
+
+// Selects the accounts of type `T`, by discriminator, matching the filters.
+fn program_accounts_config<T: Discriminator>(
+    filters: Vec<RpcFilterType>,
+    commitment: Option<CommitmentConfig>,
+) -> RpcProgramAccountsConfig {
+    let account_type_filter =
+        RpcFilterType::Memcmp(Memcmp::new_base58_encoded(0, T::DISCRIMINATOR));
+    RpcProgramAccountsConfig {
+        filters: Some([vec![account_type_filter], filters].concat()),
+        account_config: RpcAccountInfoConfig {
+            encoding: Some(UiAccountEncoding::Base64),
+            data_slice: None,
+            commitment,
+            min_context_slot: None,
+        },
+        with_context: None,
+    }
+}
+
+/// Converts filters built from the layout of an account, e.g. by
+/// `Vault::filter().authority_eq(authority)`, into RPC filters, to be given
+/// to [`Program::accounts`].
+pub fn rpc_filters(filters: impl Into<Vec<AccountFilter>>) -> Vec<RpcFilterType> {
+    filters
+        .into()
+        .into_iter()
+        .map(|filter| match filter {
+            AccountFilter::Memcmp { offset, bytes } => {
+                RpcFilterType::Memcmp(Memcmp::new_base58_encoded(offset, &bytes))
+            }
+            AccountFilter::DataSize(size) => RpcFilterType::DataSize(size),
+        })
+        .collect()
 }
 
 /// Iterator with items of type (Pubkey, T). Used to lazily deserialize account structs.
@@ -274,7 +797,7 @@ impl<T> Iterator for ProgramAccountsIterator<T> {
     }
 }
 
-fn handle_program_log<T: anchor_lang::Event + anchor_lang::AnchorDeserialize>(
+fn handle_program_log<T: EventSet>(
     self_program_str: &str,
     l: &str,
 ) -> Result<(Option<T>, Option<String>, bool), ClientError> {
@@ -290,20 +813,7 @@ fn handle_program_log<T: anchor_lang::Event + anchor_lang::AnchorDeserialize>(
             }
         };
 
-        let mut slice: &[u8] = &borsh_bytes[..];
-        let disc: [u8; 8] = {
-            let mut disc = [0; 8];
-            disc.copy_from_slice(&borsh_bytes[..8]);
-            slice = &slice[8..];
-            disc
-        };
-        let mut event = None;
-        if disc == T::discriminator() {
-            let e: T = anchor_lang::AnchorDeserialize::deserialize(&mut slice)
-                .map_err(|e| ClientError::LogParseError(e.to_string()))?;
-            event = Some(e);
-        }
-        Ok((event, None, false))
+        Ok((T::decode(&borsh_bytes)?, None, false))
     }
     // System log.
     else {
@@ -315,24 +825,31 @@ fn handle_program_log<T: anchor_lang::Event + anchor_lang::AnchorDeserialize>(
 fn handle_system_log(this_program_str: &str, log: &str) -> (Option<String>, bool) {
     if log.starts_with(&format!("Program {} log:", this_program_str)) {
         (Some(this_program_str.to_string()), false)
-    } else if log.contains("invoke") {
-        (Some("cpi".to_string()), false) // Any string will do.
+    } else if let Some(c) = Regex::new(r"^Program (\S+) invoke \[\d+\]$")
+        .unwrap()
+        .captures(log)
+    {
+        (Some(c[1].to_string()), false)
     } else {
-        let re = Regex::new(r"^Program (.*) success*$").unwrap();
-        if re.is_match(log) {
-            (None, true)
-        } else {
-            (None, false)
-        }
+        let re = Regex::new(r"^Program \S+ (success|failed: .*)$").unwrap();
+        (None, re.is_match(log))
     }
 }
 
-struct Execution {
+struct Execution<'a> {
     stack: Vec<String>,
+    // Index of the top level instruction being executed.
+    instruction_index: usize,
+    // Programs of the top level instructions, if known. Otherwise every top
+    // level invocation is taken to be the next instruction.
+    instruction_programs: Option<&'a [String]>,
 }
 
-impl Execution {
-    pub fn new(logs: &mut &[String]) -> Result<Self, ClientError> {
+impl<'a> Execution<'a> {
+    pub fn new(
+        logs: &mut &[String],
+        instruction_programs: Option<&'a [String]>,
+    ) -> Result<Self, ClientError> {
         let l = &logs[0];
         *logs = &logs[1..];
 
@@ -346,26 +863,50 @@ impl Execution {
             .as_str()
             .to_string();
         Ok(Self {
+            instruction_index: Self::find_instruction(instruction_programs, 0, &program),
             stack: vec![program],
+            instruction_programs,
         })
     }
 
-    pub fn program(&self) -> String {
-        assert!(!self.stack.is_empty());
-        self.stack[self.stack.len() - 1].clone()
+    // Index of the first instruction from `start` invoking `program`.
+    // Instructions of precompiled programs are skipped that way, since they
+    // don't log their invocation.
+    fn find_instruction(programs: Option<&[String]>, start: usize, program: &str) -> usize {
+        programs
+            .and_then(|programs| {
+                programs
+                    .iter()
+                    .skip(start)
+                    .position(|p| p == program)
+                    .map(|idx| start + idx)
+            })
+            .unwrap_or(start)
+    }
+
+    // The program emitting the logs, if any, i.e. none between top level
+    // instructions.
+    pub fn program(&self) -> Option<&str> {
+        self.stack.last().map(String::as_str)
     }
 
     pub fn push(&mut self, new_program: String) {
+        if self.stack.is_empty() {
+            self.instruction_index = Self::find_instruction(
+                self.instruction_programs,
+                self.instruction_index + 1,
+                &new_program,
+            );
+        }
         self.stack.push(new_program);
     }
 
     pub fn pop(&mut self) {
-        assert!(!self.stack.is_empty());
-        self.stack.pop().unwrap();
+        self.stack.pop();
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct EventContext {
     pub signature: Signature,
     pub slot: u64,
@@ -375,16 +916,133 @@ pub struct EventContext {
 pub enum ClientError {
     #[error("Account not found")]
     AccountNotFound,
+    #[error("Transaction not found")]
+    TransactionNotFound,
     #[error("{0}")]
     AnchorError(#[from] anchor_lang::error::Error),
     #[error("{0}")]
     ProgramError(#[from] ProgramError),
     #[error("{0}")]
-    SolanaClientError(#[from] SolanaClientError),
+    CustomError(CustomError),
+    #[error("{0}")]
+    SolanaClientError(SolanaClientError),
     #[error("{0}")]
     SolanaClientPubsubError(#[from] PubsubClientError),
     #[error("Unable to parse log: {0}")]
     LogParseError(String),
+    #[error("Unexpected RPC response: {0}")]
+    UnexpectedRpcResponse(String),
+    #[error("{0}")]
+    SignerError(#[from] SignerError),
+    #[error("Instruction {0} doesn't fit in a transaction")]
+    InstructionTooLarge(usize),
+    #[error("Subscription cancelled before it connected")]
+    SubscriptionCancelled,
+}
+
+impl ClientError {
+    /// Decodes the code of a custom program error as a variant of the
+    /// `#[error_code]` enum `E`, for errors of the program defining it.
+    pub fn decode<E>(self) -> Self
+    where
+        E: FromErrorCode + Into<anchor_lang::error::Error>,
+    {
+        match self {
+            ClientError::CustomError(mut err) => {
+                if let Some(error_code) = E::from_error_code(err.code) {
+                    err.error = match error_code.into() {
+                        anchor_lang::error::Error::AnchorError(error) => Some(error),
+                        anchor_lang::error::Error::ProgramError(_) => None,
+                    };
+                }
+                ClientError::CustomError(err)
+            }
+            err => err,
+        }
+    }
+
+    /// Decodes the code of a custom program error using the `errors` of the
+    /// program's IDL. Requires the `idl` feature.
+    #[cfg(feature = "idl")]
+    pub fn decode_with_idl(self, idl: &Idl) -> Self {
+        match self {
+            ClientError::CustomError(mut err) => {
+                let mut errors = idl.errors.iter().flatten();
+                if let Some(error_code) = errors.find(|e| e.code == err.code) {
+                    err.error = Some(AnchorError {
+                        error_name: error_code.name.clone(),
+                        error_code_number: error_code.code,
+                        error_msg: error_code
+                            .msg
+                            .clone()
+                            .unwrap_or_else(|| error_code.name.clone()),
+                        source: None,
+                        account_name: None,
+                    });
+                }
+                ClientError::CustomError(err)
+            }
+            err => err,
+        }
+    }
+}
+
+impl From<SolanaClientError> for ClientError {
+    fn from(err: SolanaClientError) -> Self {
+        match err.kind().get_transaction_error() {
+            Some(TransactionError::InstructionError(
+                instruction_index,
+                InstructionError::Custom(code),
+            )) => ClientError::CustomError(CustomError::new(instruction_index, code, err)),
+            _ => ClientError::SolanaClientError(err),
+        }
+    }
+}
+
+/// The custom program error, i.e. `InstructionError::Custom`, an instruction
+/// of a transaction failed with.
+#[derive(Debug)]
+pub struct CustomError {
+    /// Index of the failed instruction in the transaction.
+    pub instruction_index: u8,
+    pub code: u32,
+    /// The error the code stands for. Set for the errors of the framework, and
+    /// for those of the program once decoded by [`ClientError::decode`] or,
+    /// with the `idl` feature, `ClientError::decode_with_idl`.
+    pub error: Option<AnchorError>,
+    pub rpc_error: SolanaClientError,
+}
+
+impl CustomError {
+    fn new(instruction_index: u8, code: u32, rpc_error: SolanaClientError) -> Self {
+        let error = match ErrorCode::from_error_code(code).map(Into::into) {
+            Some(anchor_lang::error::Error::AnchorError(error)) => Some(error),
+            _ => None,
+        };
+        Self {
+            instruction_index,
+            code,
+            error,
+            rpc_error,
+        }
+    }
+}
+
+impl std::fmt::Display for CustomError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.error {
+            Some(error) => write!(
+                f,
+                "Instruction {} failed: {} ({}): {}",
+                self.instruction_index, error.error_name, self.code, error.error_msg
+            ),
+            None => write!(
+                f,
+                "Instruction {} failed: custom program error: {:#x}",
+                self.instruction_index, self.code
+            ),
+        }
+    }
 }
 
 /// `RequestBuilder` provides a builder interface to create and send
@@ -401,6 +1059,7 @@ pub struct RequestBuilder<'a> {
     signers: Vec<&'a dyn Signer>,
     // True if the user is sending a state instruction.
     namespace: RequestNamespace,
+    compute_budget: ComputeBudget,
 }
 
 #[derive(PartialEq)]
@@ -431,6 +1090,7 @@ impl<'a> RequestBuilder<'a> {
             instruction_data: None,
             signers: Vec::new(),
             namespace,
+            compute_budget: ComputeBudget::default(),
         }
     }
 
@@ -493,64 +1153,155 @@ impl<'a> RequestBuilder<'a> {
         self
     }
 
+    /// Sets the maximum number of compute units the transaction can consume.
+    #[must_use]
+    pub fn compute_unit_limit(mut self, units: u32) -> Self {
+        self.compute_budget.unit_limit = Some(units);
+        self
+    }
+
+    /// Sets the price of a compute unit, in micro-lamports, paid as a
+    /// priority fee.
+    #[must_use]
+    pub fn compute_unit_price(mut self, micro_lamports: u64) -> Self {
+        self.compute_budget.unit_price = Some(micro_lamports);
+        self
+    }
+
+    /// Requests a heap of `bytes`, a multiple of 1024, for the programs
+    /// invoked.
+    #[must_use]
+    pub fn heap_frame_size(mut self, bytes: u32) -> Self {
+        self.compute_budget.heap_frame = Some(bytes);
+        self
+    }
+
+    /// Sets the compute unit limit when sending to the units consumed by a
+    /// simulation of the transaction, plus `margin_percent` percent. Until
+    /// then, e.g. when simulating, the limit is the maximum.
+    #[must_use]
+    pub fn auto_compute_unit_limit(mut self, margin_percent: u32) -> Self {
+        self.compute_budget.auto_margin = Some(margin_percent);
+        self
+    }
+
+    /// Returns the instructions of the request, preceded by the compute
+    /// budget instructions, if any.
     pub fn instructions(&self) -> Result<Vec<Instruction>, ClientError> {
-        let mut accounts = match self.namespace {
-            RequestNamespace::State { new } => match new {
-                false => vec![AccountMeta::new(
-                    anchor_lang::__private::state::address(&self.program_id),
-                    false,
-                )],
-                true => vec![
-                    AccountMeta::new_readonly(self.payer.pubkey(), true),
-                    AccountMeta::new(
-                        anchor_lang::__private::state::address(&self.program_id),
-                        false,
-                    ),
-                    AccountMeta::new_readonly(
-                        Pubkey::find_program_address(&[], &self.program_id).0,
-                        false,
-                    ),
-                    AccountMeta::new_readonly(system_program::ID, false),
-                    AccountMeta::new_readonly(self.program_id, false),
-                ],
-            },
-            _ => Vec::new(),
-        };
-        accounts.extend_from_slice(&self.accounts);
+        Ok([
+            self.compute_budget.instructions(None),
+            self.request_instructions()?,
+        ]
+        .concat())
+    }
 
+    fn request_instructions(&self) -> Result<Vec<Instruction>, ClientError> {
         let mut instructions = self.instructions.clone();
         if let Some(ix_data) = &self.instruction_data {
-            instructions.push(Instruction {
-                program_id: self.program_id,
-                data: ix_data.clone(),
-                accounts,
-            });
+            instructions.push(request_instruction(
+                self.program_id,
+                &self.namespace,
+                self.payer.pubkey(),
+                &self.accounts,
+                ix_data.clone(),
+            ));
         }
-
         Ok(instructions)
     }
 
     pub fn send(self) -> Result<Signature, ClientError> {
-        let instructions = self.instructions()?;
+        let rpc_client = RpcClient::new_with_commitment(self.cluster.clone(), self.options);
+        let instructions = self.compute_budget.prepend(
+            &rpc_client,
+            self.program_id,
+            &self.payer.pubkey(),
+            &self.request_instructions()?,
+        )?;
+        let tx = self.transaction(&rpc_client, &instructions)?;
 
-        let mut signers = self.signers;
+        rpc_client
+            .send_and_confirm_transaction(&tx)
+            .map_err(Into::into)
+    }
+
+    /// Sends the instructions of the request in as few transactions as fit,
+    /// in order, returning the result of each transaction sent. Every
+    /// transaction is signed by the payer and by those of the request's
+    /// signers its instructions require.
+    pub fn send_batched(self, mode: BatchMode) -> Result<Vec<BatchResult>, ClientError> {
+        let instructions = self.request_instructions()?;
+
+        let mut signers = self.signers.clone();
         signers.push(&*self.payer);
 
-        let rpc_client = RpcClient::new_with_commitment(self.cluster, self.options);
+        batch::send_batched(
+            &self.cluster,
+            self.options,
+            self.program_id,
+            &self.compute_budget,
+            &instructions,
+            &signers,
+            mode,
+        )
+    }
 
-        let tx = {
-            let (recent_hash, _fee_calc) = rpc_client.get_recent_blockhash()?;
-            Transaction::new_signed_with_payer(
-                &instructions,
-                Some(&self.payer.pubkey()),
-                &signers,
-                recent_hash,
-            )
-        };
+    /// Simulates the transaction of the request, without sending it.
+    pub fn simulate(&self) -> Result<Simulation, ClientError> {
+        let rpc_client = RpcClient::new_with_commitment(self.cluster.clone(), self.options);
+        let tx = self.transaction(&rpc_client, &self.instructions()?)?;
 
-        rpc_client
-            .send_and_confirm_transaction(&tx)
-            .map_err(Into::into)
+        let result = rpc_client.simulate_transaction(&tx)?.value;
+        Ok(Simulation::new(self.program_id, result))
+    }
+
+    fn transaction(
+        &self,
+        rpc_client: &RpcClient,
+        instructions: &[Instruction],
+    ) -> Result<Transaction, ClientError> {
+        let mut signers = self.signers.clone();
+        signers.push(&*self.payer);
+
+        let recent_hash = rpc_client.get_latest_blockhash()?;
+        Ok(Transaction::new_signed_with_payer(
+            instructions,
+            Some(&self.payer.pubkey()),
+            &signers,
+            recent_hash,
+        ))
+    }
+}
+
+// The instruction of a request, given its data and the accounts added by
+// `RequestBuilder::accounts`, prepending the accounts of state instructions.
+fn request_instruction(
+    program_id: Pubkey,
+    namespace: &RequestNamespace,
+    payer: Pubkey,
+    request_accounts: &[AccountMeta],
+    data: Vec<u8>,
+) -> Instruction {
+    let mut accounts = match namespace {
+        RequestNamespace::State { new } => match new {
+            false => vec![AccountMeta::new(
+                anchor_lang::__private::state::address(&program_id),
+                false,
+            )],
+            true => vec![
+                AccountMeta::new_readonly(payer, true),
+                AccountMeta::new(anchor_lang::__private::state::address(&program_id), false),
+                AccountMeta::new_readonly(Pubkey::find_program_address(&[], &program_id).0, false),
+                AccountMeta::new_readonly(system_program::ID, false),
+                AccountMeta::new_readonly(program_id, false),
+            ],
+        },
+        _ => Vec::new(),
+    };
+    accounts.extend_from_slice(request_accounts);
+    Instruction {
+        program_id,
+        data,
+        accounts,
     }
 }
 
@@ -561,7 +1312,7 @@ mod tests {
     fn new_execution() {
         let mut logs: &[String] =
             &["Program 7Y8VDzehoewALqJfyxZYMgYCnMTCDhWuGfJKUvjYWATw invoke [1]".to_string()];
-        let exe = Execution::new(&mut logs).unwrap();
+        let exe = Execution::new(&mut logs, None).unwrap();
         assert_eq!(
             exe.stack[0],
             "7Y8VDzehoewALqJfyxZYMgYCnMTCDhWuGfJKUvjYWATw".to_string()
@@ -583,4 +1334,356 @@ mod tests {
         assert_eq!(program, None);
         assert!(!did_pop);
     }
+
+    mod errors {
+        use anchor_lang::prelude::*;
+
+        #[error_code]
+        pub enum MyError {
+            #[msg("This is an error message")]
+            Hello,
+            World,
+        }
+    }
+
+    fn custom_error(instruction_index: u8, code: u32) -> ClientError {
+        ClientError::from(SolanaClientError::from(TransactionError::InstructionError(
+            instruction_index,
+            InstructionError::Custom(code),
+        )))
+    }
+
+    fn decoded(err: ClientError) -> (u8, u32, Option<String>, Option<String>) {
+        match err {
+            ClientError::CustomError(err) => (
+                err.instruction_index,
+                err.code,
+                err.error.as_ref().map(|e| e.error_name.clone()),
+                err.error.as_ref().map(|e| e.error_msg.clone()),
+            ),
+            err => panic!("not a custom error: {}", err),
+        }
+    }
+
+    #[test]
+    fn decode_custom_errors() {
+        assert_eq!(
+            decoded(custom_error(2, 2000)),
+            (
+                2,
+                2000,
+                Some("ConstraintMut".into()),
+                Some("A mut constraint was violated".into())
+            )
+        );
+        assert_eq!(decoded(custom_error(0, 6000)), (0, 6000, None, None));
+        assert_eq!(
+            decoded(custom_error(0, 6000).decode::<errors::MyError>()),
+            (
+                0,
+                6000,
+                Some("Hello".into()),
+                Some("This is an error message".into())
+            )
+        );
+        assert_eq!(
+            decoded(custom_error(0, 6002).decode::<errors::MyError>()),
+            (0, 6002, None, None)
+        );
+
+        assert!(matches!(
+            ClientError::from(SolanaClientError::from(TransactionError::AccountNotFound)),
+            ClientError::SolanaClientError(_)
+        ));
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct Ping(u8);
+
+    #[derive(Debug, PartialEq)]
+    struct Pong(u8);
+
+    macro_rules! impl_event {
+        ($name:ident, $discriminator:expr) => {
+            impl anchor_lang::AnchorSerialize for $name {
+                fn serialize<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+                    anchor_lang::AnchorSerialize::serialize(&self.0, writer)
+                }
+            }
+
+            impl anchor_lang::AnchorDeserialize for $name {
+                fn deserialize(buf: &mut &[u8]) -> std::io::Result<Self> {
+                    anchor_lang::AnchorDeserialize::deserialize(buf).map($name)
+                }
+            }
+
+            impl Discriminator for $name {
+                const DISCRIMINATOR: &'static [u8] = &$discriminator;
+            }
+
+            impl anchor_lang::Event for $name {
+                fn data(&self) -> Vec<u8> {
+                    let mut data = Self::DISCRIMINATOR.to_vec();
+                    data.push(self.0);
+                    data
+                }
+            }
+        };
+    }
+
+    impl_event!(Ping, [1, 1]);
+    impl_event!(Pong, [2, 2]);
+
+    event_enum! {
+        #[derive(Debug, PartialEq)]
+        enum PingPong {
+            Ping(Ping),
+            Pong(Pong),
+        }
+    }
+
+    #[cfg(feature = "idl")]
+    #[test]
+    fn decode_custom_errors_with_idl() {
+        let idl: Idl = serde_json::from_value(serde_json::json!({
+            "version": "0.1.0",
+            "name": "errors",
+            "instructions": [],
+            "errors": [
+                { "code": 6000, "name": "Hello", "msg": "This is an error message" },
+                { "code": 6001, "name": "World" }
+            ]
+        }))
+        .unwrap();
+        assert_eq!(
+            decoded(custom_error(1, 6001).decode_with_idl(&idl)),
+            (1, 6001, Some("World".into()), Some("World".into()))
+        );
+    }
+
+    #[test]
+    fn parse_events_across_instructions() {
+        fn log(event: &impl anchor_lang::Event) -> String {
+            let data = anchor_lang::__private::base64::encode(event.data());
+            format!("Program log: {}", data)
+        }
+        let logs = vec![
+            "Program Other invoke [1]".to_string(),
+            "Program Other success".to_string(),
+            "Program Self invoke [1]".to_string(),
+            log(&Ping(1)),
+            "Program Other invoke [2]".to_string(),
+            log(&Ping(2)),
+            "Program Other success".to_string(),
+            "Program Self invoke [2]".to_string(),
+            log(&Ping(5)),
+            "Program Self success".to_string(),
+            log(&Pong(3)),
+            "Program Self success".to_string(),
+            "Program Self invoke [1]".to_string(),
+            log(&Pong(4)),
+            "Program Self failed: custom program error: 0x1770".to_string(),
+        ];
+        let mut events = vec![];
+        parse_logs("Self", &logs, &mut |e: ParsedEvent<PingPong>| {
+            events.push((e.event, e.instruction_index, e.depth))
+        })
+        .unwrap();
+        assert_eq!(
+            events,
+            vec![
+                (PingPong::Ping(Ping(1)), 1, 1),
+                (PingPong::Ping(Ping(5)), 1, 2),
+                (PingPong::Pong(Pong(3)), 1, 1),
+                (PingPong::Pong(Pong(4)), 2, 1)
+            ]
+        );
+
+        let mut pings = vec![];
+        parse_logs("Self", &logs, &mut |e: ParsedEvent<Ping>| {
+            pings.push(e.event)
+        })
+        .unwrap();
+        assert_eq!(pings, vec![Ping(1), Ping(5)]);
+    }
+
+    #[test]
+    fn parse_transaction_events() {
+        let program_id = Pubkey::new_unique();
+        let data = anchor_lang::__private::base64::encode(anchor_lang::Event::data(&Ping(7)));
+        let transaction = serde_json::json!({
+            "slot": 42,
+            "blockTime": null,
+            "transaction": {},
+            "meta": {
+                "err": { "InstructionError": [0, { "Custom": 6000 }] },
+                "logMessages": [
+                    format!("Program {} invoke [1]", program_id),
+                    format!("Program log: {}", data),
+                    "Program log: AnchorError occurred. Error Code: Hello. Error Number: 6000. \
+                     Error Message: This is an error message.",
+                    format!("Program {} failed: custom program error: 0x1770", program_id),
+                ]
+            }
+        });
+        let signature = Signature::default();
+        let events =
+            TransactionEvents::<Ping>::from_transaction(&program_id, signature, transaction)
+                .unwrap();
+        assert_eq!(events.slot, 42);
+        assert_eq!(
+            events.events,
+            vec![ParsedEvent {
+                event: Ping(7),
+                instruction_index: 0,
+                depth: 1
+            }]
+        );
+        assert_eq!(
+            events.err,
+            Some(TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(6000)
+            ))
+        );
+        assert_eq!(events.error.unwrap().error_code_number, 6000);
+    }
+
+    #[test]
+    fn index_events_by_transaction_instructions() {
+        let program_id = Pubkey::new_unique();
+        let data = anchor_lang::__private::base64::encode(anchor_lang::Event::data(&Ping(7)));
+        // The Ed25519 program is precompiled, so it doesn't log.
+        let ed25519_program = "Ed25519SigVerify111111111111111111111111111";
+        let transaction = serde_json::json!({
+            "slot": 42,
+            "transaction": {
+                "message": {
+                    "accountKeys": [Pubkey::new_unique().to_string(), ed25519_program, program_id.to_string()],
+                    "instructions": [
+                        { "programIdIndex": 1, "accounts": [], "data": "" },
+                        { "programIdIndex": 2, "accounts": [], "data": "" },
+                        { "programIdIndex": 1, "accounts": [], "data": "" },
+                        { "programIdIndex": 2, "accounts": [], "data": "" },
+                    ],
+                },
+            },
+            "meta": {
+                "err": null,
+                "logMessages": [
+                    format!("Program {} invoke [1]", program_id),
+                    format!("Program log: {}", data),
+                    format!("Program {} success", program_id),
+                    format!("Program {} invoke [1]", program_id),
+                    format!("Program log: {}", data),
+                    format!("Program {} success", program_id),
+                ]
+            }
+        });
+        let events = TransactionEvents::<Ping>::from_transaction(
+            &program_id,
+            Signature::default(),
+            transaction,
+        )
+        .unwrap();
+        let indices: Vec<usize> = events.events.iter().map(|e| e.instruction_index).collect();
+        assert_eq!(indices, vec![1, 3]);
+    }
+
+    #[test]
+    fn get_accounts_in_chunks() {
+        let addresses: Vec<Pubkey> = (0..250).map(|_| Pubkey::new_unique()).collect();
+        let mut chunks = Vec::new();
+        let accounts = get_chunked(&addresses, |chunk| {
+            chunks.push(chunk.len());
+            // Every other address has an account, with the address as data.
+            Ok(chunk
+                .iter()
+                .map(|address| {
+                    let has_account = addresses.iter().position(|a| a == address).unwrap() % 2 == 0;
+                    has_account.then(|| Account {
+                        data: address.to_bytes().to_vec(),
+                        ..Account::default()
+                    })
+                })
+                .collect())
+        })
+        .unwrap();
+        assert_eq!(chunks, vec![100, 100, 50]);
+        assert_eq!(accounts.len(), 250);
+        for (i, (address, account)) in addresses.iter().zip(&accounts).enumerate() {
+            match account {
+                Some(account) => assert_eq!(account.data, address.to_bytes()),
+                None => assert_eq!(i % 2, 1),
+            }
+        }
+
+        assert!(matches!(
+            get_chunked(&addresses, |_| Ok(vec![None])),
+            Err(ClientError::UnexpectedRpcResponse(_))
+        ));
+    }
+
+    struct Counter(u8);
+
+    impl Discriminator for Counter {
+        const DISCRIMINATOR: &'static [u8] = &[1, 2];
+    }
+
+    impl AccountDeserialize for Counter {
+        fn try_deserialize_unchecked(buf: &mut &[u8]) -> anchor_lang::Result<Self> {
+            buf.get(2)
+                .map(|count| Counter(*count))
+                .ok_or_else(|| ErrorCode::AccountDidNotDeserialize.into())
+        }
+    }
+
+    #[test]
+    fn decode_accounts_by_discriminator() {
+        let counter = try_decode_account::<Counter>(&[1, 2, 7]).unwrap();
+        assert_eq!(counter.map(|c| c.0), Some(7));
+        assert!(try_decode_account::<Counter>(&[2, 1, 7]).unwrap().is_none());
+        assert!(try_decode_account::<Counter>(&[1, 2]).is_err());
+    }
+
+    #[test]
+    fn resubscribe_on_disconnect() {
+        let subscriptions = Arc::new(std::sync::atomic::AtomicU32::new(0));
+        let counter = subscriptions.clone();
+        let subscribe = move || {
+            // Each subscription yields a message and disconnects.
+            let n = counter.fetch_add(1, Ordering::Relaxed);
+            Ok(((), vec![n]))
+        };
+        let (sender, receiver) = mpsc::channel();
+        let handle = spawn_subscription(subscribe, move |message: Result<u32, ClientError>| {
+            sender.send(message.unwrap()).unwrap();
+        })
+        .unwrap();
+
+        let timeout = Duration::from_secs(10);
+        assert_eq!(receiver.recv_timeout(timeout).unwrap(), 0);
+        assert_eq!(receiver.recv_timeout(timeout).unwrap(), 1);
+        handle.unsubscribe();
+        assert!(receiver.recv_timeout(timeout).is_err());
+        assert!(subscriptions.load(Ordering::Relaxed) <= 3);
+    }
+
+    #[test]
+    fn no_resubscribe_after_unsubscribe() {
+        let subscriptions = Arc::new(std::sync::atomic::AtomicU32::new(0));
+        let counter = subscriptions.clone();
+        let subscribe = move || {
+            // Each subscription disconnects right away.
+            counter.fetch_add(1, Ordering::Relaxed);
+            Ok(((), Vec::<u32>::new()))
+        };
+        let handle = spawn_subscription(subscribe, |_| {}).unwrap();
+
+        // Unsubscribe while waiting to reconnect.
+        std::thread::sleep(MIN_RECONNECT_DELAY / 5);
+        handle.unsubscribe();
+        std::thread::sleep(MIN_RECONNECT_DELAY * 2);
+        assert_eq!(subscriptions.load(Ordering::Relaxed), 1);
+    }
 }
@@ -0,0 +1,613 @@
+//! Async versions of [`Client`](crate::Client), [`Program`](crate::Program)
+//! and [`RequestBuilder`](crate::RequestBuilder) for use with tokio, enabled
+//! by the `async` feature.
+//!
+//! They hold the payer as an `Arc<dyn Signer + Send + Sync>`, so that they're
+//! `Send + Sync` and their futures can be spawned onto a multi-threaded
+//! runtime. RPC requests are made by the nonblocking `RpcClient` of
+//! `solana_client`, shared by a [`Program`] and its requests, and events are
+//! delivered through a [`Stream`].
+
+use crate::batch::{check_signers, pack_after, sign_transaction, MAX_PARALLEL_TRANSACTIONS};
+use crate::compute_budget::ComputeBudget;
+use crate::history::{get_transaction_params, transaction_events};
+use crate::{
+    check_chunk, decode_accounts, decode_program_accounts, program_accounts_config,
+    request_instruction, subscribe_account, subscribe_events, subscribe_program_accounts,
+    AccountUpdate, BatchMode, BatchResult, ClientError, Cluster, EventContext, EventHandle,
+    EventSet, RequestNamespace, Simulation, TransactionEvents, MULTIPLE_ACCOUNTS_CHUNK_SIZE,
+};
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::pubkey::Pubkey;
+use anchor_lang::{AccountDeserialize, Discriminator, InstructionData, ToAccountMetas};
+use futures::Stream;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_filter::RpcFilterType;
+use solana_client::rpc_request::RpcRequest;
+use solana_sdk::account::Account;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::hash::Hash;
+use solana_sdk::signature::{Signature, Signer};
+use solana_sdk::transaction::Transaction;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use tokio::sync::mpsc;
+
+/// Async version of [`crate::Client`].
+pub struct Client {
+    cfg: Config,
+}
+
+impl Client {
+    pub fn new(cluster: Cluster, payer: Arc<dyn Signer + Send + Sync>) -> Self {
+        Self {
+            cfg: Config {
+                cluster,
+                payer,
+                options: None,
+            },
+        }
+    }
+
+    pub fn new_with_options(
+        cluster: Cluster,
+        payer: Arc<dyn Signer + Send + Sync>,
+        options: CommitmentConfig,
+    ) -> Self {
+        Self {
+            cfg: Config {
+                cluster,
+                payer,
+                options: Some(options),
+            },
+        }
+    }
+
+    /// Returns a client of the program, with an RPC client of its own.
+    pub fn program(&self, program_id: Pubkey) -> Program {
+        Program {
+            program_id,
+            cfg: self.cfg.clone(),
+            rpc: Arc::new(RpcClient::new_with_commitment(
+                self.cfg.cluster.url().to_string(),
+                self.cfg.options.unwrap_or_default(),
+            )),
+        }
+    }
+}
+
+// Internal configuration for a client.
+#[derive(Clone)]
+struct Config {
+    cluster: Cluster,
+    payer: Arc<dyn Signer + Send + Sync>,
+    options: Option<CommitmentConfig>,
+}
+
+/// Async version of [`crate::Program`].
+#[derive(Clone)]
+pub struct Program {
+    program_id: Pubkey,
+    cfg: Config,
+    rpc: Arc<RpcClient>,
+}
+
+impl Program {
+    pub fn payer(&self) -> Pubkey {
+        self.cfg.payer.pubkey()
+    }
+
+    pub fn id(&self) -> Pubkey {
+        self.program_id
+    }
+
+    /// Returns the RPC client shared by the program and its requests.
+    pub fn rpc(&self) -> Arc<RpcClient> {
+        self.rpc.clone()
+    }
+
+    /// Returns a request builder.
+    pub fn request(&self) -> RequestBuilder {
+        RequestBuilder::new_with_rpc(
+            self.program_id,
+            self.rpc.clone(),
+            self.cfg.payer.clone(),
+            RequestNamespace::Global,
+        )
+    }
+
+    /// Returns a request builder for program state.
+    pub fn state_request(&self) -> RequestBuilder {
+        RequestBuilder::new_with_rpc(
+            self.program_id,
+            self.rpc.clone(),
+            self.cfg.payer.clone(),
+            RequestNamespace::State { new: false },
+        )
+    }
+
+    /// Returns the account at the given address.
+    pub async fn account<T: AccountDeserialize>(&self, address: Pubkey) -> Result<T, ClientError> {
+        let account = self
+            .rpc
+            .get_account_with_commitment(&address, CommitmentConfig::processed())
+            .await?
+            .value
+            .ok_or(ClientError::AccountNotFound)?;
+        let mut data: &[u8] = &account.data;
+        T::try_deserialize(&mut data).map_err(Into::into)
+    }
+
+    /// Returns all program accounts of the given type matching the given filters.
+    pub async fn accounts<T: AccountDeserialize + Discriminator>(
+        &self,
+        filters: Vec<RpcFilterType>,
+    ) -> Result<Vec<(Pubkey, T)>, ClientError> {
+        let config = program_accounts_config::<T>(filters, None);
+        let accounts = self
+            .rpc
+            .get_program_accounts_with_config(&self.program_id, config)
+            .await?;
+        decode_program_accounts(accounts).collect()
+    }
+
+    /// Returns the accounts of type `T` at the given addresses, in order,
+    /// with `None` for the addresses without an account, fetched with one
+    /// `getMultipleAccounts` request per 100 addresses.
+    pub async fn accounts_by_address<T: AccountDeserialize>(
+        &self,
+        addresses: &[Pubkey],
+    ) -> Result<Vec<Option<T>>, ClientError> {
+        decode_accounts(self.raw_accounts_by_address(addresses).await?)
+    }
+
+    /// Returns the raw accounts at the given addresses, which may be of
+    /// different types, like [`Program::accounts_by_address`].
+    pub async fn raw_accounts_by_address(
+        &self,
+        addresses: &[Pubkey],
+    ) -> Result<Vec<Option<Account>>, ClientError> {
+        let mut accounts = Vec::with_capacity(addresses.len());
+        for chunk in addresses.chunks(MULTIPLE_ACCOUNTS_CHUNK_SIZE) {
+            let mut chunk_accounts = self.rpc.get_multiple_accounts(chunk).await?;
+            check_chunk(chunk, &chunk_accounts)?;
+            accounts.append(&mut chunk_accounts);
+        }
+        Ok(accounts)
+    }
+
+    pub async fn state<T: AccountDeserialize>(&self) -> Result<T, ClientError> {
+        self.account(anchor_lang::__private::state::address(&self.program_id))
+            .await
+    }
+
+    /// Returns the events of type `T` emitted by the program in a confirmed
+    /// transaction.
+    pub async fn transaction_events<T: EventSet>(
+        &self,
+        signature: Signature,
+    ) -> Result<TransactionEvents<T>, ClientError> {
+        let params = get_transaction_params(self.rpc.commitment(), &signature);
+        let transaction = self.rpc.send(RpcRequest::GetTransaction, params).await?;
+        transaction_events(&self.program_id, &signature, transaction)
+    }
+
+    /// Subscribes to the events of type `T`, an `#[event]` or an
+    /// [`event_enum!`](crate::event_enum) of several, emitted by the program.
+    /// The subscription reconnects with backoff when the websocket
+    /// disconnects, and ends when the stream is dropped.
+    pub async fn on<T: EventSet + Send + 'static>(&self) -> Result<EventStream<T>, ClientError> {
+        let ws_url = self.cfg.cluster.ws_url().to_string();
+        let (program_id, commitment) = (self.program_id, self.cfg.options);
+        subscribe(move |sender| {
+            subscribe_events(&ws_url, program_id, commitment, move |event| {
+                let _ = sender.send(event);
+            })
+        })
+        .await
+    }
+
+    /// Subscribes to the changes of the account of type `T` at the given
+    /// address. The subscription ends when the stream is dropped.
+    pub async fn subscribe_account<T: AccountDeserialize + Send + 'static>(
+        &self,
+        address: Pubkey,
+    ) -> Result<AccountStream<T>, ClientError> {
+        let ws_url = self.cfg.cluster.ws_url().to_string();
+        let commitment = self.cfg.options;
+        subscribe(move |sender| {
+            subscribe_account(&ws_url, address, commitment, move |update| {
+                let _ = sender.send(update);
+            })
+        })
+        .await
+    }
+
+    /// Subscribes to the changes of all program accounts of type `T` matching
+    /// the given filters. The subscription ends when the stream is dropped.
+    pub async fn subscribe_program_accounts<T>(
+        &self,
+        filters: Vec<RpcFilterType>,
+    ) -> Result<AccountStream<T>, ClientError>
+    where
+        T: AccountDeserialize + Discriminator + Send + 'static,
+    {
+        let ws_url = self.cfg.cluster.ws_url().to_string();
+        let (program_id, commitment) = (self.program_id, self.cfg.options);
+        subscribe(move |sender| {
+            subscribe_program_accounts(&ws_url, program_id, commitment, filters, move |update| {
+                let _ = sender.send(update);
+            })
+        })
+        .await
+    }
+}
+
+/// Stream of the events of a program, along with the transaction that emitted
+/// them. Yields an error for transactions whose logs can't be parsed and for
+/// failed attempts to reconnect.
+pub type EventStream<T> = SubscriptionStream<(EventContext, T)>;
+
+/// Stream of the updates of accounts. Yields an error for accounts that can't
+/// be deserialized and for failed attempts to reconnect.
+pub type AccountStream<T> = SubscriptionStream<AccountUpdate<T>>;
+
+/// Stream of the items of a subscription, which ends when it's dropped.
+pub struct SubscriptionStream<T> {
+    items: mpsc::UnboundedReceiver<Result<T, ClientError>>,
+    // Unsubscribes when the stream is dropped.
+    _handle: EventHandle,
+}
+
+impl<T> Stream for SubscriptionStream<T> {
+    type Item = Result<T, ClientError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.items.poll_recv(cx)
+    }
+}
+
+type ItemSender<T> = mpsc::UnboundedSender<Result<T, ClientError>>;
+
+// Subscribes on the blocking thread pool, where the websocket connects, with
+// `f` sending the items of the subscription to the returned stream.
+async fn subscribe<T, F>(f: F) -> Result<SubscriptionStream<T>, ClientError>
+where
+    T: Send + 'static,
+    F: FnOnce(ItemSender<T>) -> Result<EventHandle, ClientError> + Send + 'static,
+{
+    let (sender, items) = mpsc::unbounded_channel();
+    let handle = match tokio::task::spawn_blocking(move || f(sender)).await {
+        Ok(handle) => handle?,
+        Err(err) if err.is_panic() => std::panic::resume_unwind(err.into_panic()),
+        // The runtime is shutting down.
+        Err(_) => return Err(ClientError::SubscriptionCancelled),
+    };
+    Ok(SubscriptionStream {
+        items,
+        _handle: handle,
+    })
+}
+
+/// Async version of [`crate::RequestBuilder`].
+pub struct RequestBuilder<'a> {
+    rpc: Arc<RpcClient>,
+    program_id: Pubkey,
+    accounts: Vec<AccountMeta>,
+    instructions: Vec<Instruction>,
+    payer: Arc<dyn Signer + Send + Sync>,
+    // Serialized instruction data for the target RPC.
+    instruction_data: Option<Vec<u8>>,
+    signers: Vec<&'a (dyn Signer + Send + Sync)>,
+    // True if the user is sending a state instruction.
+    namespace: RequestNamespace,
+    compute_budget: ComputeBudget,
+}
+
+impl<'a> RequestBuilder<'a> {
+    pub fn from(
+        program_id: Pubkey,
+        cluster: &str,
+        payer: Arc<dyn Signer + Send + Sync>,
+        options: Option<CommitmentConfig>,
+        namespace: RequestNamespace,
+    ) -> Self {
+        let rpc = RpcClient::new_with_commitment(cluster.to_string(), options.unwrap_or_default());
+        Self::new_with_rpc(program_id, Arc::new(rpc), payer, namespace)
+    }
+
+    // A request made with the RPC client of a program.
+    fn new_with_rpc(
+        program_id: Pubkey,
+        rpc: Arc<RpcClient>,
+        payer: Arc<dyn Signer + Send + Sync>,
+        namespace: RequestNamespace,
+    ) -> Self {
+        Self {
+            rpc,
+            program_id,
+            payer,
+            accounts: Vec::new(),
+            instructions: Vec::new(),
+            instruction_data: None,
+            signers: Vec::new(),
+            namespace,
+            compute_budget: ComputeBudget::default(),
+        }
+    }
+
+    #[must_use]
+    pub fn payer(mut self, payer: Arc<dyn Signer + Send + Sync>) -> Self {
+        self.payer = payer;
+        self
+    }
+
+    #[must_use]
+    pub fn cluster(mut self, url: &str) -> Self {
+        let rpc = RpcClient::new_with_commitment(url.to_string(), self.rpc.commitment());
+        self.rpc = Arc::new(rpc);
+        self
+    }
+
+    #[must_use]
+    pub fn instruction(mut self, ix: Instruction) -> Self {
+        self.instructions.push(ix);
+        self
+    }
+
+    #[must_use]
+    pub fn program(mut self, program_id: Pubkey) -> Self {
+        self.program_id = program_id;
+        self
+    }
+
+    #[must_use]
+    pub fn accounts(mut self, accounts: impl ToAccountMetas) -> Self {
+        let mut metas = accounts.to_account_metas(None);
+        self.accounts.append(&mut metas);
+        self
+    }
+
+    #[must_use]
+    pub fn options(mut self, options: CommitmentConfig) -> Self {
+        self.rpc = Arc::new(RpcClient::new_with_commitment(self.rpc.url(), options));
+        self
+    }
+
+    #[must_use]
+    pub fn args(mut self, args: impl InstructionData) -> Self {
+        self.instruction_data = Some(args.data());
+        self
+    }
+
+    /// Invokes the `#[state]`'s `new` constructor.
+    #[allow(clippy::wrong_self_convention)]
+    #[must_use]
+    pub fn new(mut self, args: impl InstructionData) -> Self {
+        assert!(self.namespace == RequestNamespace::State { new: false });
+        self.namespace = RequestNamespace::State { new: true };
+        self.instruction_data = Some(args.data());
+        self
+    }
+
+    #[must_use]
+    pub fn signer(mut self, signer: &'a (dyn Signer + Send + Sync)) -> Self {
+        self.signers.push(signer);
+        self
+    }
+
+    /// Sets the maximum number of compute units the transaction can consume.
+    #[must_use]
+    pub fn compute_unit_limit(mut self, units: u32) -> Self {
+        self.compute_budget.unit_limit = Some(units);
+        self
+    }
+
+    /// Sets the price of a compute unit, in micro-lamports, paid as a
+    /// priority fee.
+    #[must_use]
+    pub fn compute_unit_price(mut self, micro_lamports: u64) -> Self {
+        self.compute_budget.unit_price = Some(micro_lamports);
+        self
+    }
+
+    /// Requests a heap of `bytes`, a multiple of 1024, for the programs
+    /// invoked.
+    #[must_use]
+    pub fn heap_frame_size(mut self, bytes: u32) -> Self {
+        self.compute_budget.heap_frame = Some(bytes);
+        self
+    }
+
+    /// Sets the compute unit limit when sending to the units consumed by a
+    /// simulation of the transaction, plus `margin_percent` percent, like
+    /// [`crate::RequestBuilder::auto_compute_unit_limit`].
+    #[must_use]
+    pub fn auto_compute_unit_limit(mut self, margin_percent: u32) -> Self {
+        self.compute_budget.auto_margin = Some(margin_percent);
+        self
+    }
+
+    /// Returns the instructions of the request, preceded by the compute
+    /// budget instructions, if any.
+    pub fn instructions(&self) -> Result<Vec<Instruction>, ClientError> {
+        Ok([
+            self.compute_budget.instructions(None),
+            self.request_instructions()?,
+        ]
+        .concat())
+    }
+
+    fn request_instructions(&self) -> Result<Vec<Instruction>, ClientError> {
+        let mut instructions = self.instructions.clone();
+        if let Some(ix_data) = &self.instruction_data {
+            instructions.push(request_instruction(
+                self.program_id,
+                &self.namespace,
+                self.payer.pubkey(),
+                &self.accounts,
+                ix_data.clone(),
+            ));
+        }
+        Ok(instructions)
+    }
+
+    pub async fn send(self) -> Result<Signature, ClientError> {
+        let instructions = self
+            .with_compute_budget(self.request_instructions()?)
+            .await?;
+        let tx = self.transaction(&instructions).await?;
+        self.send_transaction(tx).await
+    }
+
+    /// Sends the instructions of the request in as few transactions as fit,
+    /// like [`crate::RequestBuilder::send_batched`].
+    pub async fn send_batched(self, mode: BatchMode) -> Result<Vec<BatchResult>, ClientError> {
+        let instructions = self.request_instructions()?;
+        let payer = self.payer.pubkey();
+        let prefix = self.compute_budget.instructions(None);
+        let batches = pack_after(&prefix, &instructions, &payer)?;
+        check_signers(&instructions, &payer, &self.signers())?;
+
+        let mut results = Vec::with_capacity(batches.len());
+        match mode {
+            BatchMode::Sequential => {
+                for batch in batches {
+                    let result = match self.batch_transaction(&instructions[batch.clone()]).await {
+                        Ok(tx) => self.send_transaction(tx).await,
+                        Err(err) => Err(err),
+                    };
+                    let failed = result.is_err();
+                    results.push(BatchResult {
+                        instructions: batch,
+                        result,
+                    });
+                    if failed {
+                        break;
+                    }
+                }
+            }
+            BatchMode::Parallel => {
+                for wave in batches.chunks(MAX_PARALLEL_TRANSACTIONS) {
+                    let (this, instructions) = (&self, &instructions);
+                    let sends = wave.iter().map(|batch| async move {
+                        let tx = this.batch_transaction(&instructions[batch.clone()]).await?;
+                        this.send_transaction(tx).await
+                    });
+                    let wave_results = futures::future::join_all(sends).await;
+                    for (batch, result) in wave.iter().zip(wave_results) {
+                        results.push(BatchResult {
+                            instructions: batch.clone(),
+                            result,
+                        });
+                    }
+                }
+            }
+        }
+        Ok(results)
+    }
+
+    /// Simulates the transaction of the request, without sending it.
+    pub async fn simulate(&self) -> Result<Simulation, ClientError> {
+        let tx = self.transaction(&self.instructions()?).await?;
+        let result = self.rpc.simulate_transaction(&tx).await?.value;
+        Ok(Simulation::new(self.program_id, result))
+    }
+
+    async fn transaction(&self, instructions: &[Instruction]) -> Result<Transaction, ClientError> {
+        let recent_hash = self.recent_blockhash().await?;
+
+        Ok(Transaction::new_signed_with_payer(
+            instructions,
+            Some(&self.payer.pubkey()),
+            &self.signers(),
+            recent_hash,
+        ))
+    }
+
+    // The transaction of a batch of the request's instructions, preceded by
+    // the compute budget instructions and signed by the signers it requires.
+    async fn batch_transaction(
+        &self,
+        instructions: &[Instruction],
+    ) -> Result<Transaction, ClientError> {
+        let instructions = self.with_compute_budget(instructions.to_vec()).await?;
+        let recent_hash = self.recent_blockhash().await?;
+        sign_transaction(
+            &instructions,
+            &self.payer.pubkey(),
+            &self.signers(),
+            recent_hash,
+        )
+    }
+
+    // Prepends the compute budget instructions, resolving the limit of the
+    // automatic mode by simulating the transaction.
+    async fn with_compute_budget(
+        &self,
+        instructions: Vec<Instruction>,
+    ) -> Result<Vec<Instruction>, ClientError> {
+        let compute_budget = &self.compute_budget;
+        if compute_budget.auto_margin.is_none() {
+            return Ok([compute_budget.instructions(None), instructions].concat());
+        }
+        let recent_hash = self.recent_blockhash().await?;
+        let tx =
+            compute_budget.simulation_transaction(&self.payer.pubkey(), &instructions, recent_hash);
+        let result = self.rpc.simulate_transaction(&tx).await?.value;
+        compute_budget.resolve(self.program_id, &instructions, result)
+    }
+
+    async fn send_transaction(&self, tx: Transaction) -> Result<Signature, ClientError> {
+        Ok(self.rpc.send_and_confirm_transaction(&tx).await?)
+    }
+
+    async fn recent_blockhash(&self) -> Result<Hash, ClientError> {
+        Ok(self.rpc.get_latest_blockhash().await?)
+    }
+
+    // The signers of the request, including the payer.
+    fn signers(&self) -> Vec<&dyn Signer> {
+        let mut signers: Vec<&dyn Signer> = self.signers.iter().map(|s| *s as _).collect();
+        signers.push(&*self.payer);
+        signers
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::signature::Keypair;
+
+    fn assert_send<T: Send>(_: T) {}
+
+    #[test]
+    fn futures_are_send() {
+        let client = Client::new(Cluster::Localnet, Arc::new(Keypair::new()));
+        let program = client.program(Pubkey::new_unique());
+        let signer = Keypair::new();
+        let ix = Instruction::new_with_bytes(program.id(), &[], vec![]);
+
+        assert_send(program.account::<anchor_lang::idl::IdlAccount>(Pubkey::new_unique()));
+        assert_send(program.accounts::<anchor_lang::idl::IdlAccount>(vec![]));
+        assert_send(
+            program.subscribe_account::<anchor_lang::idl::IdlAccount>(Pubkey::new_unique()),
+        );
+        assert_send(program.request().instruction(ix.clone()).simulate());
+        assert_send(
+            program
+                .request()
+                .instruction(ix.clone())
+                .signer(&signer)
+                .send(),
+        );
+        assert_send(
+            program
+                .request()
+                .instruction(ix)
+                .signer(&signer)
+                .send_batched(BatchMode::Parallel),
+        );
+    }
+}
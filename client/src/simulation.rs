@@ -0,0 +1,195 @@
+use crate::{parse_logs, ClientError, EventSet};
+use anchor_lang::solana_program::pubkey::Pubkey;
+use anchor_lang::AnchorDeserialize;
+use regex::Regex;
+use solana_client::rpc_response::RpcSimulateTransactionResult;
+use solana_sdk::transaction::TransactionError;
+
+/// The outcome of simulating a request, returned by
+/// [`RequestBuilder::simulate`](crate::RequestBuilder::simulate).
+#[derive(Debug, Clone)]
+pub struct Simulation {
+    /// The program the request was built for, whose events and return data
+    /// are decoded.
+    pub program_id: Pubkey,
+    /// Error of the transaction, if it failed.
+    pub err: Option<TransactionError>,
+    pub logs: Vec<String>,
+    /// Compute units consumed by all instructions of the transaction, as
+    /// logged by the runtime.
+    pub units_consumed: Option<u64>,
+    /// The last Anchor error logged, i.e. the one the transaction failed with.
+    pub error: Option<AnchorErrorLog>,
+}
+
+impl Simulation {
+    pub(crate) fn new(program_id: Pubkey, result: RpcSimulateTransactionResult) -> Self {
+        let logs = result.logs.unwrap_or_default();
+        Self {
+            program_id,
+            err: result.err,
+            units_consumed: units_consumed(&logs),
+            error: logs.iter().rev().find_map(|l| AnchorErrorLog::parse(l)),
+            logs,
+        }
+    }
+
+    /// Returns the events of type `T` emitted by the program.
+    pub fn events<T: EventSet>(&self) -> Result<Vec<T>, ClientError> {
+        let mut events = vec![];
+        parse_logs(&self.program_id.to_string(), &self.logs, &mut |e| {
+            events.push(e.event)
+        })?;
+        Ok(events)
+    }
+
+    /// Returns the data last returned by the program, deserialized as `T`.
+    pub fn return_data<T: AnchorDeserialize>(&self) -> Result<Option<T>, ClientError> {
+        let prefix = format!("Program return: {} ", self.program_id);
+        let data = match self.logs.iter().rev().find_map(|l| l.strip_prefix(&prefix)) {
+            None => return Ok(None),
+            Some(data) => data,
+        };
+        let bytes = anchor_lang::__private::base64::decode(data).map_err(|_| {
+            ClientError::UnexpectedRpcResponse(format!("invalid return data {}", data))
+        })?;
+        T::deserialize(&mut &bytes[..])
+            .map(Some)
+            .map_err(|e| ClientError::UnexpectedRpcResponse(e.to_string()))
+    }
+}
+
+// Sums the compute units consumed by the top level instructions, which
+// include those of the programs they invoke.
+fn units_consumed(logs: &[String]) -> Option<u64> {
+    let invoke = Regex::new(r"^Program \S+ invoke \[(\d+)\]$").unwrap();
+    let consumed = Regex::new(r"^Program \S+ consumed (\d+) of \d+ compute units$").unwrap();
+    let mut depth = 0;
+    let mut units = None;
+    for l in logs {
+        if let Some(c) = invoke.captures(l) {
+            depth = c[1].parse().unwrap_or(depth);
+        } else if let Some(c) = consumed.captures(l) {
+            if depth == 1 {
+                *units.get_or_insert(0) += c[1].parse::<u64>().unwrap_or(0);
+            }
+        } else if l.starts_with("Program ") && (l.ends_with(" success") || l.contains(" failed: "))
+        {
+            depth -= 1;
+        }
+    }
+    units
+}
+
+/// An [`AnchorError`](anchor_lang::error::AnchorError) as logged by a
+/// program.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnchorErrorLog {
+    pub error_name: String,
+    pub error_code_number: u32,
+    pub error_msg: String,
+    pub account_name: Option<String>,
+    pub source: Option<ErrorSource>,
+}
+
+/// Location in the program's source where an error was thrown.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ErrorSource {
+    pub filename: String,
+    pub line: u32,
+}
+
+impl AnchorErrorLog {
+    /// Parses the log line of an Anchor error, e.g. `Program log: AnchorError
+    /// occurred. Error Code: ..`.
+    pub fn parse(log: &str) -> Option<Self> {
+        let re = Regex::new(concat!(
+            r"^Program log: AnchorError ",
+            r"(?:thrown in (.+):(\d+)|caused by account: (.+)|occurred)\. ",
+            r"Error Code: (\w+)\. Error Number: (\d+)\. Error Message: (.*)\.$",
+        ))
+        .unwrap();
+        let c = re.captures(log)?;
+        let source = match (c.get(1), c.get(2)) {
+            (Some(filename), Some(line)) => Some(ErrorSource {
+                filename: filename.as_str().to_string(),
+                line: line.as_str().parse().ok()?,
+            }),
+            _ => None,
+        };
+        Some(Self {
+            error_name: c[4].to_string(),
+            error_code_number: c[5].parse().ok()?,
+            error_msg: c[6].to_string(),
+            account_name: c.get(3).map(|m| m.as_str().to_string()),
+            source,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_anchor_error_logs() {
+        assert_eq!(
+            AnchorErrorLog::parse(
+                "Program log: AnchorError thrown in programs/errors/src/lib.rs:18. \
+                 Error Code: Hello. Error Number: 6000. Error Message: This is an error message."
+            ),
+            Some(AnchorErrorLog {
+                error_name: "Hello".into(),
+                error_code_number: 6000,
+                error_msg: "This is an error message".into(),
+                account_name: None,
+                source: Some(ErrorSource {
+                    filename: "programs/errors/src/lib.rs".into(),
+                    line: 18,
+                }),
+            })
+        );
+        assert_eq!(
+            AnchorErrorLog::parse(
+                "Program log: AnchorError caused by account: my_account. Error Code: \
+                 ConstraintMut. Error Number: 2000. Error Message: A mut constraint was violated."
+            )
+            .unwrap()
+            .account_name,
+            Some("my_account".into())
+        );
+        assert_eq!(
+            AnchorErrorLog::parse(
+                "Program log: AnchorError occurred. Error Code: Overflow. Error Number: 6001. \
+                 Error Message: Overflow."
+            )
+            .unwrap()
+            .error_code_number,
+            6001
+        );
+        assert_eq!(
+            AnchorErrorLog::parse("Program log: Instruction: Hello"),
+            None
+        );
+    }
+
+    #[test]
+    fn sum_top_level_units() {
+        let logs: Vec<String> = [
+            "Program A invoke [1]",
+            "Program B invoke [2]",
+            "Program B consumed 100 of 190000 compute units",
+            "Program B success",
+            "Program A consumed 1000 of 200000 compute units",
+            "Program A success",
+            "Program A invoke [1]",
+            "Program A consumed 500 of 200000 compute units",
+            "Program A failed: custom program error: 0x1770",
+        ]
+        .iter()
+        .map(|l| l.to_string())
+        .collect();
+        assert_eq!(units_consumed(&logs), Some(1500));
+        assert_eq!(units_consumed(&[]), None);
+    }
+}
@@ -0,0 +1,232 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::ext::IdentExt;
+
+// Types whose Borsh encoding has the same, fixed, size as their in memory
+// representation.
+const FIXED_SIZE_TYPES: &[&str] = &[
+    "bool", "u8", "i8", "u16", "i16", "u32", "i32", "u64", "i64", "u128", "i128", "f32", "f64",
+    "Pubkey",
+];
+
+/// Generates the `AccountLayout` implementation of an account and, for non
+/// generic accounts built off chain, a builder of `getProgramAccounts` filters
+/// matching its fields, e.g. `Vault::filter().authority_eq(key)`.
+///
+/// `header_len` is the length of the data preceding the fields, i.e. the
+/// discriminator and version byte.
+pub fn generate(
+    strct: &syn::ItemStruct,
+    header_len: usize,
+    is_zero_copy: bool,
+) -> syn::Result<TokenStream> {
+    let name = &strct.ident;
+    let (impl_gen, type_gen, where_clause) = strct.generics.split_for_impl();
+    let fields: Vec<&syn::Field> = match &strct.fields {
+        syn::Fields::Named(fields) => fields.named.iter().collect(),
+        _ => Vec::new(),
+    };
+
+    // Offsets are computed by the generated code, from the size and alignment
+    // of each field's type, in a const block of `let` statements. Borsh
+    // accounts have a static prefix up to the first field of unknown size,
+    // while every field of a zero copy account is at a fixed offset.
+    let pack = match is_zero_copy {
+        true => repr_pack(strct)?,
+        false => None,
+    };
+    let mut statements = Vec::new();
+    let mut prefix = Vec::new();
+    for (i, field) in fields.iter().enumerate() {
+        let ty = &field.ty;
+        let offset = format_ident!("offset_{}", i);
+        let end = format_ident!("end_{}", i);
+        let prev_end = match i {
+            0 => quote! { 0 },
+            _ => {
+                let prev_end = format_ident!("end_{}", i - 1);
+                quote! { #prev_end }
+            }
+        };
+        let offset_expr = if is_zero_copy {
+            let align = quote! { std::mem::align_of::<#ty>() };
+            let align = match pack {
+                None => align,
+                Some(pack) => quote! { anchor_lang::layout::packed_align(#align, #pack) },
+            };
+            quote! { anchor_lang::layout::align_up(#prev_end, #align) }
+        } else if is_fixed_size(ty) {
+            prev_end
+        } else {
+            break;
+        };
+        statements.push(quote! {
+            let #offset = #offset_expr;
+            let #end = #offset + std::mem::size_of::<#ty>();
+        });
+        prefix.push((*field, offset));
+    }
+    let is_fixed_size = is_zero_copy || prefix.len() == fields.len();
+
+    let names = prefix
+        .iter()
+        .map(|(field, _)| field.ident.as_ref().unwrap().unraw().to_string());
+    let offsets = prefix.iter().map(|(_, offset)| offset);
+    let data_size = match (is_zero_copy, is_fixed_size) {
+        (true, _) => quote! { Some(#header_len + std::mem::size_of::<Self>()) },
+        (false, true) => match prefix.last() {
+            None => quote! { Some(#header_len) },
+            Some(_) => {
+                let end = format_ident!("end_{}", prefix.len() - 1);
+                quote! { Some(#header_len + { #(#statements)* #end }) }
+            }
+        },
+        (false, false) => quote! { None },
+    };
+    let layout_impl = quote! {
+        #[automatically_derived]
+        impl #impl_gen anchor_lang::AccountLayout for #name #type_gen #where_clause {
+            #[allow(unused_variables)]
+            const FIELD_OFFSETS: &'static [(&'static str, usize)] = &{
+                #(#statements)*
+                [#((#names, #header_len + #offsets)),*]
+            };
+            #[allow(unused_variables)]
+            const DATA_SIZE: Option<usize> = #data_size;
+        }
+    };
+
+    // Filter builders aren't generated for generic accounts, whose filters
+    // depend on the type parameters.
+    if !strct.generics.params.is_empty() {
+        return Ok(layout_impl);
+    }
+
+    let vis = &strct.vis;
+    let filter_name = format_ident!("{}Filter", name);
+    let filter_doc = format!(
+        "Builder of `getProgramAccounts` filters matching the fields of `{}` accounts.",
+        name
+    );
+    let methods = prefix.iter().enumerate().map(|(i, (field, _))| {
+        let field_name = field.ident.as_ref().unwrap();
+        let method = format_ident!("{}_eq", field_name.unraw());
+        let ty = &field.ty;
+        let doc = format!(
+            "Matches accounts whose `{}` is `value`.",
+            field_name.unraw()
+        );
+        let bytes = match is_zero_copy {
+            true => quote! {{
+                let mut account: #name = anchor_lang::__private::bytemuck::Zeroable::zeroed();
+                account.#field_name = value;
+                let start = offset - #header_len;
+                anchor_lang::__private::bytemuck::bytes_of(&account)
+                    [start..start + std::mem::size_of::<#ty>()]
+                    .to_vec()
+            }},
+            false => quote! {
+                anchor_lang::AnchorSerialize::try_to_vec(&value)
+                    .expect("fixed size fields always serialize")
+            },
+        };
+        quote! {
+            #[doc = #doc]
+            pub fn #method(mut self, value: #ty) -> Self {
+                let offset = <#name as anchor_lang::AccountLayout>::FIELD_OFFSETS[#i].1;
+                self.filters.push(anchor_lang::AccountFilter::Memcmp {
+                    offset,
+                    bytes: #bytes,
+                });
+                self
+            }
+        }
+    });
+
+    Ok(quote! {
+        #layout_impl
+
+        // Filters are only of use to clients, so programs don't get items
+        // that may clash with their own.
+        anchor_lang::__off_chain! {
+            #[doc = #filter_doc]
+            #[derive(Debug, Clone)]
+            #vis struct #filter_name {
+                filters: Vec<anchor_lang::AccountFilter>,
+            }
+
+            #[automatically_derived]
+            impl #name {
+                #[doc = #filter_doc]
+                ///
+                /// Matches accounts of the exact size of the type, if it's fixed.
+                #vis fn filter() -> #filter_name {
+                    #filter_name {
+                        filters: <Self as anchor_lang::AccountLayout>::DATA_SIZE
+                            .map(|size| anchor_lang::AccountFilter::DataSize(size as u64))
+                            .into_iter()
+                            .collect(),
+                    }
+                }
+            }
+
+            #[automatically_derived]
+            impl #filter_name {
+                #(#methods)*
+
+                /// Returns the filters built.
+                pub fn into_filters(self) -> Vec<anchor_lang::AccountFilter> {
+                    self.filters
+                }
+            }
+
+            #[automatically_derived]
+            impl From<#filter_name> for Vec<anchor_lang::AccountFilter> {
+                fn from(filter: #filter_name) -> Self {
+                    filter.filters
+                }
+            }
+        }
+    })
+}
+
+fn is_fixed_size(ty: &syn::Type) -> bool {
+    match ty {
+        syn::Type::Array(array) => is_fixed_size(&array.elem),
+        syn::Type::Path(path) if path.qself.is_none() => match path.path.segments.last() {
+            Some(segment) => {
+                segment.arguments.is_empty()
+                    && FIXED_SIZE_TYPES.contains(&segment.ident.to_string().as_str())
+            }
+            None => false,
+        },
+        _ => false,
+    }
+}
+
+// The `N` of a `#[repr(packed(N))]` attribute, where `#[repr(packed)]` is
+// `N = 1`.
+fn repr_pack(strct: &syn::ItemStruct) -> syn::Result<Option<usize>> {
+    for attr in strct.attrs.iter().filter(|attr| attr.path.is_ident("repr")) {
+        if let syn::Meta::List(list) = attr.parse_meta()? {
+            for nested in list.nested.iter() {
+                match nested {
+                    syn::NestedMeta::Meta(syn::Meta::Path(path)) if path.is_ident("packed") => {
+                        return Ok(Some(1))
+                    }
+                    syn::NestedMeta::Meta(syn::Meta::List(packed))
+                        if packed.path.is_ident("packed") =>
+                    {
+                        if let Some(syn::NestedMeta::Lit(syn::Lit::Int(pack))) =
+                            packed.nested.first()
+                        {
+                            return Ok(Some(pack.base10_parse()?));
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+    Ok(None)
+}
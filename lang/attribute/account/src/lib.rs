@@ -3,6 +3,7 @@ extern crate proc_macro;
 use quote::quote;
 use syn::parse_macro_input;
 
+mod filter;
 mod id;
 
 /// An attribute for a data structure representing a Solana account.
@@ -20,10 +21,87 @@ mod id;
 /// reserved for a unique account discriminator, self described by the first 8
 /// bytes of the SHA256 of the account's Rust ident.
 ///
+/// The discriminator can instead be given explicitly, with any non-zero
+/// length, e.g. to match the layout of accounts owned by a non Anchor program.
+/// The account data then starts directly after those bytes.
+///
+/// ```ignore
+/// #[account(discriminator = [1, 2])]
+/// pub struct Data {
+///     pub authority: Pubkey,
+/// }
+/// ```
+///
 /// As a result, any calls to `AccountDeserialize`'s `try_deserialize` will
 /// check this discriminator. If it doesn't match, an invalid account was given,
 /// and the account deserialization will exit with an error.
 ///
+/// # Versioning
+///
+/// Changing the fields of an account breaks deserialization of every account
+/// already stored with the old layout. To allow a layout to evolve, an account
+/// can be declared with a `version`, which is stored as a single byte directly
+/// after the discriminator, and a list of previous layouts to `migrate` from.
+///
+/// ```ignore
+/// #[account(version = 2, migrate(1 = DataV1))]
+/// pub struct Data {
+///     pub authority: Pubkey,
+///     pub amount: u64,
+/// }
+///
+/// #[derive(AnchorSerialize, AnchorDeserialize)]
+/// pub struct DataV1 {
+///     pub authority: Pubkey,
+/// }
+///
+/// impl Migrate<DataV1> for Data {
+///     fn migrate(prev: DataV1) -> Self {
+///         Self {
+///             authority: prev.authority,
+///             amount: 0,
+///         }
+///     }
+/// }
+/// ```
+///
+/// Deserializing an account stored with an older version transparently
+/// upgrades it via [`Migrate`](./trait.Migrate.html). When the account is
+/// persisted at the end of the instruction it's written back with the current
+/// layout, reallocating the account if the new layout is larger. Note that
+/// the account must hold enough lamports to remain rent exempt at its new
+/// size, failing with `ErrorCode::AccountReallocNotRentExempt` otherwise, and
+/// `space` for new accounts must include the extra version byte.
+///
+/// Versioning can't be added to an account that was previously stored
+/// without a version, since the first byte of its data can't be told apart
+/// from a version byte.
+///
+/// # Filters
+///
+/// The offsets of the fields laid out at a fixed position in the account data,
+/// i.e. all fields up to the first one of variable size, are given by
+/// [`AccountLayout`](./trait.AccountLayout.html), along with the size of the
+/// data when every field has a fixed size. Off chain, a builder of
+/// `getProgramAccounts` filters matching those fields, named after the
+/// account, is generated as well, e.g.
+///
+/// ```ignore
+/// #[account]
+/// pub struct Vault {
+///     pub authority: Pubkey,
+///     pub amount: u64,
+/// }
+///
+/// let filters = Vault::filter().authority_eq(authority).into_filters();
+/// ```
+///
+/// matches vaults of the right size whose `authority` is `authority`, at an
+/// offset past the discriminator. The builder is a `VaultFilter`, so a `Vault`
+/// account can't have another `filter` method nor share its module with a
+/// `VaultFilter` item when built off chain. Offsets describe the current layout of an
+/// account, so filters don't match accounts stored with a previous `version`.
+///
 /// # Zero Copy Deserialization
 ///
 /// **WARNING**: Zero copy deserialization is an experimental feature. It's
@@ -63,47 +141,37 @@ pub fn account(
     args: proc_macro::TokenStream,
     input: proc_macro::TokenStream,
 ) -> proc_macro::TokenStream {
-    let mut namespace = "".to_string();
-    let mut is_zero_copy = false;
-    let args_str = args.to_string();
-    let args: Vec<&str> = args_str.split(',').collect();
-    if args.len() > 2 {
-        panic!("Only two args are allowed to the account attribute.")
-    }
-    for arg in args {
-        let ns = arg
-            .to_string()
-            .replace('\"', "")
-            .chars()
-            .filter(|c| !c.is_whitespace())
-            .collect();
-        if ns == "zero_copy" {
-            is_zero_copy = true;
-        } else {
-            namespace = ns;
-        }
-    }
+    let args = parse_macro_input!(args as anchor_syn::AccountArgs);
+    let namespace = args.namespace.clone().unwrap_or_default();
+    let is_zero_copy = args.zero_copy;
 
     let account_strct = parse_macro_input!(input as syn::ItemStruct);
     let account_name = &account_strct.ident;
     let (impl_gen, type_gen, where_clause) = account_strct.generics.split_for_impl();
 
-    let discriminator: proc_macro2::TokenStream = {
-        // Namespace the discriminator to prevent collisions.
-        let discriminator_preimage = {
-            // For now, zero copy accounts can't be namespaced.
-            if namespace.is_empty() {
-                format!("account:{}", account_name)
-            } else {
-                format!("{}:{}", namespace, account_name)
-            }
-        };
+    let discriminator: Vec<u8> = match &args.discriminator {
+        Some(discriminator) => discriminator.bytes.clone(),
+        None => {
+            // Namespace the discriminator to prevent collisions.
+            let discriminator_preimage = {
+                // For now, zero copy accounts can't be namespaced.
+                if namespace.is_empty() {
+                    format!("account:{}", account_name)
+                } else {
+                    format!("{}:{}", namespace, account_name)
+                }
+            };
+            anchor_syn::hash::hash(discriminator_preimage.as_bytes()).to_bytes()[..8].to_vec()
+        }
+    };
+    let disc_len = discriminator.len();
 
-        let mut discriminator = [0u8; 8];
-        discriminator.copy_from_slice(
-            &anchor_syn::hash::hash(discriminator_preimage.as_bytes()).to_bytes()[..8],
-        );
-        format!("{:?}", discriminator).parse().unwrap()
+    let layout = {
+        let header_len = disc_len + args.version.as_ref().map_or(0, |_| 1);
+        match filter::generate(&account_strct, header_len, is_zero_copy) {
+            Ok(layout) => layout,
+            Err(err) => return err.to_compile_error().into(),
+        }
     };
 
     let owner_impl = {
@@ -121,6 +189,78 @@ pub fn account(
         }
     };
 
+    // Versioned accounts store a single version byte directly after the
+    // discriminator and upgrade older layouts on deserialization.
+    let (version_header, header_len, deserialize_data, realloc_on_exit) = match &args.version {
+        None => (
+            quote! {},
+            quote! { #disc_len },
+            quote! { Self::try_deserialize_unchecked(buf) },
+            quote! {},
+        ),
+        Some(version) => {
+            let version = version.version;
+            let header_len = disc_len + 1;
+            let migration_arms: Vec<proc_macro2::TokenStream> = args
+                .migrations
+                .iter()
+                .map(|m| {
+                    let m_version = m.version;
+                    let layout = &m.layout;
+                    quote! {
+                        #m_version => {
+                            let prev: #layout = AnchorDeserialize::deserialize(&mut data)
+                                .map_err(|_| anchor_lang::error::ErrorCode::AccountDidNotDeserialize)?;
+                            Ok(<Self as anchor_lang::Migrate<#layout>>::migrate(prev))
+                        }
+                    }
+                })
+                .collect();
+            (
+                quote! {
+                    if writer.write_all(&[#version]).is_err() {
+                        return Err(anchor_lang::error::ErrorCode::AccountDidNotSerialize.into());
+                    }
+                },
+                quote! { #header_len },
+                quote! {
+                    let version = match buf.get(#disc_len) {
+                        None => return Err(anchor_lang::error::ErrorCode::AccountVersionUnsupported.into()),
+                        Some(version) => *version,
+                    };
+                    let mut data: &[u8] = &buf[#header_len..];
+                    match version {
+                        #version => AnchorDeserialize::deserialize(&mut data)
+                            .map_err(|_| anchor_lang::error::ErrorCode::AccountDidNotDeserialize.into()),
+                        #(#migration_arms)*
+                        _ => Err(anchor_lang::error::ErrorCode::AccountVersionUnsupported.into()),
+                    }
+                },
+                quote! {
+                    fn realloc_on_exit(&self) -> bool {
+                        true
+                    }
+                },
+            )
+        }
+    };
+
+    // Zero copy structs implement `IdlBuild` via `#[zero_copy]`.
+    #[cfg(feature = "idl-build")]
+    let idl_build = {
+        let print_fn = anchor_syn::idl::build::gen_idl_print_fn_account(&account_strct, &args);
+        let idl_build_impl = match is_zero_copy {
+            true => quote! {},
+            false => anchor_syn::idl::build::gen_idl_build_impl(&account_strct),
+        };
+        quote! {
+            #idl_build_impl
+            #print_fn
+        }
+    };
+    #[cfg(not(feature = "idl-build"))]
+    let idl_build = quote! {};
+
     proc_macro::TokenStream::from({
         if is_zero_copy {
             quote! {
@@ -137,9 +277,7 @@ pub fn account(
 
                 #[automatically_derived]
                 impl #impl_gen anchor_lang::Discriminator for #account_name #type_gen #where_clause {
-                    fn discriminator() -> [u8; 8] {
-                        #discriminator
-                    }
+                    const DISCRIMINATOR: &'static [u8] = &[#(#discriminator),*];
                 }
 
                 // This trait is useful for clients deserializing accounts.
@@ -147,18 +285,19 @@ pub fn account(
                 #[automatically_derived]
                 impl #impl_gen anchor_lang::AccountDeserialize for #account_name #type_gen #where_clause {
                     fn try_deserialize(buf: &mut &[u8]) -> anchor_lang::Result<Self> {
-                        if buf.len() < #discriminator.len() {
+                        let disc = <Self as anchor_lang::Discriminator>::DISCRIMINATOR;
+                        if buf.len() < disc.len() {
                             return Err(anchor_lang::error::ErrorCode::AccountDiscriminatorNotFound.into());
                         }
-                        let given_disc = &buf[..8];
-                        if &#discriminator != given_disc {
+                        let given_disc = &buf[..disc.len()];
+                        if disc != given_disc {
                             return Err(anchor_lang::error::ErrorCode::AccountDiscriminatorMismatch.into());
                         }
                         Self::try_deserialize_unchecked(buf)
                     }
 
                     fn try_deserialize_unchecked(buf: &mut &[u8]) -> anchor_lang::Result<Self> {
-                        let data: &[u8] = &buf[8..];
+                        let data: &[u8] = &buf[#disc_len..];
                         // Re-interpret raw bytes into the POD data structure.
                         let account = anchor_lang::__private::bytemuck::from_bytes(data);
                         // Copy out the bytes into a new, owned data structure.
@@ -167,6 +306,10 @@ pub fn account(
                 }
 
                 #owner_impl
+
+                #layout
+
+                #idl_build
             }
         } else {
             quote! {
@@ -176,32 +319,35 @@ pub fn account(
                 #[automatically_derived]
                 impl #impl_gen anchor_lang::AccountSerialize for #account_name #type_gen #where_clause {
                     fn try_serialize<W: std::io::Write>(&self, writer: &mut W) -> anchor_lang::Result<()> {
-                        if writer.write_all(&#discriminator).is_err() {
+                        if writer.write_all(<Self as anchor_lang::Discriminator>::DISCRIMINATOR).is_err() {
                             return Err(anchor_lang::error::ErrorCode::AccountDidNotSerialize.into());
                         }
-
+                        #version_header
                         if AnchorSerialize::serialize(self, writer).is_err() {
                             return Err(anchor_lang::error::ErrorCode::AccountDidNotSerialize.into());
                         }
                         Ok(())
                     }
+
+                    #realloc_on_exit
                 }
 
                 #[automatically_derived]
                 impl #impl_gen anchor_lang::AccountDeserialize for #account_name #type_gen #where_clause {
                     fn try_deserialize(buf: &mut &[u8]) -> anchor_lang::Result<Self> {
-                        if buf.len() < #discriminator.len() {
+                        let disc = <Self as anchor_lang::Discriminator>::DISCRIMINATOR;
+                        if buf.len() < disc.len() {
                             return Err(anchor_lang::error::ErrorCode::AccountDiscriminatorNotFound.into());
                         }
-                        let given_disc = &buf[..8];
-                        if &#discriminator != given_disc {
+                        let given_disc = &buf[..disc.len()];
+                        if disc != given_disc {
                             return Err(anchor_lang::error::ErrorCode::AccountDiscriminatorMismatch.into());
                         }
-                        Self::try_deserialize_unchecked(buf)
+                        #deserialize_data
                     }
 
                     fn try_deserialize_unchecked(buf: &mut &[u8]) -> anchor_lang::Result<Self> {
-                        let mut data: &[u8] = &buf[8..];
+                        let mut data: &[u8] = &buf[#header_len..];
                         AnchorDeserialize::deserialize(&mut data)
                             .map_err(|_| anchor_lang::error::ErrorCode::AccountDidNotDeserialize.into())
                     }
@@ -209,12 +355,170 @@ pub fn account(
 
                 #[automatically_derived]
                 impl #impl_gen anchor_lang::Discriminator for #account_name #type_gen #where_clause {
-                    fn discriminator() -> [u8; 8] {
-                        #discriminator
-                    }
+                    const DISCRIMINATOR: &'static [u8] = &[#(#discriminator),*];
                 }
 
                 #owner_impl
+
+                #layout
+
+                #idl_build
+            }
+        }
+    })
+}
+
+/// An attribute wrapping the state of a native, i.e. non Anchor, program so
+/// that it can be used with [`Account`](./accounts/account/struct.Account.html).
+///
+/// The attribute is given a tuple struct with a single field, the wrapped type,
+/// and the program owning accounts of that type.
+///
+/// ```ignore
+/// #[native_account(owner = spl_token::ID)]
+/// #[derive(Clone)]
+/// pub struct TokenAccount(spl_token::state::Account);
+/// ```
+///
+/// `#[native_account]` generates implementations for the following traits:
+///
+/// - [`AccountSerialize`](./trait.AccountSerialize.html)
+/// - [`AccountDeserialize`](./trait.AccountDeserialize.html)
+/// - [`Owner`](./trait.Owner.html)
+/// - `Deref` and `DerefMut`, to the wrapped type
+///
+/// By default the wrapped type must implement
+/// [`Pack`](../solana_program/program_pack/trait.Pack.html) and
+/// [`IsInitialized`](../solana_program/program_pack/trait.IsInitialized.html).
+/// Deserialization unpacks the account and checks it's initialized, while
+/// `try_deserialize_unchecked` skips the check, e.g. for accounts that are
+/// being created. Serialization goes through `Pack::pack`, so accounts owned
+/// by the executing program can be mutated through `Account<'info, T>`. A
+/// `LEN` constant is added to the wrapper for convenience.
+///
+/// Types that are Borsh encoded, rather than packed, can be wrapped with the
+/// `borsh` argument, e.g. `#[native_account(owner = ID, borsh)]`.
+#[proc_macro_attribute]
+pub fn native_account(
+    args: proc_macro::TokenStream,
+    input: proc_macro::TokenStream,
+) -> proc_macro::TokenStream {
+    let args = parse_macro_input!(args as anchor_syn::NativeAccountArgs);
+    let account_strct = parse_macro_input!(input as syn::ItemStruct);
+    let account_name = &account_strct.ident;
+    let (impl_gen, type_gen, where_clause) = account_strct.generics.split_for_impl();
+    let owner = &args.owner;
+
+    let inner_ty = match &account_strct.fields {
+        syn::Fields::Unnamed(fields) if fields.unnamed.len() == 1 => &fields.unnamed[0].ty,
+        _ => {
+            return syn::Error::new_spanned(
+                &account_strct,
+                "native accounts must be a tuple struct wrapping a single type",
+            )
+            .to_compile_error()
+            .into()
+        }
+    };
+
+    let (len_impl, deserialize, serialize) = if args.borsh {
+        (
+            quote! {},
+            quote! {
+                fn try_deserialize(buf: &mut &[u8]) -> anchor_lang::Result<Self> {
+                    let account = Self::try_deserialize_unchecked(buf)?;
+                    if !anchor_lang::solana_program::program_pack::IsInitialized::is_initialized(&account.0) {
+                        return Err(anchor_lang::error::ErrorCode::AccountNotInitialized.into());
+                    }
+                    Ok(account)
+                }
+
+                fn try_deserialize_unchecked(buf: &mut &[u8]) -> anchor_lang::Result<Self> {
+                    let mut data: &[u8] = buf;
+                    let inner: #inner_ty = anchor_lang::AnchorDeserialize::deserialize(&mut data)
+                        .map_err(|_| anchor_lang::error::ErrorCode::AccountDidNotDeserialize)?;
+                    Ok(Self(inner))
+                }
+            },
+            quote! {
+                fn try_serialize<W: std::io::Write>(&self, writer: &mut W) -> anchor_lang::Result<()> {
+                    anchor_lang::AnchorSerialize::serialize(&self.0, writer)
+                        .map_err(|_| anchor_lang::error::ErrorCode::AccountDidNotSerialize)?;
+                    Ok(())
+                }
+            },
+        )
+    } else {
+        (
+            quote! {
+                #[automatically_derived]
+                impl #impl_gen #account_name #type_gen #where_clause {
+                    pub const LEN: usize =
+                        <#inner_ty as anchor_lang::solana_program::program_pack::Pack>::LEN;
+                }
+            },
+            quote! {
+                fn try_deserialize(buf: &mut &[u8]) -> anchor_lang::Result<Self> {
+                    <#inner_ty as anchor_lang::solana_program::program_pack::Pack>::unpack(buf)
+                        .map(Self)
+                        .map_err(Into::into)
+                }
+
+                fn try_deserialize_unchecked(buf: &mut &[u8]) -> anchor_lang::Result<Self> {
+                    <#inner_ty as anchor_lang::solana_program::program_pack::Pack>::unpack_unchecked(buf)
+                        .map(Self)
+                        .map_err(Into::into)
+                }
+            },
+            quote! {
+                fn try_serialize<W: std::io::Write>(&self, writer: &mut W) -> anchor_lang::Result<()> {
+                    let mut data =
+                        vec![0u8; <#inner_ty as anchor_lang::solana_program::program_pack::Pack>::LEN];
+                    anchor_lang::solana_program::program_pack::Pack::pack(self.0.clone(), &mut data)?;
+                    writer
+                        .write_all(&data)
+                        .map_err(|_| anchor_lang::error::ErrorCode::AccountDidNotSerialize)?;
+                    Ok(())
+                }
+            },
+        )
+    };
+
+    proc_macro::TokenStream::from(quote! {
+        #account_strct
+
+        #len_impl
+
+        #[automatically_derived]
+        impl #impl_gen anchor_lang::AccountDeserialize for #account_name #type_gen #where_clause {
+            #deserialize
+        }
+
+        #[automatically_derived]
+        impl #impl_gen anchor_lang::AccountSerialize for #account_name #type_gen #where_clause {
+            #serialize
+        }
+
+        #[automatically_derived]
+        impl #impl_gen anchor_lang::Owner for #account_name #type_gen #where_clause {
+            fn owner() -> anchor_lang::solana_program::pubkey::Pubkey {
+                #owner
+            }
+        }
+
+        #[automatically_derived]
+        impl #impl_gen std::ops::Deref for #account_name #type_gen #where_clause {
+            type Target = #inner_ty;
+
+            fn deref(&self) -> &Self::Target {
+                &self.0
+            }
+        }
+
+        #[automatically_derived]
+        impl #impl_gen std::ops::DerefMut for #account_name #type_gen #where_clause {
+            fn deref_mut(&mut self) -> &mut Self::Target {
+                &mut self.0
             }
         }
     })
@@ -304,10 +608,17 @@ pub fn zero_copy(
         None => quote! {#[repr(C)]},
     };
 
+    #[cfg(feature = "idl-build")]
+    let idl_build = anchor_syn::idl::build::gen_idl_build_impl(&account_strct);
+    #[cfg(not(feature = "idl-build"))]
+    let idl_build = quote! {};
+
     proc_macro::TokenStream::from(quote! {
         #[derive(anchor_lang::__private::ZeroCopyAccessor, Copy, Clone)]
         #repr
         #account_strct
+
+        #idl_build
     })
 }
 
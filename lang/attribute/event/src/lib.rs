@@ -7,22 +7,31 @@ use syn::parse_macro_input;
 /// [emit!](./macro.emit.html) so that programs can log significant events in
 /// their programs that clients can subscribe to. Currently, this macro is for
 /// structs only.
+///
+/// By default the event discriminator is the first 8 bytes of the SHA256 of
+/// `event:<EventName>`. It can be set explicitly, with any non-zero length,
+/// e.g. `#[event(discriminator = [1, 2])]`.
 #[proc_macro_attribute]
 pub fn event(
-    _args: proc_macro::TokenStream,
+    args: proc_macro::TokenStream,
     input: proc_macro::TokenStream,
 ) -> proc_macro::TokenStream {
+    let args = parse_macro_input!(args as anchor_syn::EventArgs);
     let event_strct = parse_macro_input!(input as syn::ItemStruct);
 
     let event_name = &event_strct.ident;
 
-    let discriminator: proc_macro2::TokenStream = {
-        let discriminator_preimage = format!("event:{}", event_name);
-        let mut discriminator = [0u8; 8];
-        discriminator.copy_from_slice(
-            &anchor_syn::hash::hash(discriminator_preimage.as_bytes()).to_bytes()[..8],
-        );
-        format!("{:?}", discriminator).parse().unwrap()
+    #[cfg(feature = "idl-build")]
+    let idl_build = anchor_syn::idl::build::gen_idl_print_fn_event(&event_strct);
+    #[cfg(not(feature = "idl-build"))]
+    let idl_build = quote! {};
+
+    let discriminator: Vec<u8> = match args.discriminator {
+        Some(discriminator) => discriminator.bytes,
+        None => {
+            let discriminator_preimage = format!("event:{}", event_name);
+            anchor_syn::hash::hash(discriminator_preimage.as_bytes()).to_bytes()[..8].to_vec()
+        }
     };
 
     proc_macro::TokenStream::from(quote! {
@@ -31,17 +40,17 @@ pub fn event(
 
         impl anchor_lang::Event for #event_name {
             fn data(&self) -> Vec<u8> {
-                let mut d = #discriminator.to_vec();
+                let mut d = <Self as anchor_lang::Discriminator>::DISCRIMINATOR.to_vec();
                 d.append(&mut self.try_to_vec().unwrap());
                 d
             }
         }
 
         impl anchor_lang::Discriminator for #event_name {
-            fn discriminator() -> [u8; 8] {
-                #discriminator
-            }
+            const DISCRIMINATOR: &'static [u8] = &[#(#discriminator),*];
         }
+
+        #idl_build
     })
 }
 
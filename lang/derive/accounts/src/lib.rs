@@ -89,6 +89,25 @@ use syn::parse_macro_input;
 ///         </tr>
 ///         <tr>
 ///             <td>
+///                 <code>#[account(mut, dup)]</code>
+///             </td>
+///             <td>
+///                 By default, the same account can't be given for multiple mutable
+///                 fields, including those of nested accounts structs, since only
+///                 the last one's changes would be persisted.<br>
+///                 <code>dup</code> allows the account to alias other mutable accounts.
+///                 On a nested accounts struct, it allows all of its accounts to.<br><br>
+///                 Example:
+///                 <pre><code>
+/// #[account(mut)]
+/// pub from: Account<'info, MyData>,
+/// #[account(mut, dup)]
+/// pub to: Account<'info, MyData>
+///                 </code></pre>
+///             </td>
+///         </tr>
+///         <tr>
+///             <td>
 ///                 <code>#[account(init, payer = &lt;target_account&gt;)]</code><br><br>
 ///                 <code>#[account(init, payer = &lt;target_account&gt;, space = &lt;num_bytes&gt;)]</code>
 ///             </td>
@@ -5,6 +5,8 @@ use crate::*;
 use solana_program::account_info::AccountInfo;
 use solana_program::instruction::AccountMeta;
 use solana_program::pubkey::Pubkey;
+use solana_program::sysvar::rent::Rent;
+use solana_program::sysvar::Sysvar;
 use std::collections::BTreeMap;
 use std::fmt;
 use std::ops::{Deref, DerefMut};
@@ -331,6 +333,23 @@ impl<'info, T: AccountSerialize + AccountDeserialize + Owner + Clone> AccountsEx
         // Only persist if the owner is the current program.
         if &T::owner() == program_id {
             let info = self.to_account_info();
+            if self.account.realloc_on_exit() {
+                // The serialized account may have outgrown the account data,
+                // e.g., when migrating from an older layout.
+                let mut buf = Vec::new();
+                self.account.try_serialize(&mut buf)?;
+                if buf.len() > info.data_len() {
+                    // There's no payer on exit, so the account must already
+                    // hold the rent of its new size.
+                    if info.lamports() < Rent::get()?.minimum_balance(buf.len()) {
+                        return Err(ErrorCode::AccountReallocNotRentExempt.into());
+                    }
+                    info.realloc(buf.len(), false)?;
+                }
+                let mut data = info.try_borrow_mut_data()?;
+                data[..buf.len()].copy_from_slice(&buf);
+                return Ok(());
+            }
             let mut data = info.try_borrow_mut_data()?;
             let dst: &mut [u8] = &mut data;
             let mut cursor = std::io::Cursor::new(dst);
@@ -29,6 +29,10 @@ impl<'info, T: Accounts<'info>> Accounts<'info> for Box<T> {
     ) -> Result<Self> {
         T::try_accounts(program_id, accounts, ix_data, bumps).map(Box::new)
     }
+
+    fn mutable_keys(&self) -> Vec<Pubkey> {
+        T::mutable_keys(self)
+    }
 }
 
 impl<'info, T: AccountsExit<'info>> AccountsExit<'info> for Box<T> {
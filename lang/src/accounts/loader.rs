@@ -3,7 +3,6 @@ use crate::{
     Accounts, AccountsClose, AccountsExit, Result, ToAccountInfo, ToAccountInfos, ToAccountMetas,
     ZeroCopy,
 };
-use arrayref::array_ref;
 use solana_program::account_info::AccountInfo;
 use solana_program::instruction::AccountMeta;
 use solana_program::pubkey::Pubkey;
@@ -62,8 +61,8 @@ impl<'info, T: ZeroCopy> Loader<'info, T> {
         }
         let data: &[u8] = &acc_info.try_borrow_data()?;
         // Discriminator must match.
-        let disc_bytes = array_ref![data, 0, 8];
-        if disc_bytes != &T::discriminator() {
+        let disc = T::DISCRIMINATOR;
+        if data.len() < disc.len() || &data[..disc.len()] != disc {
             return Err(ErrorCode::AccountDiscriminatorMismatch.into());
         }
 
@@ -88,12 +87,14 @@ impl<'info, T: ZeroCopy> Loader<'info, T> {
     pub fn load(&self) -> Result<Ref<T>> {
         let data = self.acc_info.try_borrow_data()?;
 
-        let disc_bytes = array_ref![data, 0, 8];
-        if disc_bytes != &T::discriminator() {
+        let disc = T::DISCRIMINATOR;
+        if data.len() < disc.len() || &data[..disc.len()] != disc {
             return Err(ErrorCode::AccountDiscriminatorMismatch.into());
         }
 
-        Ok(Ref::map(data, |data| bytemuck::from_bytes(&data[8..])))
+        Ok(Ref::map(data, |data| {
+            bytemuck::from_bytes(&data[disc.len()..])
+        }))
     }
 
     /// Returns a `RefMut` to the account data structure for reading or writing.
@@ -107,13 +108,13 @@ impl<'info, T: ZeroCopy> Loader<'info, T> {
 
         let data = self.acc_info.try_borrow_mut_data()?;
 
-        let disc_bytes = array_ref![data, 0, 8];
-        if disc_bytes != &T::discriminator() {
+        let disc = T::DISCRIMINATOR;
+        if data.len() < disc.len() || &data[..disc.len()] != disc {
             return Err(ErrorCode::AccountDiscriminatorMismatch.into());
         }
 
         Ok(RefMut::map(data, |data| {
-            bytemuck::from_bytes_mut(&mut data.deref_mut()[8..])
+            bytemuck::from_bytes_mut(&mut data.deref_mut()[disc.len()..])
         }))
     }
 
@@ -130,15 +131,13 @@ impl<'info, T: ZeroCopy> Loader<'info, T> {
         let data = self.acc_info.try_borrow_mut_data()?;
 
         // The discriminator should be zero, since we're initializing.
-        let mut disc_bytes = [0u8; 8];
-        disc_bytes.copy_from_slice(&data[..8]);
-        let discriminator = u64::from_le_bytes(disc_bytes);
-        if discriminator != 0 {
+        let disc_len = T::DISCRIMINATOR.len();
+        if data[..disc_len].iter().any(|b| *b != 0) {
             return Err(ErrorCode::AccountDiscriminatorAlreadySet.into());
         }
 
         Ok(RefMut::map(data, |data| {
-            bytemuck::from_bytes_mut(&mut data.deref_mut()[8..])
+            bytemuck::from_bytes_mut(&mut data.deref_mut()[disc_len..])
         }))
     }
 }
@@ -169,7 +168,7 @@ impl<'info, T: ZeroCopy> AccountsExit<'info> for Loader<'info, T> {
         let mut data = self.acc_info.try_borrow_mut_data()?;
         let dst: &mut [u8] = &mut data;
         let mut cursor = std::io::Cursor::new(dst);
-        cursor.write_all(&T::discriminator()).unwrap();
+        cursor.write_all(T::DISCRIMINATOR).unwrap();
         Ok(())
     }
 }
@@ -41,6 +41,12 @@ pub enum ErrorCode {
     /// 1001 - Invalid program given to the IDL instruction
     #[msg("Invalid program given to the IDL instruction")]
     IdlInstructionInvalidProgram,
+    /// 1002 - The IDL account can't be resized below the size of its data
+    #[msg("The IDL account can't be resized below the size of its data")]
+    IdlAccountTooSmall,
+    /// 1003 - The IDL account can't be larger than the maximum account size
+    #[msg("The IDL account can't be larger than the maximum account size")]
+    IdlAccountTooLarge,
 
     // Constraints
     /// 2000 - A mut constraint was violated
@@ -105,6 +111,9 @@ pub enum ErrorCode {
     /// 2019 - A space constraint was violated
     #[msg("A space constraint was violated")]
     ConstraintSpace,
+    /// 2020 - The same account was given for multiple mutable accounts
+    #[msg("The same account was given for multiple mutable accounts")]
+    ConstraintDuplicateMutableAccount,
 
     // Accounts.
     /// 3000 - The account discriminator was already set on this account
@@ -152,6 +161,12 @@ pub enum ErrorCode {
     /// 3014 - The given account is not the associated token account
     #[msg("The given account is not the associated token account")]
     AccountNotAssociatedTokenAccount,
+    /// 3015 - The account version is not supported by the program
+    #[msg("The account version is not supported by the program")]
+    AccountVersionUnsupported,
+    /// 3016 - The account doesn't hold enough lamports to be rent exempt once reallocated
+    #[msg("The account doesn't hold enough lamports to be rent exempt once reallocated")]
+    AccountReallocNotRentExempt,
 
     // State.
     /// 4000 - The given state account does not have the correct address
@@ -169,6 +184,13 @@ pub enum ErrorCode {
     Deprecated = 5000,
 }
 
+/// Looks up the variant of an error enum by its error number, e.g. to decode
+/// the custom program error of a failed transaction. Implemented by
+/// `#[error_code]`.
+pub trait FromErrorCode: Sized {
+    fn from_error_code(code: u32) -> Option<Self>;
+}
+
 #[derive(Debug)]
 pub enum Error {
     AnchorError(AnchorError),
@@ -14,13 +14,21 @@
 //! multiple transactions via the `Write` instruction to continuously append to
 //! the account's IDL data buffer.
 //!
+//! The canonical IDL account can be grown (or shrunk) with the `Resize`
+//! instruction, when the IDL outgrows the space allocated on creation, and
+//! both the IDL account and buffers can be closed with the `Close`
+//! instruction, to reclaim their rent.
+//!
 //! Note that IDL account instructions are automatically inserted into all
 //! Anchor programs. To remove them, one can use the `no-idl` feature.
 
 #[allow(deprecated)]
 use crate::accounts::program_account::ProgramAccount;
+use crate::error::ErrorCode;
 use crate::prelude::*;
 use solana_program::pubkey::Pubkey;
+use solana_program::system_instruction::MAX_PERMITTED_DATA_LENGTH;
+use std::convert::TryFrom;
 
 // The first 8 bytes of an instruction to create or modify the IDL account. This
 // instruction is defined outside the main program's instruction enum, so that
@@ -41,6 +49,12 @@ pub enum IdlInstruction {
     SetBuffer,
     // Sets a new authority on the IdlAccount.
     SetAuthority { new_authority: Pubkey },
+    // Resizes the IdlAccount to hold `data_len` bytes of idl data. The account
+    // can grow by at most `MAX_PERMITTED_DATA_INCREASE` bytes per instruction.
+    Resize { data_len: u64 },
+    // Closes an IdlAccount or buffer, returning its lamports to the
+    // destination.
+    Close,
 }
 
 // Accounts for the Create instruction.
@@ -81,6 +95,30 @@ pub struct IdlSetBuffer<'info> {
     pub authority: AccountInfo<'info>,
 }
 
+// Accounts for resizing an IdlAccount.
+#[derive(Accounts)]
+pub struct IdlResizeAccount<'info> {
+    #[account(mut, has_one = authority)]
+    #[allow(deprecated)]
+    pub idl: ProgramAccount<'info, IdlAccount>,
+    // Pays for, or is refunded, the rent of the resized account.
+    #[account(mut, signer, constraint = authority.key != &Pubkey::new_from_array([0u8; 32]))]
+    pub authority: AccountInfo<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+// Accounts for closing an IdlAccount or buffer.
+#[derive(Accounts)]
+pub struct IdlCloseAccount<'info> {
+    #[account(mut, has_one = authority, close = sol_destination)]
+    #[allow(deprecated)]
+    pub account: ProgramAccount<'info, IdlAccount>,
+    #[account(signer, constraint = authority.key != &Pubkey::new_from_array([0u8; 32]))]
+    pub authority: AccountInfo<'info>,
+    #[account(mut)]
+    pub sol_destination: AccountInfo<'info>,
+}
+
 // The account holding a program's IDL. This is stored on chain so that clients
 // can fetch it and generate a client with nothing but a program's ID.
 //
@@ -104,4 +142,19 @@ impl IdlAccount {
     pub fn seed() -> &'static str {
         "anchor:idl"
     }
+    // Space needed to store `data_len` bytes of idl data.
+    //
+    // Space: account discriminator || authority pubkey || vec len || vec data
+    pub fn space(data_len: usize) -> usize {
+        8 + 32 + 4 + data_len
+    }
+    // Space needed to store `data_len` bytes of idl data, as requested by an
+    // instruction, failing if an account can't be that large.
+    pub fn try_space(data_len: u64) -> Result<usize> {
+        usize::try_from(data_len)
+            .ok()
+            .and_then(|data_len| Self::space(0).checked_add(data_len))
+            .filter(|space| *space as u64 <= MAX_PERMITTED_DATA_LENGTH)
+            .ok_or_else(|| error!(ErrorCode::IdlAccountTooLarge))
+    }
 }
@@ -0,0 +1,220 @@
+//! Building the IDL by compiling the program, enabled by the `idl-build`
+//! feature.
+//!
+//! With the feature, `#[account]`, `#[event]`, `#[error_code]`,
+//! `#[derive(Accounts)]` and `#[program]` generate tests printing fragments of
+//! the IDL, which `anchor build` collects when the program's `Cargo.toml` has
+//! an `idl-build` feature enabling `anchor-lang/idl-build`.
+//!
+//! The types used by those fragments are resolved through [`IdlBuild`], which
+//! is implemented for primitives, collections, and structs marked with
+//! `#[account]` or `#[zero_copy]`. It can be implemented by hand for other
+//! types. Types that don't implement it are parsed from source instead, and
+//! their definitions are taken from the source parser.
+
+use solana_program::pubkey::Pubkey;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::marker::PhantomData;
+
+pub use anchor_syn::idl::build::print_fragment;
+pub use anchor_syn::idl::{
+    Idl, IdlAccount, IdlAccountItem, IdlAccounts, IdlErrorCode, IdlEvent, IdlEventField, IdlField,
+    IdlInstruction, IdlType, IdlTypeDefinition, IdlTypeDefinitionTy,
+};
+
+/// Definitions of the user defined types used by the IDL, by name.
+pub type IdlTypes = BTreeMap<String, IdlTypeDefinition>;
+
+/// A type with a representation in the IDL.
+pub trait IdlBuild {
+    /// The IDL type of fields and arguments of this type.
+    fn idl_type() -> IdlType;
+
+    /// Adds the definitions of the user defined types used by this type,
+    /// including itself, to `types`.
+    fn insert_types(_types: &mut IdlTypes) {}
+}
+
+/// A struct of accounts with a representation in the IDL. Implemented by the
+/// `Accounts` derive macro.
+pub trait IdlBuildAccounts {
+    fn idl_accounts() -> Vec<IdlAccountItem>;
+}
+
+impl<T: IdlBuildAccounts> IdlBuildAccounts for Box<T> {
+    fn idl_accounts() -> Vec<IdlAccountItem> {
+        T::idl_accounts()
+    }
+}
+
+/// Inserts the definition of a type named `name`, unless already present. `ty`
+/// is given `types` to insert the types used by its fields.
+pub fn insert_type_def(
+    types: &mut IdlTypes,
+    name: &str,
+    ty: impl FnOnce(&mut IdlTypes) -> IdlTypeDefinitionTy,
+) {
+    if types.contains_key(name) {
+        return;
+    }
+    let ty_def = |ty| IdlTypeDefinition {
+        name: name.to_string(),
+        generics: vec![],
+        ty,
+        version: None,
+        history: vec![],
+        discriminator: None,
+        repr: None,
+        zero_copy: false,
+        size: None,
+    };
+    // Insert a placeholder first, so that recursive types terminate.
+    types.insert(
+        name.to_string(),
+        ty_def(IdlTypeDefinitionTy::Struct { fields: vec![] }),
+    );
+    let ty = ty(types);
+    types.insert(name.to_string(), ty_def(ty));
+}
+
+// Resolves the IDL type of `T` in generated code, via `IdlBuild` if it's
+// implemented, or else by parsing the source of the type. This relies on
+// method resolution picking `IdlTypeViaBuild`, which is implemented on
+// `IdlTypeOf<T>`, before auto-referencing for `IdlTypeViaSource`.
+#[doc(hidden)]
+pub struct IdlTypeOf<T>(pub PhantomData<T>);
+
+#[doc(hidden)]
+pub trait IdlTypeViaBuild {
+    fn idl_type(&self, types: &mut IdlTypes, source: &str) -> IdlType;
+}
+
+impl<T: IdlBuild> IdlTypeViaBuild for IdlTypeOf<T> {
+    fn idl_type(&self, types: &mut IdlTypes, _source: &str) -> IdlType {
+        T::insert_types(types);
+        T::idl_type()
+    }
+}
+
+#[doc(hidden)]
+pub trait IdlTypeViaSource {
+    fn idl_type(&self, types: &mut IdlTypes, source: &str) -> IdlType;
+}
+
+impl<T> IdlTypeViaSource for &IdlTypeOf<T> {
+    fn idl_type(&self, _types: &mut IdlTypes, source: &str) -> IdlType {
+        source
+            .parse()
+            .unwrap_or_else(|e| panic!("Could not parse IDL type `{}`: {}", source, e))
+    }
+}
+
+macro_rules! impl_idl_build {
+    ($($ty:ty => $idl_ty:ident),* $(,)?) => {
+        $(
+            impl IdlBuild for $ty {
+                fn idl_type() -> IdlType {
+                    IdlType::$idl_ty
+                }
+            }
+        )*
+    };
+}
+
+impl_idl_build! {
+    bool => Bool,
+    u8 => U8,
+    i8 => I8,
+    u16 => U16,
+    i16 => I16,
+    u32 => U32,
+    i32 => I32,
+    f32 => F32,
+    u64 => U64,
+    i64 => I64,
+    f64 => F64,
+    u128 => U128,
+    i128 => I128,
+    String => String,
+    Pubkey => PublicKey,
+}
+
+impl<T: IdlBuild> IdlBuild for Box<T> {
+    fn idl_type() -> IdlType {
+        T::idl_type()
+    }
+
+    fn insert_types(types: &mut IdlTypes) {
+        T::insert_types(types)
+    }
+}
+
+impl<T: IdlBuild> IdlBuild for Option<T> {
+    fn idl_type() -> IdlType {
+        IdlType::Option(Box::new(T::idl_type()))
+    }
+
+    fn insert_types(types: &mut IdlTypes) {
+        T::insert_types(types)
+    }
+}
+
+impl<T: IdlBuild> IdlBuild for Vec<T> {
+    fn idl_type() -> IdlType {
+        match T::idl_type() {
+            IdlType::U8 => IdlType::Bytes,
+            ty => IdlType::Vec(Box::new(ty)),
+        }
+    }
+
+    fn insert_types(types: &mut IdlTypes) {
+        T::insert_types(types)
+    }
+}
+
+impl<T: IdlBuild, const N: usize> IdlBuild for [T; N] {
+    fn idl_type() -> IdlType {
+        IdlType::Array(Box::new(T::idl_type()), N)
+    }
+
+    fn insert_types(types: &mut IdlTypes) {
+        T::insert_types(types)
+    }
+}
+
+macro_rules! impl_idl_build_map {
+    ($($map:ident),*) => {
+        $(
+            impl<K: IdlBuild, V: IdlBuild> IdlBuild for $map<K, V> {
+                fn idl_type() -> IdlType {
+                    IdlType::$map(Box::new(K::idl_type()), Box::new(V::idl_type()))
+                }
+
+                fn insert_types(types: &mut IdlTypes) {
+                    K::insert_types(types);
+                    V::insert_types(types);
+                }
+            }
+        )*
+    };
+}
+
+impl_idl_build_map!(HashMap, BTreeMap);
+
+macro_rules! impl_idl_build_set {
+    ($($set:ident),*) => {
+        $(
+            impl<T: IdlBuild> IdlBuild for $set<T> {
+                fn idl_type() -> IdlType {
+                    IdlType::$set(Box::new(T::idl_type()))
+                }
+
+                fn insert_types(types: &mut IdlTypes) {
+                    T::insert_types(types)
+                }
+            }
+        )*
+    };
+}
+
+impl_idl_build_set!(HashSet, BTreeSet);
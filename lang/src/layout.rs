@@ -0,0 +1,74 @@
+//! Layout of account data, used to filter the accounts of a program by the
+//! value of their fields, e.g. with `getProgramAccounts`.
+
+use crate::Discriminator;
+
+/// A filter on the data of an account, mirroring the filters of the
+/// `getProgramAccounts` RPC method.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AccountFilter {
+    /// Matches accounts whose data contains `bytes` at `offset`.
+    Memcmp { offset: usize, bytes: Vec<u8> },
+    /// Matches accounts whose data is exactly this many bytes long.
+    DataSize(u64),
+}
+
+/// The statically known layout of an account's data, implemented by
+/// [`#[account]`](./attr.account.html).
+///
+/// Offsets are from the start of the account data, i.e. they include the
+/// discriminator and, for versioned accounts, the version byte. They describe
+/// the current layout of the account only.
+pub trait AccountLayout: Discriminator {
+    /// Names and offsets of the fields laid out at a fixed offset, i.e. all
+    /// fields up to the first one with a variable size, such as a `Vec` or
+    /// `String`.
+    const FIELD_OFFSETS: &'static [(&'static str, usize)];
+
+    /// Size of the account data, if all of its fields have a fixed size.
+    const DATA_SIZE: Option<usize>;
+
+    /// Offset of the field `name`, if it's laid out at a fixed offset.
+    fn field_offset(name: &str) -> Option<usize> {
+        Self::FIELD_OFFSETS
+            .iter()
+            .find(|(field, _)| *field == name)
+            .map(|(_, offset)| *offset)
+    }
+}
+
+/// Aligns `offset` up to `align`, which must be a power of two.
+#[doc(hidden)]
+pub const fn align_up(offset: usize, align: usize) -> usize {
+    (offset + align - 1) & !(align - 1)
+}
+
+/// Alignment of a field of a `#[repr(packed(pack))]` struct.
+#[doc(hidden)]
+pub const fn packed_align(align: usize, pack: usize) -> usize {
+    if align < pack {
+        align
+    } else {
+        pack
+    }
+}
+
+// The target is checked here, rather than by a `#[cfg]` in the code generated
+// for programs, which may not declare it.
+#[allow(unexpected_cfgs)]
+mod off_chain {
+    /// Expands to the given items only off chain, e.g. in clients and tests.
+    #[doc(hidden)]
+    #[macro_export]
+    #[cfg(not(any(target_arch = "bpf", target_os = "solana")))]
+    macro_rules! __off_chain {
+        ($($item:item)*) => { $($item)* };
+    }
+
+    #[doc(hidden)]
+    #[macro_export]
+    #[cfg(any(target_arch = "bpf", target_os = "solana"))]
+    macro_rules! __off_chain {
+        ($($item:item)*) => {};
+    }
+}
@@ -39,13 +39,17 @@ mod ctor;
 pub mod error;
 #[doc(hidden)]
 pub mod idl;
+#[cfg(feature = "idl-build")]
+pub mod idl_build;
+pub mod layout;
 mod system_program;
 
 pub use crate::system_program::System;
 mod vec;
 pub use crate::bpf_upgradeable_state::*;
+pub use crate::layout::{AccountFilter, AccountLayout};
 pub use anchor_attribute_access_control::access_control;
-pub use anchor_attribute_account::{account, declare_id, zero_copy};
+pub use anchor_attribute_account::{account, declare_id, native_account, zero_copy};
 pub use anchor_attribute_constant::constant;
 pub use anchor_attribute_error;
 pub use anchor_attribute_event::{emit, event};
@@ -83,6 +87,16 @@ pub trait Accounts<'info>: ToAccountMetas + ToAccountInfos<'info> + Sized {
         ix_data: &[u8],
         bumps: &mut BTreeMap<String, u8>,
     ) -> Result<Self>;
+
+    /// Returns the keys of all mutable accounts in the struct, including
+    /// nested structs, except those marked with `#[account(dup)]`.
+    ///
+    /// Used by the `Accounts` derive macro to reject the same account being
+    /// given for multiple mutable fields, across nested structs. Implementors
+    /// without mutable accounts can rely on the default, empty, list.
+    fn mutable_keys(&self) -> Vec<Pubkey> {
+        Vec::new()
+    }
 }
 
 /// The exit procedure for an account. Any cleanup or persistence to storage
@@ -150,6 +164,13 @@ pub trait AccountSerialize {
     fn try_serialize<W: Write>(&self, _writer: &mut W) -> Result<()> {
         Ok(())
     }
+
+    /// Returns true if the account's data may be grown to fit the serialized
+    /// account when it's persisted, e.g., after a versioned account has been
+    /// migrated from an older, smaller layout.
+    fn realloc_on_exit(&self) -> bool {
+        false
+    }
 }
 
 /// A data structure that can be deserialized and stored into account storage,
@@ -174,11 +195,20 @@ pub trait AccountDeserialize: Sized {
     fn try_deserialize_unchecked(buf: &mut &[u8]) -> Result<Self>;
 }
 
+/// Upgrades a previous layout of a versioned account into the current one.
+///
+/// Implement this for each layout listed in the `migrate` argument of an
+/// account declared with [`#[account(version = ..)]`](./attr.account.html).
+pub trait Migrate<Prev>: Sized {
+    fn migrate(prev: Prev) -> Self;
+}
+
 /// An account data structure capable of zero copy deserialization.
 pub trait ZeroCopy: Discriminator + Copy + Clone + Zeroable + Pod {}
 
 /// Calculates the data for an instruction invocation, where the data is
-/// `Sha256(<namespace>::<method_name>)[..8] || BorshSerialize(args)`.
+/// `Sha256(<namespace>::<method_name>)[..8] || BorshSerialize(args)`, unless
+/// the instruction was given an explicit discriminator.
 /// `args` is a borsh serialized struct of named fields for each argument given
 /// to an instruction.
 pub trait InstructionData: AnchorSerialize {
@@ -198,9 +228,17 @@ pub trait EventData: AnchorSerialize + Discriminator {
     fn data(&self) -> Vec<u8>;
 }
 
-/// 8 byte unique identifier for a type.
+/// Unique identifier for a type.
+///
+/// By default this is the first 8 bytes of the SHA256 of the type's namespaced
+/// name. It can be set explicitly, with any non-zero length, via the
+/// `discriminator` argument, e.g. `#[account(discriminator = [1, 2])]`.
 pub trait Discriminator {
-    fn discriminator() -> [u8; 8];
+    const DISCRIMINATOR: &'static [u8];
+
+    fn discriminator() -> &'static [u8] {
+        Self::DISCRIMINATOR
+    }
 }
 
 /// Bump seed for program derived addresses.
@@ -241,10 +279,11 @@ pub mod prelude {
         accounts::signer::Signer, accounts::system_account::SystemAccount,
         accounts::sysvar::Sysvar, accounts::unchecked_account::UncheckedAccount, constant,
         context::Context, context::CpiContext, declare_id, emit, err, error, event, interface,
-        program, require, solana_program::bpf_loader_upgradeable::UpgradeableLoaderState, source,
-        state, zero_copy, AccountDeserialize, AccountSerialize, Accounts, AccountsExit,
-        AnchorDeserialize, AnchorSerialize, Id, Key, Owner, ProgramData, Result, System,
-        ToAccountInfo, ToAccountInfos, ToAccountMetas,
+        native_account, program, require,
+        solana_program::bpf_loader_upgradeable::UpgradeableLoaderState, source, state, zero_copy,
+        AccountDeserialize, AccountSerialize, Accounts, AccountsExit, AnchorDeserialize,
+        AnchorSerialize, Id, Key, Migrate, Owner, ProgramData, Result, System, ToAccountInfo,
+        ToAccountInfos, ToAccountMetas,
     };
     pub use anchor_attribute_error::*;
     pub use borsh;
@@ -256,8 +295,11 @@ pub mod prelude {
     pub use solana_program::pubkey::Pubkey;
     pub use solana_program::sysvar::clock::Clock;
     pub use solana_program::sysvar::epoch_schedule::EpochSchedule;
+    // Deprecated by newer solana versions, kept for the programs using them.
+    #[allow(deprecated)]
     pub use solana_program::sysvar::fees::Fees;
     pub use solana_program::sysvar::instructions::Instructions;
+    #[allow(deprecated)]
     pub use solana_program::sysvar::recent_blockhashes::RecentBlockhashes;
     pub use solana_program::sysvar::rent::Rent;
     pub use solana_program::sysvar::rewards::Rewards;
@@ -308,7 +350,7 @@ pub mod __private {
     #[doc(hidden)]
     impl ZeroCopyAccessor<Pubkey> for [u8; 32] {
         fn get(&self) -> Pubkey {
-            Pubkey::new(self)
+            Pubkey::new_from_array(*self)
         }
         fn set(input: &Pubkey) -> [u8; 32] {
             input.to_bytes()
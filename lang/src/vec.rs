@@ -31,6 +31,10 @@ impl<'info, T: Accounts<'info>> Accounts<'info> for Vec<T> {
         T::try_accounts(program_id, accounts, ix_data, bumps).map(|item| vec.push(item))?;
         Ok(vec)
     }
+
+    fn mutable_keys(&self) -> Vec<Pubkey> {
+        self.iter().flat_map(|item| item.mutable_keys()).collect()
+    }
 }
 
 #[cfg(test)]
@@ -22,6 +22,11 @@ pub fn generate(accs: &AccountsStruct) -> proc_macro2::TokenStream {
     let __client_accounts_mod = __client_accounts::generate(accs);
     let __cpi_client_accounts_mod = __cpi_client_accounts::generate(accs);
 
+    #[cfg(feature = "idl-build")]
+    let impl_idl_build = crate::idl::build::gen_idl_build_impl_accounts(accs);
+    #[cfg(not(feature = "idl-build"))]
+    let impl_idl_build = quote! {};
+
     quote! {
         #impl_try_accounts
         #impl_to_account_infos
@@ -30,6 +35,8 @@ pub fn generate(accs: &AccountsStruct) -> proc_macro2::TokenStream {
 
         #__client_accounts_mod
         #__cpi_client_accounts_mod
+
+        #impl_idl_build
     }
 }
 
@@ -53,7 +53,9 @@ pub fn generate(accs: &AccountsStruct) -> proc_macro2::TokenStream {
         })
         .collect();
 
+    let duplicate_checks = generate_duplicate_checks(accs);
     let constraints = generate_constraints(accs);
+    let mutable_keys = generate_mutable_keys(accs);
     let accounts_instance = generate_accounts_instance(accs);
 
     let ix_de = match &accs.instruction_api {
@@ -100,11 +102,106 @@ pub fn generate(accs: &AccountsStruct) -> proc_macro2::TokenStream {
                 #ix_de
                 // Deserialize each account.
                 #(#deser_fields)*
+                // Reject accounts given for multiple mutable fields.
+                #duplicate_checks
                 // Execute accounts constraints.
                 #constraints
                 // Success. Return the validated accounts.
                 Ok(#accounts_instance)
             }
+
+            #mutable_keys
+        }
+    }
+}
+
+// Mutable fields that must not alias each other. Composite fields are included
+// as a whole, since their mutable accounts can alias those of the outer struct.
+fn mutable_fields(accs: &AccountsStruct) -> Vec<&AccountField> {
+    accs.fields
+        .iter()
+        .filter(|af| match af {
+            AccountField::CompositeField(s) => !s.constraints.is_dup(),
+            AccountField::Field(f) => f.constraints.is_mutable() && !f.constraints.is_dup(),
+        })
+        .collect()
+}
+
+// Generates the check that no account key is given for more than one mutable
+// field, including the mutable fields of nested `Accounts` structs.
+fn generate_duplicate_checks(accs: &AccountsStruct) -> proc_macro2::TokenStream {
+    let fields = mutable_fields(accs);
+    // A single composite field has already checked its own accounts.
+    if fields.len() < 2 {
+        return quote! {};
+    }
+    let keys: Vec<proc_macro2::TokenStream> = fields
+        .iter()
+        .map(|af| match af {
+            AccountField::CompositeField(s) => {
+                let name = &s.ident;
+                let name_str = name.to_string();
+                quote! {
+                    __mutable_keys.extend(
+                        anchor_lang::Accounts::mutable_keys(&#name)
+                            .into_iter()
+                            .map(|key| (#name_str, key))
+                    );
+                }
+            }
+            AccountField::Field(f) => {
+                let name = &f.ident;
+                let name_str = name.to_string();
+                quote! {
+                    __mutable_keys.push((#name_str, #name.key()));
+                }
+            }
+        })
+        .collect();
+    quote! {
+        {
+            use anchor_lang::Key as _;
+            let mut __mutable_keys: Vec<(&str, anchor_lang::solana_program::pubkey::Pubkey)> = Vec::new();
+            #(#keys)*
+            for (idx, (name, key)) in __mutable_keys.iter().enumerate() {
+                if __mutable_keys[..idx].iter().any(|(_, prev)| prev == key) {
+                    return Err(anchor_lang::anchor_attribute_error::error_with_account_name!(anchor_lang::error::ErrorCode::ConstraintDuplicateMutableAccount, name));
+                }
+            }
+        }
+    }
+}
+
+// Generates `Accounts::mutable_keys`, used by outer structs to check for
+// duplicates across nested structs.
+fn generate_mutable_keys(accs: &AccountsStruct) -> proc_macro2::TokenStream {
+    let fields = mutable_fields(accs);
+    if fields.is_empty() {
+        return quote! {};
+    }
+    let keys: Vec<proc_macro2::TokenStream> = fields
+        .iter()
+        .map(|af| match af {
+            AccountField::CompositeField(s) => {
+                let name = &s.ident;
+                quote! {
+                    keys.extend(anchor_lang::Accounts::mutable_keys(&self.#name));
+                }
+            }
+            AccountField::Field(f) => {
+                let name = &f.ident;
+                quote! {
+                    keys.push(self.#name.key());
+                }
+            }
+        })
+        .collect();
+    quote! {
+        fn mutable_keys(&self) -> Vec<anchor_lang::solana_program::pubkey::Pubkey> {
+            use anchor_lang::Key as _;
+            let mut keys = Vec::new();
+            #(#keys)*
+            keys
         }
     }
 }
@@ -47,6 +47,18 @@ pub fn generate(error: Error) -> proc_macro2::TokenStream {
         })
         .collect();
 
+    let variants: Vec<&syn::Ident> = error
+        .raw_enum
+        .variants
+        .iter()
+        .map(|variant| &variant.ident)
+        .collect();
+
+    #[cfg(feature = "idl-build")]
+    let idl_build = crate::idl::build::gen_idl_print_fn_errors(&error);
+    #[cfg(not(feature = "idl-build"))]
+    let idl_build = quote! {};
+
     let offset = match error.args {
         None => quote! { anchor_lang::error::ERROR_CODE_OFFSET},
         Some(args) => {
@@ -75,6 +87,15 @@ pub fn generate(error: Error) -> proc_macro2::TokenStream {
             }
         }
 
+        impl anchor_lang::error::FromErrorCode for #enum_name {
+            fn from_error_code(code: u32) -> Option<Self> {
+                [#(#enum_name::#variants),*]
+                    .iter()
+                    .copied()
+                    .find(|error_code| u32::from(*error_code) == code)
+            }
+        }
+
         impl From<#enum_name> for anchor_lang::error::Error {
             fn from(error_code: #enum_name) -> Error {
                 anchor_lang::error::Error::from(
@@ -96,5 +117,7 @@ pub fn generate(error: Error) -> proc_macro2::TokenStream {
                 }
             }
         }
+
+        #idl_build
     }
 }
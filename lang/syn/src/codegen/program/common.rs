@@ -1,5 +1,5 @@
 use crate::parser;
-use crate::{IxArg, State};
+use crate::{Ix, IxArg, State};
 use heck::CamelCase;
 use quote::quote;
 
@@ -10,6 +10,11 @@ pub const SIGHASH_STATE_NAMESPACE: &str = "state";
 // not affecting program state.
 pub const SIGHASH_GLOBAL_NAMESPACE: &str = "global";
 
+// Mirrors `anchor_lang::idl::IDL_IX_TAG`, whose little endian bytes prefix
+// the IDL instructions injected into every program. A test of `anchor-lang`
+// checks that they match.
+pub const IDL_IX_TAG: u64 = 0x0a69e9a778bcf440;
+
 // We don't technically use sighash, because the input arguments aren't given.
 // Rust doesn't have method overloading so no need to use the arguments.
 // However, we do namespace methods in the preeimage so that we can use
@@ -26,6 +31,27 @@ pub fn sighash_ctor() -> [u8; 8] {
     sighash(SIGHASH_STATE_NAMESPACE, "new")
 }
 
+// The discriminator of a global instruction, either given explicitly via
+// `#[instruction(discriminator = ..)]` or derived from the handler's name.
+pub fn ix_discriminator(ix: &Ix) -> Vec<u8> {
+    match &ix.discriminator {
+        Some(discriminator) => discriminator.bytes.clone(),
+        None => sighash(
+            SIGHASH_GLOBAL_NAMESPACE,
+            &ix.raw_method.sig.ident.to_string(),
+        )
+        .to_vec(),
+    }
+}
+
+// A slice pattern matching instruction data that starts with the given
+// discriminator, binding the remaining bytes to `ix_data`.
+pub fn discriminator_pattern(discriminator: &[u8]) -> proc_macro2::TokenStream {
+    quote! {
+        [#(#discriminator,)* ix_data @ ..]
+    }
+}
+
 pub fn generate_ix_variant(name: String, args: &[IxArg]) -> proc_macro2::TokenStream {
     let ix_arg_names: Vec<&syn::Ident> = args.iter().map(|arg| &arg.name).collect();
     let ix_name_camel: proc_macro2::TokenStream = {
@@ -9,11 +9,9 @@ pub fn generate(program: &Program) -> proc_macro2::TokenStream {
         Some(state) => match state.ctor_and_anchor.is_some() {
             false => quote! {},
             true => {
-                let sighash_arr = sighash_ctor();
-                let sighash_tts: proc_macro2::TokenStream =
-                    format!("{:?}", sighash_arr).parse().unwrap();
+                let pattern = discriminator_pattern(&sighash_ctor());
                 quote! {
-                    #sighash_tts => {
+                    #pattern => {
                         __private::__state::__ctor(
                             program_id,
                             accounts,
@@ -38,11 +36,10 @@ pub fn generate(program: &Program) -> proc_macro2::TokenStream {
                         let name = &ix.raw_method.sig.ident.to_string();
                         let ix_method_name: proc_macro2::TokenStream =
                             { format!("__{}", name).parse().unwrap() };
-                        let sighash_arr = sighash(SIGHASH_STATE_NAMESPACE, name);
-                        let sighash_tts: proc_macro2::TokenStream =
-                            format!("{:?}", sighash_arr).parse().unwrap();
+                        let pattern =
+                            discriminator_pattern(&sighash(SIGHASH_STATE_NAMESPACE, name));
                         quote! {
-                            #sighash_tts => {
+                            #pattern => {
                                 __private::__state::#ix_method_name(
                                     program_id,
                                     accounts,
@@ -70,14 +67,15 @@ pub fn generate(program: &Program) -> proc_macro2::TokenStream {
                             .methods
                             .iter()
                             .map(|m: &crate::StateIx| {
-                                let sighash_arr = sighash(&iface.trait_name, &m.ident.to_string());
-                                let sighash_tts: proc_macro2::TokenStream =
-                                    format!("{:?}", sighash_arr).parse().unwrap();
+                                let pattern = discriminator_pattern(&sighash(
+                                    &iface.trait_name,
+                                    &m.ident.to_string(),
+                                ));
                                 let name = &m.raw_method.sig.ident.to_string();
                                 let ix_method_name: proc_macro2::TokenStream =
                                     format!("__{}_{}", iface.trait_name, name).parse().unwrap();
                                 quote! {
-                                    #sighash_tts => {
+                                    #pattern => {
                                         __private::__interface::#ix_method_name(
                                             program_id,
                                             accounts,
@@ -99,11 +97,9 @@ pub fn generate(program: &Program) -> proc_macro2::TokenStream {
         .iter()
         .map(|ix| {
             let ix_method_name = &ix.raw_method.sig.ident;
-            let sighash_arr = sighash(SIGHASH_GLOBAL_NAMESPACE, &ix_method_name.to_string());
-            let sighash_tts: proc_macro2::TokenStream =
-                format!("{:?}", sighash_arr).parse().unwrap();
+            let pattern = discriminator_pattern(&ix_discriminator(ix));
             quote! {
-                #sighash_tts => {
+                #pattern => {
                     __private::__global::#ix_method_name(
                         program_id,
                         accounts,
@@ -135,34 +131,31 @@ pub fn generate(program: &Program) -> proc_macro2::TokenStream {
         /// With this 8 byte identifier, Anchor performs method dispatch,
         /// matching the given 8 byte identifier to the associated method
         /// handler, which leads to user defined code being eventually invoked.
+        ///
+        /// Instructions given an explicit discriminator via
+        /// `#[instruction(discriminator = ..)]` are matched on those bytes
+        /// instead, which may be of any length.
         fn dispatch(
             program_id: &Pubkey,
             accounts: &[AccountInfo],
             data: &[u8],
         ) -> anchor_lang::Result<()> {
-            // Split the instruction data into the first 8 byte method
-            // identifier (sighash) and the serialized instruction data.
-            let mut ix_data: &[u8] = data;
-            let sighash: [u8; 8] = {
-                let mut sighash: [u8; 8] = [0; 8];
-                sighash.copy_from_slice(&ix_data[..8]);
-                ix_data = &ix_data[8..];
-                sighash
-            };
-
             // If the method identifier is the IDL tag, then execute an IDL
             // instruction, injected into all Anchor programs.
             if cfg!(not(feature = "no-idl")) {
-                if sighash == anchor_lang::idl::IDL_IX_TAG.to_le_bytes() {
+                let idl_tag = anchor_lang::idl::IDL_IX_TAG.to_le_bytes();
+                if data.starts_with(&idl_tag) {
                     return __private::__idl::__idl_dispatch(
                         program_id,
                         accounts,
-                        &ix_data,
+                        &data[idl_tag.len()..],
                     );
                 }
             }
 
-            match sighash {
+            // Split the instruction data into the method identifier and the
+            // serialized instruction data.
+            match data {
                 #ctor_state_dispatch_arm
                 #(#state_dispatch_arms)*
                 #(#trait_dispatch_arms)*
@@ -59,6 +59,20 @@ pub fn generate(program: &Program) -> proc_macro2::TokenStream {
                         __idl_set_buffer(program_id, &mut accounts)?;
                         accounts.exit(program_id)?;
                     },
+                    anchor_lang::idl::IdlInstruction::Resize { data_len } => {
+                        let mut bumps = std::collections::BTreeMap::new();
+                        let mut accounts =
+                            anchor_lang::idl::IdlResizeAccount::try_accounts(program_id, &mut accounts, &[], &mut bumps)?;
+                        __idl_resize_account(program_id, &mut accounts, data_len)?;
+                        accounts.exit(program_id)?;
+                    },
+                    anchor_lang::idl::IdlInstruction::Close => {
+                        let mut bumps = std::collections::BTreeMap::new();
+                        let mut accounts =
+                            anchor_lang::idl::IdlCloseAccount::try_accounts(program_id, &mut accounts, &[], &mut bumps)?;
+                        __idl_close_account(program_id, &mut accounts)?;
+                        accounts.exit(program_id)?;
+                    },
                 }
                 Ok(())
             }
@@ -89,8 +103,7 @@ pub fn generate(program: &Program) -> proc_macro2::TokenStream {
                 let seed = anchor_lang::idl::IdlAccount::seed();
                 let owner = accounts.program.key;
                 let to = Pubkey::create_with_seed(&base, seed, owner).unwrap();
-                // Space: account discriminator || authority pubkey || vec len || vec data
-                let space = 8 + 32 + 4 + data_len as usize;
+                let space = anchor_lang::idl::IdlAccount::try_space(data_len)?;
                 let rent = Rent::get()?;
                 let lamports = rent.minimum_balance(space);
                 let seeds = &[&[nonce][..]];
@@ -186,6 +199,64 @@ pub fn generate(program: &Program) -> proc_macro2::TokenStream {
                 accounts.idl.data = accounts.buffer.data.clone();
                 Ok(())
             }
+
+            #[inline(never)]
+            pub fn __idl_resize_account(
+                program_id: &Pubkey,
+                accounts: &mut anchor_lang::idl::IdlResizeAccount,
+                data_len: u64,
+            ) -> anchor_lang::Result<()> {
+                #[cfg(not(feature = "no-log-ix-name"))]
+                anchor_lang::prelude::msg!("Instruction: IdlResizeAccount");
+
+                let space = anchor_lang::idl::IdlAccount::try_space(data_len)?;
+                if space < anchor_lang::idl::IdlAccount::space(accounts.idl.data.len()) {
+                    return Err(anchor_lang::error::ErrorCode::IdlAccountTooSmall.into());
+                }
+
+                // Keep the account rent exempt, with the authority paying for
+                // the extra space or being refunded the space given up.
+                let info = accounts.idl.to_account_info();
+                let rent = Rent::get()?;
+                let lamports = rent.minimum_balance(space);
+                let current_lamports = info.lamports();
+                if lamports > current_lamports {
+                    let ix = anchor_lang::solana_program::system_instruction::transfer(
+                        accounts.authority.key,
+                        info.key,
+                        lamports - current_lamports,
+                    );
+                    anchor_lang::solana_program::program::invoke(
+                        &ix,
+                        &[
+                            accounts.authority.clone(),
+                            info.clone(),
+                            accounts.system_program.to_account_info(),
+                        ],
+                    )?;
+                } else {
+                    let authority_lamports = accounts.authority.lamports();
+                    **info.lamports.borrow_mut() = lamports;
+                    **accounts.authority.lamports.borrow_mut() = authority_lamports
+                        .checked_add(current_lamports - lamports)
+                        .unwrap();
+                }
+
+                info.realloc(space, false)?;
+                Ok(())
+            }
+
+            #[inline(never)]
+            pub fn __idl_close_account(
+                program_id: &Pubkey,
+                accounts: &mut anchor_lang::idl::IdlCloseAccount,
+            ) -> anchor_lang::Result<()> {
+                #[cfg(not(feature = "no-log-ix-name"))]
+                anchor_lang::prelude::msg!("Instruction: IdlCloseAccount");
+
+                // The account is closed by its `close` constraint on exit.
+                Ok(())
+            }
         }
     };
     // Constructor handler.
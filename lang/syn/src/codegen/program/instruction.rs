@@ -31,21 +31,13 @@ pub fn generate(program: &Program) -> proc_macro2::TokenStream {
                     }
                 }
             };
-            let sighash_arr = sighash_ctor();
-            let sighash_tts: proc_macro2::TokenStream =
-                format!("{:?}", sighash_arr).parse().unwrap();
+            let ix_data_trait = generate_ix_data_trait(quote! { New }, &sighash_ctor());
             quote! {
                 /// Instruction arguments to the `#[state]`'s `new`
                 /// constructor.
                 #strct
 
-                impl anchor_lang::InstructionData for New {
-                    fn data(&self) -> Vec<u8> {
-                        let mut d = #sighash_tts.to_vec();
-                        d.append(&mut self.try_to_vec().expect("Should always serialize"));
-                        d
-                    }
-                }
+                #ix_data_trait
             }
         }
     };
@@ -78,18 +70,10 @@ pub fn generate(program: &Program) -> proc_macro2::TokenStream {
 
                         let ix_data_trait = {
                             let name = method.raw_method.sig.ident.to_string();
-                            let sighash_arr = sighash(SIGHASH_STATE_NAMESPACE, &name);
-                            let sighash_tts: proc_macro2::TokenStream =
-                                format!("{:?}", sighash_arr).parse().unwrap();
-                            quote! {
-                                impl anchor_lang::InstructionData for #ix_name_camel {
-                                    fn data(&self) -> Vec<u8> {
-                                        let mut d = #sighash_tts.to_vec();
-                                        d.append(&mut self.try_to_vec().expect("Should always serialize"));
-                                        d
-                                    }
-                                }
-                            }
+                            generate_ix_data_trait(
+                                ix_name_camel.clone(),
+                                &sighash(SIGHASH_STATE_NAMESPACE, &name),
+                            )
                         };
 
                         // If no args, output a "unit" variant instead of a struct variant.
@@ -133,20 +117,8 @@ pub fn generate(program: &Program) -> proc_macro2::TokenStream {
                         .unwrap()
                 })
                 .collect();
-            let ix_data_trait = {
-                let sighash_arr = sighash(SIGHASH_GLOBAL_NAMESPACE, name);
-                let sighash_tts: proc_macro2::TokenStream =
-                    format!("{:?}", sighash_arr).parse().unwrap();
-                quote! {
-                    impl anchor_lang::InstructionData for #ix_name_camel {
-                        fn data(&self) -> Vec<u8> {
-                            let mut d = #sighash_tts.to_vec();
-                            d.append(&mut self.try_to_vec().expect("Should always serialize"));
-                            d
-                        }
-                    }
-                }
-            };
+            let ix_data_trait =
+                generate_ix_data_trait(quote! { #ix_name_camel }, &ix_discriminator(ix));
             // If no args, output a "unit" variant instead of a struct variant.
             if ix.args.is_empty() {
                 quote! {
@@ -192,3 +164,24 @@ pub fn generate(program: &Program) -> proc_macro2::TokenStream {
         }
     }
 }
+
+// Implements `Discriminator` and `InstructionData` for an instruction struct,
+// where the data is the discriminator followed by the serialized arguments.
+fn generate_ix_data_trait(
+    ix_name: proc_macro2::TokenStream,
+    discriminator: &[u8],
+) -> proc_macro2::TokenStream {
+    quote! {
+        impl anchor_lang::Discriminator for #ix_name {
+            const DISCRIMINATOR: &'static [u8] = &[#(#discriminator),*];
+        }
+
+        impl anchor_lang::InstructionData for #ix_name {
+            fn data(&self) -> Vec<u8> {
+                let mut d = <Self as anchor_lang::Discriminator>::DISCRIMINATOR.to_vec();
+                d.append(&mut self.try_to_vec().expect("Should always serialize"));
+                d
+            }
+        }
+    }
+}
@@ -20,6 +20,11 @@ pub fn generate(program: &Program) -> proc_macro2::TokenStream {
     let cpi = cpi::generate(program);
     let accounts = accounts::generate(program);
 
+    #[cfg(feature = "idl-build")]
+    let idl_build = crate::idl::build::gen_idl_print_fn_program(program);
+    #[cfg(not(feature = "idl-build"))]
+    let idl_build = quote! {};
+
     quote! {
         // TODO: remove once we allow segmented paths in `Accounts` structs.
         use self::#mod_name::*;
@@ -31,5 +36,6 @@ pub fn generate(program: &Program) -> proc_macro2::TokenStream {
         #instruction
         #cpi
         #accounts
+        #idl_build
     }
 }
@@ -0,0 +1,709 @@
+//! IDL generation by compiling the program, rather than parsing its source.
+//!
+//! With the `idl-build` feature, the `#[program]`, `#[account]`, `#[event]`,
+//! `#[error_code]` and `#[derive(Accounts)]` macros generate tests printing
+//! fragments of the IDL. Since the types in those fragments are resolved by
+//! the compiler, they include macro generated items, respect `#[cfg]`
+//! attributes and evaluate array lengths such as `[u8; 2 * N]`.
+//!
+//! `anchor build` runs the tests and merges the fragments, completing the IDL
+//! with the source parser for what can't be known by compiling, e.g. PDAs and
+//! the definitions of types that don't implement `IdlBuild`.
+
+use crate::idl::file::{add_missing_types, find_ty_def, payer, relations, static_pubkey};
+use crate::idl::spec::IDL_SPEC;
+use crate::idl::*;
+use crate::parser::context::CrateContext;
+use crate::{AccountArgs, AccountField, AccountsStruct, Error, Program, Ty};
+use heck::{MixedCase, SnakeCase};
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+
+// Prefix of the names of the generated tests printing IDL fragments.
+pub const IDL_BUILD_TEST_PREFIX: &str = "__anchor_private_print_idl";
+
+const FRAGMENT_BEGIN: &str = "--- IDL fragment begin ---";
+const FRAGMENT_END: &str = "--- IDL fragment end ---";
+
+// Prints an IDL fragment, to be picked up by `from_fragments`.
+pub fn print_fragment(idl: &Idl) {
+    println!(
+        "{}\n{}\n{}",
+        FRAGMENT_BEGIN,
+        serde_json::to_string(idl).unwrap(),
+        FRAGMENT_END
+    );
+}
+
+// Merges the fragments printed by the generated tests into the program's IDL.
+// Returns `None` if the output doesn't contain a program.
+pub fn from_fragments(output: &str) -> anyhow::Result<Option<Idl>> {
+    let mut fragments = vec![];
+    let mut lines = output.lines().map(str::trim);
+    while let Some(line) = lines.next() {
+        if line == FRAGMENT_BEGIN {
+            let json: String = lines.by_ref().take_while(|l| *l != FRAGMENT_END).collect();
+            fragments.push(serde_json::from_str::<Idl>(&json)?);
+        }
+    }
+
+    // Only the program fragment is named.
+    let mut idl = match fragments.iter().position(|f| !f.name.is_empty()) {
+        None => return Ok(None),
+        Some(idx) => fragments.remove(idx),
+    };
+    idl.spec = IDL_SPEC.to_string();
+    for fragment in fragments {
+        idl.accounts.extend(fragment.accounts);
+        if let Some(events) = fragment.events {
+            idl.events.get_or_insert_with(Vec::new).extend(events);
+        }
+        if let Some(errors) = fragment.errors {
+            idl.errors.get_or_insert_with(Vec::new).extend(errors);
+        }
+        for ty_def in fragment.types {
+            if !idl.types.iter().any(|t| t.name == ty_def.name) {
+                idl.types.push(ty_def);
+            }
+        }
+    }
+
+    // Accounts and events used as fields are defined in their own sections.
+    let accounts = &idl.accounts;
+    let events = idl.events.iter().flatten();
+    let defined: Vec<&String> = accounts
+        .iter()
+        .map(|a| &a.name)
+        .chain(events.map(|e| &e.name))
+        .collect();
+    idl.types.retain(|ty_def| !defined.contains(&&ty_def.name));
+
+    Ok(Some(idl))
+}
+
+// Completes an IDL built from fragments with what only the source parser
+// knows: the state, constants, previous layouts of versioned accounts and PDAs,
+// from the `parsed` IDL if the program could be parsed, and the definitions of
+// types that don't implement `IdlBuild`, found in `ctx`, and sets the sizes of
+// the types. Errors if a type used by the IDL is still undefined.
+pub fn complete(idl: &mut Idl, ctx: &CrateContext, parsed: Option<Idl>) -> anyhow::Result<()> {
+    if let Some(parsed) = parsed {
+        complete_from_parsed(idl, parsed);
+    }
+    add_missing_types(idl, |name| find_ty_def(ctx, name))?;
+    size::set_sizes(idl);
+    Ok(())
+}
+
+fn complete_from_parsed(idl: &mut Idl, parsed: Idl) {
+    if idl.state.is_none() {
+        idl.state = parsed.state;
+    }
+    if idl.constants.is_empty() {
+        idl.constants = parsed.constants;
+    }
+    if idl.errors.is_none() {
+        idl.errors = parsed.errors;
+    }
+    for account in &mut idl.accounts {
+        if let Some(p) = parsed.accounts.iter().find(|p| p.name == account.name) {
+            if account.history.is_empty() {
+                account.history = p.history.clone();
+            }
+        }
+    }
+    // Attributes aren't visible to `IdlBuild`.
+    let parsed_ty_defs: Vec<&IdlTypeDefinition> =
+        parsed.accounts.iter().chain(&parsed.types).collect();
+    for ty_def in idl.accounts.iter_mut().chain(&mut idl.types) {
+        if let Some(p) = parsed_ty_defs.iter().find(|p| p.name == ty_def.name) {
+            if ty_def.repr.is_none() {
+                ty_def.repr = p.repr.clone();
+            }
+            ty_def.zero_copy |= p.zero_copy;
+        }
+    }
+    for ix in &mut idl.instructions {
+        if let Some(p) = parsed.instructions.iter().find(|p| p.name == ix.name) {
+            copy_pdas(&mut ix.accounts, &p.accounts);
+        }
+    }
+}
+
+fn copy_pdas(accounts: &mut [IdlAccountItem], parsed: &[IdlAccountItem]) {
+    for (account, p) in accounts.iter_mut().zip(parsed) {
+        match (account, p) {
+            (IdlAccountItem::IdlAccount(account), IdlAccountItem::IdlAccount(p))
+                if account.name == p.name && account.pda.is_none() =>
+            {
+                account.pda = p.pda.clone();
+            }
+            (IdlAccountItem::IdlAccounts(accounts), IdlAccountItem::IdlAccounts(p))
+                if accounts.name == p.name =>
+            {
+                copy_pdas(&mut accounts.accounts, &p.accounts);
+            }
+            _ => {}
+        }
+    }
+}
+
+// The IDL type of `ty`, inserting the types it uses into `types`, which must
+// be in scope. Types implementing `IdlBuild` are resolved by the compiler,
+// others are parsed from their source, leaving their definition to the source
+// parser.
+fn gen_idl_type(ty: &syn::Type) -> TokenStream {
+    quote! {{
+        #[allow(unused_imports)]
+        use anchor_lang::idl_build::{IdlTypeViaBuild as _, IdlTypeViaSource as _};
+        (&anchor_lang::idl_build::IdlTypeOf::<#ty>(std::marker::PhantomData))
+            .idl_type(types, stringify!(#ty))
+    }}
+}
+
+// Collects the given fields into a `Vec`, keeping the `#[cfg]` attributes of
+// each field, which aren't evaluated before attribute macros.
+fn gen_fields_vec<'a>(
+    fields: impl IntoIterator<Item = &'a syn::Field>,
+    gen_field: impl Fn(&syn::Field) -> TokenStream,
+) -> TokenStream {
+    let pushes = fields.into_iter().map(|f| {
+        let cfgs = f.attrs.iter().filter(|attr| attr.path.is_ident("cfg"));
+        let field = gen_field(f);
+        quote! {
+            #(#cfgs)*
+            fields.push(#field);
+        }
+    });
+    quote! {{
+        #[allow(unused_mut)]
+        let mut fields = vec![];
+        #(#pushes)*
+        fields
+    }}
+}
+
+fn gen_idl_field(f: &syn::Field) -> TokenStream {
+    let name = f.ident.as_ref().unwrap().to_string().to_mixed_case();
+    let ty = gen_idl_type(&f.ty);
+    quote! {
+        anchor_lang::idl_build::IdlField {
+            name: #name.into(),
+            ty: #ty,
+        }
+    }
+}
+
+fn gen_discriminator(bytes: Option<&Vec<u8>>) -> TokenStream {
+    match bytes {
+        None => quote! { None },
+        Some(bytes) => quote! { Some(vec![#(#bytes),*]) },
+    }
+}
+
+// Implements `IdlBuild` for a struct. Generic structs are left to the source
+// parser.
+pub fn gen_idl_build_impl(strct: &syn::ItemStruct) -> TokenStream {
+    if !strct.generics.params.is_empty() {
+        return quote! {};
+    }
+    let ident = &strct.ident;
+    let name = ident.to_string();
+    // Named so that it's not reported as unused for structs without fields.
+    let types = match strct.fields.is_empty() {
+        true => format_ident!("_types"),
+        false => format_ident!("types"),
+    };
+    let ty = match &strct.fields {
+        syn::Fields::Named(fields) => {
+            let fields = gen_fields_vec(&fields.named, gen_idl_field);
+            quote! {
+                anchor_lang::idl_build::IdlTypeDefinitionTy::Struct { fields: #fields }
+            }
+        }
+        syn::Fields::Unnamed(fields) => {
+            let fields = gen_fields_vec(&fields.unnamed, |f| gen_idl_type(&f.ty));
+            quote! {
+                anchor_lang::idl_build::IdlTypeDefinitionTy::Tuple { fields: #fields }
+            }
+        }
+        syn::Fields::Unit => quote! {
+            anchor_lang::idl_build::IdlTypeDefinitionTy::Struct { fields: vec![] }
+        },
+    };
+
+    quote! {
+        #[automatically_derived]
+        impl anchor_lang::idl_build::IdlBuild for #ident {
+            fn idl_type() -> anchor_lang::idl_build::IdlType {
+                anchor_lang::idl_build::IdlType::Defined(#name.into())
+            }
+
+            fn insert_types(types: &mut anchor_lang::idl_build::IdlTypes) {
+                anchor_lang::idl_build::insert_type_def(types, #name, |#types| #ty);
+            }
+        }
+    }
+}
+
+// Generates the test printing the IDL fragment of an account.
+pub fn gen_idl_print_fn_account(strct: &syn::ItemStruct, args: &AccountArgs) -> TokenStream {
+    if !strct.generics.params.is_empty() {
+        return quote! {};
+    }
+    let ident = &strct.ident;
+    let name = ident.to_string();
+    let fn_name = format_ident!("{}_account_{}", IDL_BUILD_TEST_PREFIX, name.to_snake_case());
+    let version = match &args.version {
+        None => quote! { None },
+        Some(version) => {
+            let version = version.version;
+            quote! { Some(#version) }
+        }
+    };
+
+    quote! {
+        #[test]
+        fn #fn_name() {
+            let types = &mut anchor_lang::idl_build::IdlTypes::new();
+            <#ident as anchor_lang::idl_build::IdlBuild>::insert_types(types);
+            let mut account = types.remove(#name).unwrap();
+            account.discriminator =
+                Some(<#ident as anchor_lang::Discriminator>::DISCRIMINATOR.to_vec());
+            account.version = #version;
+            anchor_lang::idl_build::print_fragment(&anchor_lang::idl_build::Idl {
+                accounts: vec![account],
+                types: types.values().cloned().collect(),
+                ..Default::default()
+            });
+        }
+    }
+}
+
+// Generates the test printing the IDL fragment of an event.
+pub fn gen_idl_print_fn_event(strct: &syn::ItemStruct) -> TokenStream {
+    let fields = match &strct.fields {
+        syn::Fields::Named(fields) if strct.generics.params.is_empty() => fields,
+        _ => return quote! {},
+    };
+    let ident = &strct.ident;
+    let name = ident.to_string();
+    let fn_name = format_ident!("{}_event_{}", IDL_BUILD_TEST_PREFIX, name.to_snake_case());
+    let fields = gen_fields_vec(&fields.named, |f| {
+        let name = f.ident.as_ref().unwrap().to_string().to_mixed_case();
+        let ty = gen_idl_type(&f.ty);
+        let index = f.attrs.iter().any(|attr| attr.path.is_ident("index"));
+        quote! {
+            anchor_lang::idl_build::IdlEventField {
+                name: #name.into(),
+                ty: #ty,
+                index: #index,
+            }
+        }
+    });
+
+    quote! {
+        #[test]
+        fn #fn_name() {
+            let types = &mut anchor_lang::idl_build::IdlTypes::new();
+            let event = anchor_lang::idl_build::IdlEvent {
+                name: #name.into(),
+                fields: #fields,
+                discriminator: <#ident as anchor_lang::Discriminator>::DISCRIMINATOR.to_vec(),
+            };
+            anchor_lang::idl_build::print_fragment(&anchor_lang::idl_build::Idl {
+                events: Some(vec![event]),
+                types: types.values().cloned().collect(),
+                ..Default::default()
+            });
+        }
+    }
+}
+
+// Generates the test printing the IDL fragment of an error enum.
+pub fn gen_idl_print_fn_errors(error: &Error) -> TokenStream {
+    let ident = &error.ident;
+    let fn_name = format_ident!(
+        "{}_errors_{}",
+        IDL_BUILD_TEST_PREFIX,
+        ident.to_string().to_snake_case()
+    );
+    let codes = error
+        .raw_enum
+        .variants
+        .iter()
+        .zip(&error.codes)
+        .map(|(variant, code)| {
+            let variant = &variant.ident;
+            let name = variant.to_string();
+            let msg = match &code.msg {
+                None => quote! { None },
+                Some(msg) => quote! { Some(#msg.into()) },
+            };
+            quote! {
+                anchor_lang::idl_build::IdlErrorCode {
+                    code: u32::from(#ident::#variant),
+                    name: #name.into(),
+                    msg: #msg,
+                }
+            }
+        });
+
+    quote! {
+        #[test]
+        fn #fn_name() {
+            anchor_lang::idl_build::print_fragment(&anchor_lang::idl_build::Idl {
+                errors: Some(vec![#(#codes),*]),
+                ..Default::default()
+            });
+        }
+    }
+}
+
+// Implements `IdlBuildAccounts` for an accounts struct.
+pub fn gen_idl_build_impl_accounts(accs: &AccountsStruct) -> TokenStream {
+    let ident = &accs.ident;
+    let (impl_gen, ty_gen, where_clause) = accs.generics.split_for_impl();
+    let accounts = accs.fields.iter().map(|acc| match acc {
+        AccountField::CompositeField(comp_f) => {
+            let name = comp_f.ident.to_string().to_mixed_case();
+            let ty = &comp_f.raw_field.ty;
+            quote! {
+                anchor_lang::idl_build::IdlAccountItem::IdlAccounts(
+                    anchor_lang::idl_build::IdlAccounts {
+                        name: #name.into(),
+                        accounts: <#ty as anchor_lang::idl_build::IdlBuildAccounts>::idl_accounts(),
+                    }
+                )
+            }
+        }
+        AccountField::Field(acc) => {
+            let name = acc.ident.to_string().to_mixed_case();
+            let is_mut = acc.constraints.is_mutable();
+            let is_signer = match acc.ty {
+                Ty::Signer => true,
+                _ => acc.constraints.is_signer(),
+            };
+            let relations = relations(acc);
+            // Constant addresses and owners are evaluated by the compiler.
+            let gen_pubkey =
+                |expr: Option<&syn::Expr>| match expr.and_then(|expr| static_pubkey(accs, expr)) {
+                    Some(path) => quote! { Some((#path).to_string()) },
+                    None => quote! { None },
+                };
+            let address = gen_pubkey(acc.constraints.address.as_ref().map(|c| &c.address));
+            let owner = gen_pubkey(acc.constraints.owner.as_ref().map(|c| &c.owner_address));
+            let payer = match payer(acc) {
+                Some(payer) => quote! { Some(#payer.into()) },
+                None => quote! { None },
+            };
+            quote! {
+                anchor_lang::idl_build::IdlAccountItem::IdlAccount(
+                    anchor_lang::idl_build::IdlAccount {
+                        name: #name.into(),
+                        is_mut: #is_mut,
+                        is_signer: #is_signer,
+                        pda: None,
+                        relations: vec![#(#relations.to_string()),*],
+                        address: #address,
+                        owner: #owner,
+                        payer: #payer,
+                    }
+                )
+            }
+        }
+    });
+
+    quote! {
+        #[automatically_derived]
+        impl #impl_gen anchor_lang::idl_build::IdlBuildAccounts for #ident #ty_gen #where_clause {
+            fn idl_accounts() -> Vec<anchor_lang::idl_build::IdlAccountItem> {
+                vec![#(#accounts),*]
+            }
+        }
+    }
+}
+
+// Generates the test printing the IDL fragment of the program, i.e. its
+// instructions and the types they use.
+pub fn gen_idl_print_fn_program(program: &Program) -> TokenStream {
+    let fn_name = format_ident!("{}_program", IDL_BUILD_TEST_PREFIX);
+    let name = program.name.to_string();
+    let instructions = program.ixs.iter().map(|ix| {
+        let name = ix.ident.to_string().to_mixed_case();
+        let anchor_ident = &ix.anchor_ident;
+        let args = ix.args.iter().map(|arg| {
+            let name = arg.name.to_string().to_mixed_case();
+            let ty = gen_idl_type(&arg.raw_arg.ty);
+            quote! {
+                anchor_lang::idl_build::IdlField {
+                    name: #name.into(),
+                    ty: #ty,
+                }
+            }
+        });
+        let discriminator = gen_discriminator(ix.discriminator.as_ref().map(|d| &d.bytes));
+        quote! {
+            anchor_lang::idl_build::IdlInstruction {
+                name: #name.into(),
+                accounts: <#anchor_ident as anchor_lang::idl_build::IdlBuildAccounts>::idl_accounts(),
+                args: vec![#(#args),*],
+                discriminator: #discriminator,
+            }
+        }
+    });
+
+    quote! {
+        #[test]
+        fn #fn_name() {
+            let types = &mut anchor_lang::idl_build::IdlTypes::new();
+            let instructions = vec![#(#instructions),*];
+            anchor_lang::idl_build::print_fragment(&anchor_lang::idl_build::Idl {
+                name: #name.into(),
+                instructions,
+                types: types.values().cloned().collect(),
+                ..Default::default()
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fragment(idl: Idl) -> String {
+        format!(
+            "{}\n{}\n{}\n",
+            FRAGMENT_BEGIN,
+            serde_json::to_string(&idl).unwrap(),
+            FRAGMENT_END
+        )
+    }
+
+    fn ty_def(name: &str) -> IdlTypeDefinition {
+        IdlTypeDefinition {
+            name: name.into(),
+            generics: vec![],
+            ty: IdlTypeDefinitionTy::Struct { fields: vec![] },
+            version: None,
+            history: vec![],
+            discriminator: None,
+            repr: None,
+            zero_copy: false,
+            size: None,
+        }
+    }
+
+    #[test]
+    fn merge_fragments() {
+        let program = Idl {
+            name: "example".into(),
+            types: vec![ty_def("Args"), ty_def("Data")],
+            ..Default::default()
+        };
+        let account = Idl {
+            accounts: vec![ty_def("Data")],
+            types: vec![ty_def("Args")],
+            ..Default::default()
+        };
+        let output = format!(
+            "running 2 tests\n{}unrelated output\n{}",
+            fragment(account),
+            fragment(program)
+        );
+
+        let idl = from_fragments(&output).unwrap().unwrap();
+        assert_eq!(idl.name, "example");
+        assert_eq!(idl.accounts, vec![ty_def("Data")]);
+        assert_eq!(idl.types, vec![ty_def("Args")]);
+
+        assert_eq!(from_fragments("running 0 tests").unwrap(), None);
+    }
+
+    #[test]
+    fn evaluates_constant_addresses_only() {
+        let strct: syn::ItemStruct = syn::parse_quote! {
+            #[derive(Accounts)]
+            #[instruction(expected: Pubkey)]
+            pub struct Check<'info> {
+                #[account(address = expected)]
+                pub checked: AccountInfo<'info>,
+                #[account(address = ADMIN, owner = crate::ID)]
+                pub admin: AccountInfo<'info>,
+            }
+        };
+        let accs = crate::parser::accounts::parse(&strct).unwrap();
+        let tokens = gen_idl_build_impl_accounts(&accs).to_string();
+        assert!(!tokens.contains("expected"));
+        assert!(tokens.contains("address : Some ((ADMIN) . to_string ())"));
+        assert!(tokens.contains("owner : Some ((crate :: ID) . to_string ())"));
+    }
+
+    #[test]
+    fn builds_idl_from_fragments_and_source() {
+        let root = std::env::temp_dir().join(format!("anchor-idl-build-{}.rs", std::process::id()));
+        std::fs::write(
+            &root,
+            r#"
+            use anchor_lang::prelude::*;
+
+            declare_id!("Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS");
+
+            pub const SEED: &[u8] = b"vault";
+
+            #[program]
+            pub mod example {
+                use super::*;
+
+                pub fn initialize(ctx: Context<Initialize>, args: Args) -> Result<()> {
+                    Ok(())
+                }
+            }
+
+            #[derive(Accounts)]
+            pub struct Initialize<'info> {
+                #[account(init, payer = authority, space = 8 + 40, seeds = [SEED, authority.key().as_ref()], bump)]
+                pub vault: Account<'info, Vault>,
+                #[account(mut)]
+                pub authority: Signer<'info>,
+                pub system_program: Program<'info, System>,
+            }
+
+            #[account]
+            pub struct Vault {
+                pub authority: Pubkey,
+                pub amount: u64,
+            }
+
+            #[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+            pub struct Args {
+                pub amount: u64,
+                pub side: Side,
+            }
+
+            #[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+            pub enum Side {
+                Bid,
+                Ask,
+            }
+
+            #[error_code]
+            pub enum ErrorCode {
+                #[msg("Amount too large")]
+                TooLarge,
+            }
+            "#,
+        )
+        .unwrap();
+        let ctx = CrateContext::parse(&root).unwrap();
+        let parsed = crate::idl::file::parse(&root, "0.1.0".into(), false, &[]).unwrap();
+        std::fs::remove_file(&root).unwrap();
+
+        // The fragments printed by the tests generated for the program above,
+        // where `Args` doesn't implement `IdlBuild`.
+        let program = serde_json::json!({
+            "version": "",
+            "name": "example",
+            "instructions": [{
+                "name": "initialize",
+                "accounts": [
+                    { "name": "vault", "isMut": true, "isSigner": false, "payer": "authority" },
+                    { "name": "authority", "isMut": true, "isSigner": true },
+                    {
+                        "name": "systemProgram",
+                        "isMut": false,
+                        "isSigner": false,
+                        "address": "11111111111111111111111111111111",
+                    },
+                ],
+                "args": [{ "name": "args", "type": { "defined": "Args" } }],
+            }],
+        });
+        let account = serde_json::json!({
+            "version": "",
+            "name": "",
+            "instructions": [],
+            "accounts": [{
+                "name": "Vault",
+                "type": { "kind": "struct", "fields": [
+                    { "name": "authority", "type": "publicKey" },
+                    { "name": "amount", "type": "u64" },
+                ]},
+                "discriminator": [211, 8, 232, 43, 2, 152, 117, 119],
+            }],
+        });
+        let errors = serde_json::json!({
+            "version": "",
+            "name": "",
+            "instructions": [],
+            "errors": [{ "code": 6000, "name": "TooLarge", "msg": "Amount too large" }],
+        });
+        let output: String = vec![account, program, errors]
+            .into_iter()
+            .map(|json| fragment(serde_json::from_value(json).unwrap()))
+            .collect();
+
+        let mut idl = from_fragments(&output).unwrap().unwrap();
+        idl.version = "0.1.0".into();
+        complete(&mut idl, &ctx, parsed).unwrap();
+        // The PDA comes from the source, as do the definitions of the types
+        // the compiler left undefined.
+        assert_eq!(
+            serde_json::to_value(&idl).unwrap(),
+            serde_json::json!({
+                "spec": IDL_SPEC,
+                "version": "0.1.0",
+                "name": "example",
+                "instructions": [{
+                    "name": "initialize",
+                    "accounts": [
+                        {
+                            "name": "vault",
+                            "isMut": true,
+                            "isSigner": false,
+                            "pda": { "seeds": [
+                                { "kind": "const", "type": "bytes", "value": [118, 97, 117, 108, 116] },
+                                { "kind": "account", "type": "publicKey", "path": "authority" },
+                            ]},
+                            "payer": "authority",
+                        },
+                        { "name": "authority", "isMut": true, "isSigner": true },
+                        {
+                            "name": "systemProgram",
+                            "isMut": false,
+                            "isSigner": false,
+                            "address": "11111111111111111111111111111111",
+                        },
+                    ],
+                    "args": [{ "name": "args", "type": { "defined": "Args" } }],
+                }],
+                "accounts": [{
+                    "name": "Vault",
+                    "type": { "kind": "struct", "fields": [
+                        { "name": "authority", "type": "publicKey" },
+                        { "name": "amount", "type": "u64" },
+                    ]},
+                    "discriminator": [211, 8, 232, 43, 2, 152, 117, 119],
+                    "size": 48,
+                }],
+                "types": [
+                    {
+                        "name": "Args",
+                        "type": { "kind": "struct", "fields": [
+                            { "name": "amount", "type": "u64" },
+                            { "name": "side", "type": { "defined": "Side" } },
+                        ]},
+                        "size": 9,
+                    },
+                    {
+                        "name": "Side",
+                        "type": { "kind": "enum", "variants": [{ "name": "Bid" }, { "name": "Ask" }] },
+                        "size": 1,
+                    },
+                ],
+                "errors": [{ "code": 6000, "name": "TooLarge", "msg": "Amount too large" }],
+            })
+        );
+    }
+}
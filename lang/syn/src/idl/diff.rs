@@ -0,0 +1,697 @@
+//! Comparison of two versions of a program's IDL, classifying each change by
+//! whether it breaks existing clients or the data stored by the program.
+
+use crate::codegen::program::common::{sighash, SIGHASH_GLOBAL_NAMESPACE, SIGHASH_STATE_NAMESPACE};
+use crate::idl::*;
+use heck::SnakeCase;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum IdlChange {
+    // Breaks clients built against the old IDL, or the accounts stored by the
+    // old program.
+    Breaking(String),
+    Compatible(String),
+}
+
+impl IdlChange {
+    pub fn is_breaking(&self) -> bool {
+        matches!(self, IdlChange::Breaking(_))
+    }
+}
+
+impl fmt::Display for IdlChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IdlChange::Breaking(change) | IdlChange::Compatible(change) => f.write_str(change),
+        }
+    }
+}
+
+// Lists the changes from the `old` IDL to the `new` one.
+pub fn diff(old: &Idl, new: &Idl) -> Vec<IdlChange> {
+    let mut changes = Changes::default();
+    diff_instructions(
+        "instruction",
+        SIGHASH_GLOBAL_NAMESPACE,
+        &old.instructions,
+        &new.instructions,
+        &mut changes,
+    );
+    match (&old.state, &new.state) {
+        (Some(old), Some(new)) => {
+            diff_ty_def("state", &old.strct, &new.strct, &mut changes);
+            diff_instructions(
+                "state method",
+                SIGHASH_STATE_NAMESPACE,
+                &old.methods,
+                &new.methods,
+                &mut changes,
+            );
+        }
+        (Some(_), None) => changes.breaking("state removed".to_string()),
+        (None, Some(_)) => changes.compatible("state added".to_string()),
+        (None, None) => {}
+    }
+    diff_ty_defs(
+        "account",
+        Some("account"),
+        &old.accounts,
+        &new.accounts,
+        &mut changes,
+    );
+    diff_ty_defs("type", None, &old.types, &new.types, &mut changes);
+    diff_events(
+        old.events.as_deref().unwrap_or_default(),
+        new.events.as_deref().unwrap_or_default(),
+        &mut changes,
+    );
+    diff_errors(
+        old.errors.as_deref().unwrap_or_default(),
+        new.errors.as_deref().unwrap_or_default(),
+        &mut changes,
+    );
+    changes.0
+}
+
+#[derive(Default)]
+struct Changes(Vec<IdlChange>);
+
+impl Changes {
+    fn breaking(&mut self, change: String) {
+        self.0.push(IdlChange::Breaking(change));
+    }
+
+    fn compatible(&mut self, change: String) {
+        self.0.push(IdlChange::Compatible(change));
+    }
+}
+
+// How a list of fields may change without breaking clients.
+struct FieldRules {
+    what: &'static str,
+    // Whether renaming a field breaks clients, i.e. they refer to it by name.
+    renames_break: bool,
+    // Whether appending a field breaks clients, i.e. they must provide it.
+    additions_break: bool,
+}
+
+// Instruction arguments are given by position, and must all be provided.
+const ARGS: FieldRules = FieldRules {
+    what: "argument",
+    renames_break: false,
+    additions_break: true,
+};
+
+// Account data must be decoded entirely, in the new layout.
+const FIELDS: FieldRules = FieldRules {
+    what: "field",
+    renames_break: true,
+    additions_break: true,
+};
+
+// Events are only decoded by clients, which ignore trailing fields.
+const EVENT_FIELDS: FieldRules = FieldRules {
+    what: "field",
+    renames_break: true,
+    additions_break: false,
+};
+
+fn diff_instructions(
+    kind: &str,
+    namespace: &str,
+    old: &[IdlInstruction],
+    new: &[IdlInstruction],
+    changes: &mut Changes,
+) {
+    for old_ix in old {
+        let ctx = format!("{} `{}`", kind, old_ix.name);
+        let new_ix = match new.iter().find(|ix| ix.name == old_ix.name) {
+            Some(new_ix) => new_ix,
+            None => {
+                changes.breaking(format!("{} removed", ctx));
+                continue;
+            }
+        };
+        if ix_discriminator(namespace, old_ix) != ix_discriminator(namespace, new_ix) {
+            changes.breaking(format!("{}: discriminator changed", ctx));
+        }
+        diff_accounts(&ctx, &old_ix.accounts, &new_ix.accounts, changes);
+        diff_fields(&ctx, &ARGS, &old_ix.args, &new_ix.args, changes);
+    }
+    for new_ix in new {
+        if !old.iter().any(|ix| ix.name == new_ix.name) {
+            changes.compatible(format!("{} `{}` added", kind, new_ix.name));
+        }
+    }
+}
+
+fn diff_accounts(ctx: &str, old: &[IdlAccountItem], new: &[IdlAccountItem], changes: &mut Changes) {
+    let old = flatten_accounts(old, "");
+    let new = flatten_accounts(new, "");
+    for (idx, old_acc) in old.iter().enumerate() {
+        let new_acc = match new.get(idx) {
+            Some(new_acc) => new_acc,
+            None => {
+                changes.breaking(format!("{}: account `{}` removed", ctx, old_acc.name));
+                continue;
+            }
+        };
+        if new_acc.name != old_acc.name {
+            match new.iter().position(|acc| acc.name == old_acc.name) {
+                Some(new_idx) => changes.breaking(format!(
+                    "{}: account `{}` moved from position {} to {}",
+                    ctx, old_acc.name, idx, new_idx
+                )),
+                None => changes.breaking(format!(
+                    "{}: account `{}` replaced by `{}`",
+                    ctx, old_acc.name, new_acc.name
+                )),
+            }
+            continue;
+        }
+        match (old_acc.is_mut, new_acc.is_mut) {
+            (false, true) => changes.breaking(format!(
+                "{}: account `{}` is now mutable",
+                ctx, old_acc.name
+            )),
+            (true, false) => changes.compatible(format!(
+                "{}: account `{}` is no longer mutable",
+                ctx, old_acc.name
+            )),
+            _ => {}
+        }
+        match (old_acc.is_signer, new_acc.is_signer) {
+            (false, true) => changes.breaking(format!(
+                "{}: account `{}` is now a signer",
+                ctx, old_acc.name
+            )),
+            (true, false) => changes.compatible(format!(
+                "{}: account `{}` is no longer a signer",
+                ctx, old_acc.name
+            )),
+            _ => {}
+        }
+    }
+    for new_acc in new.iter().skip(old.len()) {
+        changes.breaking(format!("{}: account `{}` added", ctx, new_acc.name));
+    }
+}
+
+// Flattens nested accounts into the list given to the instruction, naming
+// them by their path, e.g. `transfer.from`.
+fn flatten_accounts(items: &[IdlAccountItem], prefix: &str) -> Vec<IdlAccount> {
+    items
+        .iter()
+        .flat_map(|item| match item {
+            IdlAccountItem::IdlAccount(acc) => vec![IdlAccount {
+                name: format!("{}{}", prefix, acc.name),
+                ..acc.clone()
+            }],
+            IdlAccountItem::IdlAccounts(accs) => {
+                flatten_accounts(&accs.accounts, &format!("{}{}.", prefix, accs.name))
+            }
+        })
+        .collect()
+}
+
+fn diff_fields(
+    ctx: &str,
+    rules: &FieldRules,
+    old: &[IdlField],
+    new: &[IdlField],
+    changes: &mut Changes,
+) {
+    let what = rules.what;
+    for (idx, old_field) in old.iter().enumerate() {
+        let new_field = match new.get(idx) {
+            Some(new_field) => new_field,
+            None => {
+                changes.breaking(format!("{}: {} `{}` removed", ctx, what, old_field.name));
+                continue;
+            }
+        };
+        if new_field.name != old_field.name {
+            if let Some(new_idx) = new.iter().position(|f| f.name == old_field.name) {
+                changes.breaking(format!(
+                    "{}: {} `{}` moved from position {} to {}",
+                    ctx, what, old_field.name, idx, new_idx
+                ));
+                continue;
+            }
+            let change = format!(
+                "{}: {} `{}` renamed to `{}`",
+                ctx, what, old_field.name, new_field.name
+            );
+            match rules.renames_break {
+                true => changes.breaking(change),
+                false => changes.compatible(change),
+            }
+        }
+        if new_field.ty != old_field.ty {
+            changes.breaking(format!(
+                "{}: {} `{}` changed type from {} to {}",
+                ctx,
+                what,
+                old_field.name,
+                ty_str(&old_field.ty),
+                ty_str(&new_field.ty)
+            ));
+        }
+    }
+    for new_field in new.iter().skip(old.len()) {
+        let change = format!("{}: {} `{}` added", ctx, what, new_field.name);
+        match rules.additions_break {
+            true => changes.breaking(change),
+            false => changes.compatible(change),
+        }
+    }
+}
+
+fn diff_types(ctx: &str, old: &[IdlType], new: &[IdlType], changes: &mut Changes) {
+    let named = |types: &[IdlType]| -> Vec<IdlField> {
+        types
+            .iter()
+            .enumerate()
+            .map(|(idx, ty)| IdlField {
+                name: idx.to_string(),
+                ty: ty.clone(),
+            })
+            .collect()
+    };
+    let rules = FieldRules {
+        what: "field",
+        ..FIELDS
+    };
+    diff_fields(ctx, &rules, &named(old), &named(new), changes);
+}
+
+// Compares type definitions by name, with `namespace` deriving their
+// discriminator if they're stored in accounts.
+fn diff_ty_defs(
+    kind: &str,
+    namespace: Option<&str>,
+    old: &[IdlTypeDefinition],
+    new: &[IdlTypeDefinition],
+    changes: &mut Changes,
+) {
+    for old_def in old {
+        let ctx = format!("{} `{}`", kind, old_def.name);
+        let new_def = match new.iter().find(|def| def.name == old_def.name) {
+            Some(new_def) => new_def,
+            None => {
+                changes.breaking(format!("{} removed", ctx));
+                continue;
+            }
+        };
+        if let Some(namespace) = namespace {
+            let discriminator = |def: &IdlTypeDefinition| {
+                discriminator(namespace, &def.name, def.discriminator.as_ref())
+            };
+            if discriminator(old_def) != discriminator(new_def) {
+                changes.breaking(format!("{}: discriminator changed", ctx));
+            }
+        }
+        // Versioned accounts in an older layout are migrated by the program.
+        let migrated = new_def
+            .history
+            .iter()
+            .any(|v| Some(v.version) == old_def.version && v.ty == old_def.ty);
+        if old_def.version != new_def.version && migrated {
+            changes.compatible(format!(
+                "{}: layout changed from version {} to {}, with a migration",
+                ctx,
+                old_def.version.unwrap_or_default(),
+                new_def.version.unwrap_or_default()
+            ));
+            continue;
+        }
+        diff_ty_def(&ctx, old_def, new_def, changes);
+    }
+    for new_def in new {
+        if !old.iter().any(|def| def.name == new_def.name) {
+            changes.compatible(format!("{} `{}` added", kind, new_def.name));
+        }
+    }
+}
+
+fn diff_ty_def(ctx: &str, old: &IdlTypeDefinition, new: &IdlTypeDefinition, changes: &mut Changes) {
+    if old.generics != new.generics {
+        changes.breaking(format!("{}: generic parameters changed", ctx));
+    }
+    if old.version != new.version {
+        changes.breaking(format!("{}: layout version changed", ctx));
+    }
+    if old.repr != new.repr || old.zero_copy != new.zero_copy {
+        changes.breaking(format!("{}: memory layout changed", ctx));
+    }
+    match (&old.ty, &new.ty) {
+        (
+            IdlTypeDefinitionTy::Struct { fields: old },
+            IdlTypeDefinitionTy::Struct { fields: new },
+        ) => diff_fields(ctx, &FIELDS, old, new, changes),
+        (
+            IdlTypeDefinitionTy::Tuple { fields: old },
+            IdlTypeDefinitionTy::Tuple { fields: new },
+        ) => diff_types(ctx, old, new, changes),
+        (
+            IdlTypeDefinitionTy::Enum { variants: old },
+            IdlTypeDefinitionTy::Enum { variants: new },
+        ) => diff_variants(ctx, old, new, changes),
+        (old, new) => changes.breaking(format!(
+            "{}: changed from {} to {}",
+            ctx,
+            ty_def_kind(old),
+            ty_def_kind(new)
+        )),
+    }
+}
+
+fn diff_variants(ctx: &str, old: &[IdlEnumVariant], new: &[IdlEnumVariant], changes: &mut Changes) {
+    for (idx, old_variant) in old.iter().enumerate() {
+        let new_variant = match new.get(idx) {
+            Some(new_variant) => new_variant,
+            None => {
+                changes.breaking(format!("{}: variant `{}` removed", ctx, old_variant.name));
+                continue;
+            }
+        };
+        if new_variant.name != old_variant.name {
+            match new.iter().position(|v| v.name == old_variant.name) {
+                Some(new_idx) => changes.breaking(format!(
+                    "{}: variant `{}` moved from position {} to {}",
+                    ctx, old_variant.name, idx, new_idx
+                )),
+                None => changes.breaking(format!(
+                    "{}: variant `{}` renamed to `{}`",
+                    ctx, old_variant.name, new_variant.name
+                )),
+            }
+            continue;
+        }
+        let ctx = format!("{}: variant `{}`", ctx, old_variant.name);
+        // Without explicit discriminants, variants take their position.
+        let discriminant = |v: &IdlEnumVariant| v.discriminant.unwrap_or(idx as i64);
+        if discriminant(old_variant) != discriminant(new_variant) {
+            changes.breaking(format!(
+                "{} discriminant changed from {} to {}",
+                ctx,
+                discriminant(old_variant),
+                discriminant(new_variant)
+            ));
+        }
+        match (&old_variant.fields, &new_variant.fields) {
+            (None, None) => {}
+            (Some(EnumFields::Named(old)), Some(EnumFields::Named(new))) => {
+                diff_fields(&ctx, &FIELDS, old, new, changes)
+            }
+            (Some(EnumFields::Tuple(old)), Some(EnumFields::Tuple(new))) => {
+                diff_types(&ctx, old, new, changes)
+            }
+            _ => changes.breaking(format!("{} changed its fields", ctx)),
+        }
+    }
+    // Appended variants don't change the encoding of the others.
+    for new_variant in new.iter().skip(old.len()) {
+        changes.compatible(format!("{}: variant `{}` added", ctx, new_variant.name));
+    }
+}
+
+fn diff_events(old: &[IdlEvent], new: &[IdlEvent], changes: &mut Changes) {
+    let fields = |event: &IdlEvent| -> Vec<IdlField> {
+        event
+            .fields
+            .iter()
+            .map(|f| IdlField {
+                name: f.name.clone(),
+                ty: f.ty.clone(),
+            })
+            .collect()
+    };
+    for old_event in old {
+        let ctx = format!("event `{}`", old_event.name);
+        let new_event = match new.iter().find(|e| e.name == old_event.name) {
+            Some(new_event) => new_event,
+            None => {
+                changes.breaking(format!("{} removed", ctx));
+                continue;
+            }
+        };
+        if old_event.discriminator != new_event.discriminator {
+            changes.breaking(format!("{}: discriminator changed", ctx));
+        }
+        diff_fields(
+            &ctx,
+            &EVENT_FIELDS,
+            &fields(old_event),
+            &fields(new_event),
+            changes,
+        );
+    }
+    for new_event in new {
+        if !old.iter().any(|e| e.name == new_event.name) {
+            changes.compatible(format!("event `{}` added", new_event.name));
+        }
+    }
+}
+
+fn diff_errors(old: &[IdlErrorCode], new: &[IdlErrorCode], changes: &mut Changes) {
+    for old_err in old {
+        match new.iter().find(|e| e.name == old_err.name) {
+            Some(new_err) if new_err.code != old_err.code => changes.breaking(format!(
+                "error `{}`: code changed from {} to {}",
+                old_err.name, old_err.code, new_err.code
+            )),
+            Some(new_err) if new_err.msg != old_err.msg => {
+                changes.compatible(format!("error `{}`: message changed", old_err.name))
+            }
+            Some(_) => {}
+            // The program no longer returns it.
+            None => changes.compatible(format!("error `{}` removed", old_err.name)),
+        }
+    }
+    for new_err in new {
+        if old.iter().any(|e| e.name == new_err.name) {
+            continue;
+        }
+        match old.iter().find(|e| e.code == new_err.code) {
+            Some(old_err) => changes.breaking(format!(
+                "error code {} changed from `{}` to `{}`",
+                new_err.code, old_err.name, new_err.name
+            )),
+            None => changes.compatible(format!("error `{}` added", new_err.name)),
+        }
+    }
+}
+
+fn ix_discriminator(namespace: &str, ix: &IdlInstruction) -> Vec<u8> {
+    match &ix.discriminator {
+        Some(discriminator) => discriminator.clone(),
+        None => sighash(namespace, &ix.name.to_snake_case()).to_vec(),
+    }
+}
+
+fn discriminator(namespace: &str, name: &str, explicit: Option<&Vec<u8>>) -> Vec<u8> {
+    match explicit {
+        Some(discriminator) => discriminator.clone(),
+        None => sighash(namespace, name).to_vec(),
+    }
+}
+
+fn ty_def_kind(ty: &IdlTypeDefinitionTy) -> &'static str {
+    match ty {
+        IdlTypeDefinitionTy::Struct { .. } => "a struct",
+        IdlTypeDefinitionTy::Tuple { .. } => "a tuple struct",
+        IdlTypeDefinitionTy::Enum { .. } => "an enum",
+    }
+}
+
+fn ty_str(ty: &IdlType) -> String {
+    match serde_json::to_value(ty) {
+        Ok(serde_json::Value::String(ty)) => ty,
+        Ok(ty) => ty.to_string(),
+        Err(_) => format!("{:?}", ty),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn idl(json: serde_json::Value) -> Idl {
+        serde_json::from_value(json).unwrap()
+    }
+
+    fn breaking(changes: &[IdlChange]) -> Vec<String> {
+        changes
+            .iter()
+            .filter(|c| c.is_breaking())
+            .map(|c| c.to_string())
+            .collect()
+    }
+
+    fn compatible(changes: &[IdlChange]) -> Vec<String> {
+        changes
+            .iter()
+            .filter(|c| !c.is_breaking())
+            .map(|c| c.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn classifies_instruction_changes() {
+        let old = idl(serde_json::json!({
+            "version": "0.1.0",
+            "name": "p",
+            "instructions": [
+                {
+                    "name": "deposit",
+                    "accounts": [
+                        { "name": "vault", "isMut": true, "isSigner": false },
+                        { "name": "authority", "isMut": false, "isSigner": true }
+                    ],
+                    "args": [{ "name": "amount", "type": "u64" }]
+                },
+                { "name": "close", "accounts": [], "args": [] }
+            ]
+        }));
+        let new = idl(serde_json::json!({
+            "version": "0.2.0",
+            "name": "p",
+            "instructions": [
+                {
+                    "name": "deposit",
+                    "accounts": [
+                        { "name": "authority", "isMut": false, "isSigner": true },
+                        { "name": "vault", "isMut": true, "isSigner": false }
+                    ],
+                    "args": [{ "name": "lamports", "type": "u32" }]
+                },
+                { "name": "withdraw", "accounts": [], "args": [] }
+            ]
+        }));
+        let changes = diff(&old, &new);
+        assert_eq!(
+            breaking(&changes),
+            vec![
+                "instruction `deposit`: account `vault` moved from position 0 to 1",
+                "instruction `deposit`: account `authority` moved from position 1 to 0",
+                "instruction `deposit`: argument `amount` changed type from u64 to u32",
+                "instruction `close` removed",
+            ]
+        );
+        assert_eq!(
+            compatible(&changes),
+            vec![
+                "instruction `deposit`: argument `amount` renamed to `lamports`",
+                "instruction `withdraw` added",
+            ]
+        );
+    }
+
+    #[test]
+    fn classifies_layout_and_error_changes() {
+        let old = idl(serde_json::json!({
+            "version": "0.1.0",
+            "name": "p",
+            "instructions": [],
+            "accounts": [{
+                "name": "Vault",
+                "type": { "kind": "struct", "fields": [{ "name": "amount", "type": "u64" }] }
+            }],
+            "types": [{
+                "name": "Side",
+                "type": { "kind": "enum", "variants": [{ "name": "Bid" }, { "name": "Ask" }] }
+            }],
+            "errors": [
+                { "code": 6000, "name": "Full" },
+                { "code": 6001, "name": "Empty" }
+            ]
+        }));
+        let new = idl(serde_json::json!({
+            "version": "0.2.0",
+            "name": "p",
+            "instructions": [],
+            "accounts": [{
+                "name": "Vault",
+                "type": {
+                    "kind": "struct",
+                    "fields": [
+                        { "name": "amount", "type": "u64" },
+                        { "name": "bump", "type": "u8" }
+                    ]
+                },
+                "discriminator": [1]
+            }],
+            "types": [{
+                "name": "Side",
+                "type": {
+                    "kind": "enum",
+                    "variants": [
+                        { "name": "Bid", "discriminant": 0 },
+                        { "name": "Ask", "discriminant": 2 },
+                        { "name": "Cancel", "discriminant": 3 }
+                    ]
+                },
+                "repr": { "kind": "rust", "int": "u8" }
+            }],
+            "errors": [
+                { "code": 6000, "name": "Empty" },
+                { "code": 6001, "name": "Closed" }
+            ]
+        }));
+        let changes = diff(&old, &new);
+        assert_eq!(
+            breaking(&changes),
+            vec![
+                "account `Vault`: discriminator changed",
+                "account `Vault`: field `bump` added",
+                "type `Side`: memory layout changed",
+                "type `Side`: variant `Ask` discriminant changed from 1 to 2",
+                "error `Empty`: code changed from 6001 to 6000",
+                "error code 6001 changed from `Empty` to `Closed`",
+            ]
+        );
+        assert_eq!(
+            compatible(&changes),
+            vec![
+                "type `Side`: variant `Cancel` added",
+                "error `Full` removed"
+            ]
+        );
+    }
+
+    #[test]
+    fn migrated_accounts_are_compatible() {
+        let vault = |version: u8, fields: serde_json::Value, history: serde_json::Value| {
+            idl(serde_json::json!({
+                "version": "0.1.0",
+                "name": "p",
+                "instructions": [],
+                "accounts": [{
+                    "name": "Vault",
+                    "type": { "kind": "struct", "fields": fields },
+                    "version": version,
+                    "history": history
+                }]
+            }))
+        };
+        let v1 = serde_json::json!([{ "name": "amount", "type": "u64" }]);
+        let v2 = serde_json::json!([
+            { "name": "amount", "type": "u64" },
+            { "name": "bump", "type": "u8" }
+        ]);
+        let old = vault(1, v1.clone(), serde_json::json!([]));
+        let new = vault(
+            2,
+            v2,
+            serde_json::json!([{ "version": 1, "type": { "kind": "struct", "fields": v1 } }]),
+        );
+        let changes = diff(&old, &new);
+        assert!(breaking(&changes).is_empty());
+        assert_eq!(
+            compatible(&changes),
+            vec!["account `Vault`: layout changed from version 1 to 2, with a migration"]
+        );
+    }
+}
@@ -1,26 +1,31 @@
+use crate::codegen::program::common::sighash;
+use crate::idl::spec::IDL_SPEC;
 use crate::idl::*;
 use crate::parser::context::CrateContext;
+use crate::parser::discriminators::{self, Discriminated};
 use crate::parser::{self, accounts, error, program};
 use crate::Ty;
-use crate::{AccountField, AccountsStruct, StateIx};
+use crate::{AccountArgs, AccountField, AccountsStruct, EventArgs, Field, StateIx};
 use anyhow::Result;
-use heck::MixedCase;
+use heck::{CamelCase, MixedCase};
 use quote::ToTokens;
 use std::collections::{HashMap, HashSet};
-use std::path::Path;
+use std::convert::TryFrom;
+use std::path::{Path, PathBuf};
 
 const DERIVE_NAME: &str = "Accounts";
 // TODO: sharee this with `anchor_lang` crate.
 const ERROR_CODE_OFFSET: u32 = 6000;
 
-// Parse an entire interface file.
+// Parse an entire interface file. `dependencies` are the root files of the
+// path dependencies of the program, used to resolve the types it imports.
 pub fn parse(
     filename: impl AsRef<Path>,
     version: String,
-    seeds_feature: bool,
     safety_checks: bool,
+    dependencies: &[PathBuf],
 ) -> Result<Option<Idl>> {
-    let ctx = CrateContext::parse(filename)?;
+    let ctx = CrateContext::parse_with_dependencies(filename, dependencies)?;
     if safety_checks {
         ctx.safety_checks()?;
     }
@@ -29,7 +34,10 @@ pub fn parse(
         None => return Ok(None),
         Some(m) => m,
     };
-    let p = program::parse(program_mod)?;
+    let root_file = ctx.root_module().file().to_owned();
+    let p = program::parse(program_mod)
+        .map_err(|e| spanned_error(&root_file, e.span(), &e.to_string()))?;
+    check_discriminators(&ctx)?;
 
     let accs = parse_account_derives(&ctx);
 
@@ -49,27 +57,25 @@ pub fn parse(
                                     .args
                                     .iter()
                                     .map(|arg| {
-                                        let mut tts = proc_macro2::TokenStream::new();
-                                        arg.raw_arg.ty.to_tokens(&mut tts);
-                                        let ty = tts.to_string().parse().unwrap();
-                                        IdlField {
+                                        Ok(IdlField {
                                             name: arg.name.to_string().to_mixed_case(),
-                                            ty,
-                                        }
+                                            ty: idl_type(&ctx, &arg.raw_arg.ty, &[])?,
+                                        })
                                     })
-                                    .collect::<Vec<_>>();
+                                    .collect::<Result<Vec<_>>>()?;
                                 let accounts_strct =
                                     accs.get(&method.anchor_ident.to_string()).unwrap();
-                                let accounts =
-                                    idl_accounts(&ctx, accounts_strct, &accs, seeds_feature);
-                                IdlInstruction {
+                                let accounts = idl_accounts(&ctx, accounts_strct, &accs);
+                                Ok(IdlInstruction {
                                     name,
                                     accounts,
                                     args,
-                                }
+                                    discriminator: None,
+                                })
                             })
-                            .collect::<Vec<_>>()
+                            .collect::<Result<Vec<_>>>()
                     })
+                    .transpose()?
                     .unwrap_or_default();
                 let ctor = {
                     let name = "new".to_string();
@@ -87,24 +93,20 @@ pub fn parse(
                             _ => false,
                         })
                         .map(|arg: &syn::FnArg| match arg {
-                            syn::FnArg::Typed(arg_typed) => {
-                                let mut tts = proc_macro2::TokenStream::new();
-                                arg_typed.ty.to_tokens(&mut tts);
-                                let ty = tts.to_string().parse().unwrap();
-                                IdlField {
-                                    name: parser::tts_to_string(&arg_typed.pat).to_mixed_case(),
-                                    ty,
-                                }
-                            }
+                            syn::FnArg::Typed(arg_typed) => Ok(IdlField {
+                                name: parser::tts_to_string(&arg_typed.pat).to_mixed_case(),
+                                ty: idl_type(&ctx, &arg_typed.ty, &[])?,
+                            }),
                             _ => panic!("Invalid syntax"),
                         })
-                        .collect();
+                        .collect::<Result<Vec<_>>>()?;
                     let accounts_strct = accs.get(&anchor_ident.to_string()).unwrap();
-                    let accounts = idl_accounts(&ctx, accounts_strct, &accs, seeds_feature);
+                    let accounts = idl_accounts(&ctx, accounts_strct, &accs);
                     IdlInstruction {
                         name,
                         accounts,
                         args,
+                        discriminator: None,
                     }
                 };
 
@@ -116,20 +118,24 @@ pub fn parse(
                             .named
                             .iter()
                             .map(|f: &syn::Field| {
-                                let mut tts = proc_macro2::TokenStream::new();
-                                f.ty.to_tokens(&mut tts);
-                                let ty = tts.to_string().parse().unwrap();
-                                IdlField {
+                                Ok(IdlField {
                                     name: f.ident.as_ref().unwrap().to_string().to_mixed_case(),
-                                    ty,
-                                }
+                                    ty: idl_type(&ctx, &f.ty, &[])?,
+                                })
                             })
-                            .collect::<Vec<IdlField>>(),
+                            .collect::<Result<Vec<IdlField>>>()?,
                         _ => panic!("State must be a struct"),
                     };
                     IdlTypeDefinition {
                         name: state.name,
+                        generics: vec![],
                         ty: IdlTypeDefinitionTy::Struct { fields },
+                        version: None,
+                        history: vec![],
+                        discriminator: None,
+                        repr: None,
+                        zero_copy: false,
+                        size: None,
                     }
                 };
 
@@ -157,29 +163,36 @@ pub fn parse(
                 .args
                 .iter()
                 .map(|arg| {
-                    let mut tts = proc_macro2::TokenStream::new();
-                    arg.raw_arg.ty.to_tokens(&mut tts);
-                    let ty = tts.to_string().parse().unwrap();
-                    IdlField {
+                    Ok(IdlField {
                         name: arg.name.to_string().to_mixed_case(),
-                        ty,
-                    }
+                        ty: idl_type(&ctx, &arg.raw_arg.ty, &[])?,
+                    })
                 })
-                .collect::<Vec<_>>();
+                .collect::<Result<Vec<_>>>()?;
             // todo: don't unwrap
             let accounts_strct = accs.get(&ix.anchor_ident.to_string()).unwrap();
-            let accounts = idl_accounts(&ctx, accounts_strct, &accs, seeds_feature);
-            IdlInstruction {
+            let accounts = idl_accounts(&ctx, accounts_strct, &accs);
+            Ok(IdlInstruction {
                 name: ix.ident.to_string().to_mixed_case(),
                 accounts,
                 args,
-            }
+                discriminator: ix.discriminator.as_ref().map(|d| d.bytes.clone()),
+            })
         })
-        .collect::<Vec<_>>();
+        .collect::<Result<Vec<_>>>()?;
 
     let events = parse_events(&ctx)
         .iter()
         .map(|e: &&syn::ItemStruct| {
+            let attr = e
+                .attrs
+                .iter()
+                .find(|attr| attr.path.segments.last().unwrap().ident == "event")
+                .unwrap();
+            let args: EventArgs = match attr.tokens.is_empty() {
+                true => EventArgs::default(),
+                false => attr.parse_args()?,
+            };
             let fields = match &e.fields {
                 syn::Fields::Named(n) => n,
                 _ => panic!("Event fields must be named"),
@@ -192,20 +205,25 @@ pub fn parse(
                         None => false,
                         Some(i) => parser::tts_to_string(&i.path) == "index",
                     };
-                    IdlEventField {
+                    Ok(IdlEventField {
                         name: f.ident.clone().unwrap().to_string().to_mixed_case(),
-                        ty: parser::tts_to_string(&f.ty).parse().unwrap(),
+                        ty: idl_type(&ctx, &f.ty, &[])?,
                         index,
-                    }
+                    })
                 })
-                .collect::<Vec<IdlEventField>>();
+                .collect::<Result<Vec<IdlEventField>>>()?;
 
-            IdlEvent {
-                name: e.ident.to_string(),
+            let name = e.ident.to_string();
+            Ok(IdlEvent {
+                discriminator: args
+                    .discriminator
+                    .map(|d| d.bytes)
+                    .unwrap_or_else(|| sighash("event", &name).to_vec()),
+                name,
                 fields,
-            }
+            })
         })
-        .collect::<Vec<IdlEvent>>();
+        .collect::<Result<Vec<IdlEvent>>>()?;
 
     // All user defined types.
     let mut accounts = vec![];
@@ -213,21 +231,17 @@ pub fn parse(
     let ty_defs = parse_ty_defs(&ctx)?;
 
     let account_structs = parse_accounts(&ctx);
-    let account_names: HashSet<String> = account_structs
-        .iter()
-        .map(|a| a.ident.to_string())
-        .collect::<HashSet<_>>();
 
     let error_name = error.map(|e| e.name).unwrap_or_else(|| "".to_string());
 
     // All types that aren't in the accounts section, are in the types section.
-    for ty_def in ty_defs {
+    for ty_def in &ty_defs {
         // Don't add the error type to the types or accounts sections.
         if ty_def.name != error_name {
-            if account_names.contains(&ty_def.name) {
-                accounts.push(ty_def);
+            if let Some(strct) = account_structs.iter().find(|s| s.ident == ty_def.name) {
+                accounts.push(account_ty_def(strct, ty_def.clone(), &ty_defs)?);
             } else if !events.iter().any(|e| e.name == ty_def.name) {
-                types.push(ty_def);
+                types.push(ty_def.clone());
             }
         }
     }
@@ -241,7 +255,8 @@ pub fn parse(
         })
         .collect::<Vec<IdlConst>>();
 
-    Ok(Some(Idl {
+    let mut idl = Idl {
+        spec: IDL_SPEC.to_string(),
         version,
         name: p.name.to_string(),
         state,
@@ -256,7 +271,232 @@ pub fn parse(
         errors: error_codes,
         metadata: None,
         constants,
-    }))
+    };
+    add_missing_types(&mut idl, |name| find_ty_def(&ctx, name))?;
+    size::set_sizes(&mut idl);
+
+    Ok(Some(idl))
+}
+
+// Adds the types referenced but not defined by the IDL to the types section,
+// as found by `find`. Errors if a type can't be found at all, rather than
+// leaving a dangling `Defined` name in the IDL.
+pub(crate) fn add_missing_types(
+    idl: &mut Idl,
+    mut find: impl FnMut(&str) -> Result<Option<IdlTypeDefinition>>,
+) -> Result<()> {
+    let mut defined: HashSet<String> = idl
+        .types
+        .iter()
+        .chain(&idl.accounts)
+        .map(|ty_def| ty_def.name.clone())
+        .chain(idl.events.iter().flatten().map(|e| e.name.clone()))
+        .collect();
+
+    let mut pending = vec![];
+    let ixs = idl
+        .instructions
+        .iter()
+        .chain(idl.state.iter().flat_map(|s| &s.methods));
+    for ix in ixs {
+        for arg in &ix.args {
+            referenced_types(&arg.ty, &mut pending);
+        }
+    }
+    let ty_defs = idl
+        .types
+        .iter()
+        .chain(&idl.accounts)
+        .chain(idl.state.iter().map(|s| &s.strct));
+    for ty_def in ty_defs {
+        ty_def_references(ty_def, &mut pending);
+    }
+    for field in idl.events.iter().flatten().flat_map(|e| &e.fields) {
+        referenced_types(&field.ty, &mut pending);
+    }
+
+    while let Some(name) = pending.pop() {
+        if defined.contains(&name) {
+            continue;
+        }
+        let ty_def = find(&name)?.ok_or_else(|| {
+            anyhow::anyhow!(
+                "Could not find type `{}` referenced by the IDL. Types must be defined \
+                in the program or in a path dependency listed in its Cargo.toml, and \
+                structs must be public and derive AnchorSerialize and AnchorDeserialize",
+                name
+            )
+        })?;
+        ty_def_references(&ty_def, &mut pending);
+        defined.insert(name);
+        idl.types.push(ty_def);
+    }
+
+    Ok(())
+}
+
+// Finds the definition of a serializable type with the given name in the
+// crate, or else in its dependencies.
+pub(crate) fn find_ty_def(ctx: &CrateContext, name: &str) -> Result<Option<IdlTypeDefinition>> {
+    if let Some(ty_def) = ctx
+        .structs()
+        .filter(|strct| strct.ident == name)
+        .find_map(|strct| struct_ty_def(ctx, strct))
+    {
+        return ty_def.map(Some);
+    }
+    if let Some(enm) = ctx.enums().find(|enm| enm.ident == name) {
+        return enum_ty_def(ctx, enm).map(Some);
+    }
+    ctx.dependencies()
+        .iter()
+        .find_map(|dep| find_ty_def(dep, name).transpose())
+        .transpose()
+}
+
+// Collects the names of the user defined types used by a type definition.
+fn ty_def_references(ty_def: &IdlTypeDefinition, names: &mut Vec<String>) {
+    let tys = std::iter::once(&ty_def.ty).chain(ty_def.history.iter().map(|h| &h.ty));
+    for ty in tys {
+        match ty {
+            IdlTypeDefinitionTy::Struct { fields } => {
+                for field in fields {
+                    referenced_types(&field.ty, names);
+                }
+            }
+            IdlTypeDefinitionTy::Tuple { fields } => {
+                for field in fields {
+                    referenced_types(field, names);
+                }
+            }
+            IdlTypeDefinitionTy::Enum { variants } => {
+                for fields in variants.iter().filter_map(|v| v.fields.as_ref()) {
+                    match fields {
+                        EnumFields::Named(fields) => {
+                            for field in fields {
+                                referenced_types(&field.ty, names);
+                            }
+                        }
+                        EnumFields::Tuple(fields) => {
+                            for field in fields {
+                                referenced_types(field, names);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+// Collects the names of the user defined types used by `ty`.
+fn referenced_types(ty: &IdlType, names: &mut Vec<String>) {
+    let _ = ty.clone().map(&mut |ty| {
+        match &ty {
+            IdlType::Defined(name) | IdlType::DefinedWithTypeArgs { name, .. } => {
+                names.push(name.clone())
+            }
+            _ => {}
+        }
+        Ok(ty)
+    });
+}
+
+// Checks that no two accounts and no two events share a discriminator.
+// Instructions are checked when parsing the program mod.
+fn check_discriminators(ctx: &CrateContext) -> Result<()> {
+    let mut accounts = vec![];
+    let mut events = vec![];
+    for module in ctx.modules() {
+        let strcts = module.items().filter_map(|item| match item {
+            syn::Item::Struct(strct) => Some(strct),
+            _ => None,
+        });
+        for strct in strcts {
+            let name = strct.ident.to_string();
+            for attr in &strct.attrs {
+                let parse_err =
+                    |e: syn::Error| spanned_error(module.file(), e.span(), &e.to_string());
+                match attr
+                    .path
+                    .segments
+                    .last()
+                    .unwrap()
+                    .ident
+                    .to_string()
+                    .as_str()
+                {
+                    "account" => {
+                        let args: AccountArgs = match attr.tokens.is_empty() {
+                            true => AccountArgs::default(),
+                            false => attr.parse_args().map_err(parse_err)?,
+                        };
+                        let bytes = match (args.discriminator, args.namespace) {
+                            (Some(discriminator), _) => discriminator.bytes,
+                            (None, Some(namespace)) => sighash(&namespace, &name).to_vec(),
+                            (None, None) => sighash("account", &name).to_vec(),
+                        };
+                        accounts.push((
+                            module.file(),
+                            Discriminated {
+                                desc: format!("account `{}`", name),
+                                client_name: format!("account:{}", name.to_camel_case()),
+                                bytes,
+                                span: strct.ident.span(),
+                            },
+                        ));
+                    }
+                    "state" => accounts.push((
+                        module.file(),
+                        Discriminated {
+                            desc: format!("state account `{}`", name),
+                            client_name: format!("state:{}", name.to_camel_case()),
+                            bytes: sighash("state", &name).to_vec(),
+                            span: strct.ident.span(),
+                        },
+                    )),
+                    "event" => {
+                        let args: EventArgs = match attr.tokens.is_empty() {
+                            true => EventArgs::default(),
+                            false => attr.parse_args().map_err(parse_err)?,
+                        };
+                        events.push((
+                            module.file(),
+                            Discriminated {
+                                desc: format!("event `{}`", name),
+                                client_name: format!("event:{}", name),
+                                bytes: args
+                                    .discriminator
+                                    .map(|d| d.bytes)
+                                    .unwrap_or_else(|| sighash("event", &name).to_vec()),
+                                span: strct.ident.span(),
+                            },
+                        ));
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    for items in [accounts, events] {
+        let (files, items): (Vec<&Path>, Vec<Discriminated>) = items.into_iter().unzip();
+        if let Some((idx, msg)) = discriminators::find_collision(&items) {
+            return Err(spanned_error(files[idx], items[idx].span, &msg));
+        }
+    }
+    Ok(())
+}
+
+// Formats an error at the given span, like the safety checks.
+fn spanned_error(file: &Path, span: proc_macro2::Span, msg: &str) -> anyhow::Error {
+    anyhow::anyhow!(
+        "{}:{}:{}\n{}",
+        file.display(),
+        span.start().line,
+        span.start().column,
+        msg
+    )
 }
 
 // Parse the main program mod.
@@ -379,134 +619,554 @@ fn parse_consts(ctx: &CrateContext) -> Vec<&syn::ItemConst> {
 // Parse all user defined types in the file.
 fn parse_ty_defs(ctx: &CrateContext) -> Result<Vec<IdlTypeDefinition>> {
     ctx.structs()
-        .filter_map(|item_strct| {
-            // Only take serializable types
-            let serializable = item_strct.attrs.iter().any(|attr| {
-                let attr_string = attr.tokens.to_string();
-                let attr_name = attr.path.segments.last().unwrap().ident.to_string();
-                let attr_serializable = ["account", "associated", "event", "zero_copy"];
+        .filter_map(|item_strct| struct_ty_def(ctx, item_strct))
+        .chain(ctx.enums().map(|enm| enum_ty_def(ctx, enm)))
+        .collect()
+}
 
-                let derived_serializable = attr_name == "derive"
-                    && attr_string.contains("AnchorSerialize")
-                    && attr_string.contains("AnchorDeserialize");
+// Converts a struct to its type definition, if it's serializable and public.
+fn struct_ty_def(
+    ctx: &CrateContext,
+    item_strct: &syn::ItemStruct,
+) -> Option<Result<IdlTypeDefinition>> {
+    // Only take serializable types
+    let serializable = item_strct.attrs.iter().any(|attr| {
+        let attr_string = attr.tokens.to_string();
+        let attr_name = attr.path.segments.last().unwrap().ident.to_string();
+        let attr_serializable = ["account", "associated", "event", "zero_copy"];
 
-                attr_serializable.iter().any(|a| *a == attr_name) || derived_serializable
-            });
+        let derived_serializable = attr_name == "derive"
+            && attr_string.contains("AnchorSerialize")
+            && attr_string.contains("AnchorDeserialize");
 
-            if !serializable {
-                return None;
-            }
+        attr_serializable.iter().any(|a| *a == attr_name) || derived_serializable
+    });
 
-            // Only take public types
-            match &item_strct.vis {
-                syn::Visibility::Public(_) => (),
-                _ => return None,
-            }
+    if !serializable {
+        return None;
+    }
 
-            let name = item_strct.ident.to_string();
-            let fields = match &item_strct.fields {
-                syn::Fields::Named(fields) => fields
-                    .named
-                    .iter()
-                    .map(|f: &syn::Field| {
-                        let mut tts = proc_macro2::TokenStream::new();
-                        f.ty.to_tokens(&mut tts);
-                        // Handle array sizes that are constants
-                        let mut tts_string = tts.to_string();
-                        if tts_string.starts_with('[') {
-                            tts_string = resolve_variable_array_length(ctx, tts_string);
-                        }
-                        Ok(IdlField {
-                            name: f.ident.as_ref().unwrap().to_string().to_mixed_case(),
-                            ty: tts_string.parse()?,
+    // Only take public types
+    match &item_strct.vis {
+        syn::Visibility::Public(_) => (),
+        _ => return None,
+    }
+
+    let name = item_strct.ident.to_string();
+    let generics = idl_generics(&item_strct.generics);
+    let params = generic_names(&generics);
+    let ty = match &item_strct.fields {
+        syn::Fields::Named(fields) => fields
+            .named
+            .iter()
+            .map(|f: &syn::Field| {
+                Ok(IdlField {
+                    name: f.ident.as_ref().unwrap().to_string().to_mixed_case(),
+                    ty: idl_type(ctx, &f.ty, &params)?,
+                })
+            })
+            .collect::<Result<Vec<IdlField>>>()
+            .map(|fields| IdlTypeDefinitionTy::Struct { fields }),
+        syn::Fields::Unnamed(fields) => fields
+            .unnamed
+            .iter()
+            .map(|f: &syn::Field| idl_type(ctx, &f.ty, &params))
+            .collect::<Result<Vec<IdlType>>>()
+            .map(|fields| IdlTypeDefinitionTy::Tuple { fields }),
+        _ => panic!("Empty structs are allowed."),
+    };
+
+    Some(ty.map(|ty| IdlTypeDefinition {
+        name,
+        generics,
+        ty,
+        version: None,
+        history: vec![],
+        discriminator: None,
+        repr: idl_repr(&item_strct.attrs),
+        zero_copy: is_zero_copy(&item_strct.attrs),
+        size: None,
+    }))
+}
+
+fn enum_ty_def(ctx: &CrateContext, enm: &syn::ItemEnum) -> Result<IdlTypeDefinition> {
+    let name = enm.ident.to_string();
+    let generics = idl_generics(&enm.generics);
+    let params = generic_names(&generics);
+    let discriminants = discriminants(ctx, enm);
+    let variants = enm
+        .variants
+        .iter()
+        .zip(discriminants)
+        .map(|(variant, discriminant)| {
+            let name = variant.ident.to_string();
+            let fields = match &variant.fields {
+                syn::Fields::Unit => None,
+                syn::Fields::Unnamed(fields) => {
+                    let fields = fields
+                        .unnamed
+                        .iter()
+                        .map(|f| idl_type(ctx, &f.ty, &params))
+                        .collect::<Result<Vec<IdlType>>>()?;
+                    Some(EnumFields::Tuple(fields))
+                }
+                syn::Fields::Named(fields) => {
+                    let fields = fields
+                        .named
+                        .iter()
+                        .map(|f: &syn::Field| {
+                            let name = f.ident.as_ref().unwrap().to_string();
+                            let ty = idl_type(ctx, &f.ty, &params)?;
+                            Ok(IdlField { name, ty })
                         })
-                    })
-                    .collect::<Result<Vec<IdlField>>>(),
-                syn::Fields::Unnamed(_) => return None,
-                _ => panic!("Empty structs are allowed."),
+                        .collect::<Result<Vec<IdlField>>>()?;
+                    Some(EnumFields::Named(fields))
+                }
             };
-
-            Some(fields.map(|fields| IdlTypeDefinition {
+            Ok(IdlEnumVariant {
                 name,
-                ty: IdlTypeDefinitionTy::Struct { fields },
-            }))
+                fields,
+                discriminant,
+            })
         })
-        .chain(ctx.enums().map(|enm| {
-            let name = enm.ident.to_string();
-            let variants = enm
-                .variants
-                .iter()
-                .map(|variant: &syn::Variant| {
-                    let name = variant.ident.to_string();
-                    let fields = match &variant.fields {
-                        syn::Fields::Unit => None,
-                        syn::Fields::Unnamed(fields) => {
-                            let fields: Vec<IdlType> =
-                                fields.unnamed.iter().map(to_idl_type).collect();
-                            Some(EnumFields::Tuple(fields))
+        .collect::<Result<Vec<IdlEnumVariant>>>()?;
+    Ok(IdlTypeDefinition {
+        name,
+        generics,
+        ty: IdlTypeDefinitionTy::Enum { variants },
+        version: None,
+        history: vec![],
+        discriminator: None,
+        repr: idl_repr(&enm.attrs),
+        zero_copy: false,
+        size: None,
+    })
+}
+
+// The values of the variants of an enum, if any is given explicitly. The
+// others follow the previous variant, starting from zero. Variants whose value
+// can't be evaluated, e.g. given by a function call, and the ones following
+// them have none.
+fn discriminants(ctx: &CrateContext, enm: &syn::ItemEnum) -> Vec<Option<i64>> {
+    if enm.variants.iter().all(|v| v.discriminant.is_none()) {
+        return vec![None; enm.variants.len()];
+    }
+    let mut next = Some(0);
+    enm.variants
+        .iter()
+        .map(|variant| {
+            let value = match &variant.discriminant {
+                None => next,
+                Some((_, expr)) => eval_int(ctx, expr),
+            };
+            next = value.and_then(|value| value.checked_add(1));
+            value.and_then(|value| i64::try_from(value).ok())
+        })
+        .collect()
+}
+
+// Evaluates a constant integer expression, e.g. `-1`, `1 << 4`, `300 as u8`
+// or a constant.
+fn eval_int(ctx: &CrateContext, expr: &syn::Expr) -> Option<i128> {
+    match expr {
+        syn::Expr::Lit(syn::ExprLit {
+            lit: syn::Lit::Int(int),
+            ..
+        }) => int.base10_parse().ok(),
+        syn::Expr::Paren(expr) => eval_int(ctx, &expr.expr),
+        syn::Expr::Group(expr) => eval_int(ctx, &expr.expr),
+        syn::Expr::Cast(expr) => cast_int(eval_int(ctx, &expr.expr)?, &expr.ty),
+        syn::Expr::Unary(syn::ExprUnary {
+            op: syn::UnOp::Neg(_),
+            expr,
+            ..
+        }) => eval_int(ctx, expr)?.checked_neg(),
+        syn::Expr::Unary(syn::ExprUnary {
+            op: syn::UnOp::Not(_),
+            expr,
+            ..
+        }) => Some(!eval_int(ctx, expr)?),
+        syn::Expr::Binary(binary) => {
+            let (l, r) = (eval_int(ctx, &binary.left)?, eval_int(ctx, &binary.right)?);
+            match binary.op {
+                syn::BinOp::Add(_) => l.checked_add(r),
+                syn::BinOp::Sub(_) => l.checked_sub(r),
+                syn::BinOp::Mul(_) => l.checked_mul(r),
+                syn::BinOp::Div(_) => l.checked_div(r),
+                syn::BinOp::Rem(_) => l.checked_rem(r),
+                syn::BinOp::Shl(_) => u32::try_from(r).ok().and_then(|r| l.checked_shl(r)),
+                syn::BinOp::Shr(_) => u32::try_from(r).ok().and_then(|r| l.checked_shr(r)),
+                syn::BinOp::BitAnd(_) => Some(l & r),
+                syn::BinOp::BitOr(_) => Some(l | r),
+                syn::BinOp::BitXor(_) => Some(l ^ r),
+                _ => None,
+            }
+        }
+        // Only constants, not e.g. the variants of other enums.
+        syn::Expr::Path(path) => {
+            let name = path.path.segments.last()?.ident.to_string();
+            let constant = find_const(ctx, &name).ok()??;
+            eval_int(ctx, &constant.expr)
+        }
+        _ => None,
+    }
+}
+
+// Converts an integer to the integer type `ty` like `as` does, i.e. truncates
+// it to the width of the type.
+fn cast_int(value: i128, ty: &syn::Type) -> Option<i128> {
+    let (bits, signed) = match parser::tts_to_string(ty).as_str() {
+        "u8" => (8, false),
+        "i8" => (8, true),
+        "u16" => (16, false),
+        "i16" => (16, true),
+        "u32" => (32, false),
+        "i32" => (32, true),
+        // Programs run on a 64 bit target.
+        "u64" | "usize" => (64, false),
+        "i64" | "isize" => (64, true),
+        "u128" => return i128::try_from(value as u128).ok(),
+        "i128" => return Some(value),
+        _ => return None,
+    };
+    let shift = 128 - bits;
+    Some(match signed {
+        true => (value << shift) >> shift,
+        false => ((value as u128) << shift >> shift) as i128,
+    })
+}
+
+// The memory layout given by `#[repr(..)]`, or else `#[repr(C)]` as implied
+// by `#[zero_copy]`.
+fn idl_repr(attrs: &[syn::Attribute]) -> Option<IdlRepr> {
+    let mut repr: Option<IdlRepr> = None;
+    let hints = attrs
+        .iter()
+        .filter(|attr| attr.path.is_ident("repr"))
+        .filter_map(|attr| match attr.parse_meta() {
+            Ok(syn::Meta::List(list)) => Some(list.nested),
+            _ => None,
+        })
+        .flatten();
+    for hint in hints {
+        let repr = repr.get_or_insert(IdlRepr {
+            kind: IdlReprKind::Rust,
+            int: None,
+            packed: None,
+            align: None,
+        });
+        match hint {
+            syn::NestedMeta::Meta(syn::Meta::Path(path)) => {
+                let name = parser::tts_to_string(&path);
+                match name.as_str() {
+                    "C" => repr.kind = IdlReprKind::C,
+                    "transparent" => repr.kind = IdlReprKind::Transparent,
+                    "packed" => repr.packed = Some(1),
+                    "u8" | "i8" | "u16" | "i16" | "u32" | "i32" | "u64" | "i64" | "u128"
+                    | "i128" => repr.int = name.parse().ok(),
+                    // Programs run on a 64 bit target.
+                    "usize" => repr.int = Some(IdlType::U64),
+                    "isize" => repr.int = Some(IdlType::I64),
+                    _ => {}
+                }
+            }
+            syn::NestedMeta::Meta(syn::Meta::List(list)) => {
+                let value = match list.nested.first() {
+                    Some(syn::NestedMeta::Lit(syn::Lit::Int(int))) => int.base10_parse().ok(),
+                    _ => None,
+                };
+                if list.path.is_ident("packed") {
+                    repr.packed = value;
+                } else if list.path.is_ident("align") {
+                    repr.align = value;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    match repr {
+        None if is_zero_copy(attrs) => Some(IdlRepr {
+            kind: IdlReprKind::C,
+            int: None,
+            packed: None,
+            align: None,
+        }),
+        repr => repr,
+    }
+}
+
+// Whether a type is declared by `#[zero_copy]` or `#[account(zero_copy)]`.
+fn is_zero_copy(attrs: &[syn::Attribute]) -> bool {
+    attrs.iter().any(|attr| {
+        let name = attr.path.segments.last().unwrap().ident.to_string();
+        name == "zero_copy" || (name == "account" && attr.tokens.to_string().contains("zero_copy"))
+    })
+}
+
+// Adds the discriminator of an account, and the arguments given to
+// `#[account(..)]`, i.e. the version and all previous layouts of a versioned
+// account, as given by `#[account(version = 2, migrate(1 = DataV1))]`.
+fn account_ty_def(
+    strct: &syn::ItemStruct,
+    mut ty_def: IdlTypeDefinition,
+    ty_defs: &[IdlTypeDefinition],
+) -> Result<IdlTypeDefinition> {
+    let attr = strct
+        .attrs
+        .iter()
+        .find(|attr| matches!(attr.path.segments.last(), Some(s) if s.ident == "account"))
+        .ok_or_else(|| anyhow::anyhow!("Could not find #[account] on {}", strct.ident))?;
+    let args: AccountArgs = match attr.tokens.is_empty() {
+        true => AccountArgs::default(),
+        false => attr.parse_args()?,
+    };
+    ty_def.discriminator = Some(match (args.discriminator, args.namespace) {
+        (Some(discriminator), _) => discriminator.bytes,
+        (None, Some(namespace)) => sighash(&namespace, &ty_def.name).to_vec(),
+        (None, None) => sighash("account", &ty_def.name).to_vec(),
+    });
+    ty_def.version = args.version.map(|v| v.version);
+    ty_def.history = args
+        .migrations
+        .iter()
+        .map(|m| {
+            let layout = m
+                .layout
+                .segments
+                .last()
+                .ok_or_else(|| anyhow::anyhow!("Missing layout for version {}", m.version))?
+                .ident
+                .to_string();
+            let prev = ty_defs.iter().find(|t| t.name == layout).ok_or_else(|| {
+                anyhow::anyhow!(
+                    "Could not find layout {} for version {} of account {}",
+                    layout,
+                    m.version,
+                    ty_def.name
+                )
+            })?;
+            Ok(IdlTypeDefinitionVersion {
+                version: m.version,
+                ty: prev.ty.clone(),
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(ty_def)
+}
+
+// Converts a Rust type to its IDL type, resolving constant array lengths and
+// type aliases. `generics` are the names of the generic parameters of the
+// enclosing type definition.
+fn idl_type(ctx: &CrateContext, ty: &syn::Type, generics: &[String]) -> Result<IdlType> {
+    parser::tts_to_string(ty)
+        .parse::<IdlType>()?
+        .map(&mut |ty| match ty {
+            IdlType::Defined(name) if generics.contains(&name) => Ok(IdlType::Generic(name)),
+            IdlType::Defined(name) => match type_alias(ctx, &name)? {
+                Some((alias_ctx, alias)) => {
+                    if !alias.generics.params.is_empty() {
+                        return Err(anyhow::anyhow!(
+                            "Missing generic arguments for type alias {}",
+                            name
+                        ));
+                    }
+                    idl_type(alias_ctx, &alias.ty, &[])
+                }
+                None => Ok(IdlType::Defined(name)),
+            },
+            IdlType::DefinedWithTypeArgs { name, args } => {
+                let args = args
+                    .into_iter()
+                    .map(|arg| match arg {
+                        IdlDefinedTypeArg::Type(IdlType::Generic(name)) => {
+                            Ok(IdlDefinedTypeArg::Generic(name))
                         }
-                        syn::Fields::Named(fields) => {
-                            let fields: Vec<IdlField> = fields
-                                .named
-                                .iter()
-                                .map(|f: &syn::Field| {
-                                    let name = f.ident.as_ref().unwrap().to_string();
-                                    let ty = to_idl_type(f);
-                                    IdlField { name, ty }
-                                })
-                                .collect();
-                            Some(EnumFields::Named(fields))
+                        // Const generic arguments given by a constant.
+                        IdlDefinedTypeArg::Type(IdlType::Defined(name))
+                            if find_const(ctx, &name)?.is_some() =>
+                        {
+                            Ok(IdlDefinedTypeArg::Value(
+                                array_length(ctx, &name)?.to_string(),
+                            ))
                         }
-                    };
-                    IdlEnumVariant { name, fields }
+                        arg => Ok(arg),
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                match type_alias(ctx, &name)? {
+                    Some((alias_ctx, alias)) => {
+                        let generics = idl_generics(&alias.generics);
+                        if generics.len() != args.len() {
+                            return Err(anyhow::anyhow!(
+                                "Expected {} generic arguments for type alias {}",
+                                generics.len(),
+                                name
+                            ));
+                        }
+                        idl_type(alias_ctx, &alias.ty, &generic_names(&generics))?
+                            .map(&mut |ty| substitute_generic(ty, &generics, &args))
+                    }
+                    None => Ok(IdlType::DefinedWithTypeArgs { name, args }),
+                }
+            }
+            IdlType::GenericLenArray(ty, len) if !generics.contains(&len) => {
+                Ok(IdlType::Array(ty, array_length(ctx, &len)?))
+            }
+            ty => Ok(ty),
+        })
+}
+
+// Replaces a generic parameter of a generic type alias with its argument.
+fn substitute_generic(
+    ty: IdlType,
+    generics: &[IdlTypeDefinitionGeneric],
+    args: &[IdlDefinedTypeArg],
+) -> Result<IdlType> {
+    let arg = |name: &str| {
+        let idx = generic_names(generics)
+            .iter()
+            .position(|g| g == name)
+            .unwrap();
+        &args[idx]
+    };
+    Ok(match ty {
+        IdlType::Generic(name) => match arg(&name) {
+            IdlDefinedTypeArg::Type(ty) => ty.clone(),
+            IdlDefinedTypeArg::Generic(name) => IdlType::Generic(name.clone()),
+            IdlDefinedTypeArg::Value(_) => {
+                return Err(anyhow::anyhow!("Expected a type for generic {}", name))
+            }
+        },
+        IdlType::GenericLenArray(ty, len) => match arg(&len) {
+            IdlDefinedTypeArg::Value(value) => IdlType::Array(ty, value.parse()?),
+            IdlDefinedTypeArg::Generic(name) => IdlType::GenericLenArray(ty, name.clone()),
+            IdlDefinedTypeArg::Type(_) => {
+                return Err(anyhow::anyhow!("Expected a value for generic {}", len))
+            }
+        },
+        IdlType::DefinedWithTypeArgs { name, args: inner } => IdlType::DefinedWithTypeArgs {
+            name,
+            args: inner
+                .into_iter()
+                .map(|inner| match inner {
+                    IdlDefinedTypeArg::Generic(name) => match arg(&name) {
+                        IdlDefinedTypeArg::Type(IdlType::Generic(name)) => {
+                            IdlDefinedTypeArg::Generic(name.clone())
+                        }
+                        arg => arg.clone(),
+                    },
+                    inner => inner,
                 })
-                .collect::<Vec<IdlEnumVariant>>();
-            Ok(IdlTypeDefinition {
-                name,
-                ty: IdlTypeDefinitionTy::Enum { variants },
-            })
-        }))
-        .collect()
+                .collect(),
+        },
+        ty => ty,
+    })
+}
+
+// Finds the type alias with the given name, in the crate or else in its
+// dependencies, along with the crate defining it.
+fn type_alias<'a>(
+    ctx: &'a CrateContext,
+    name: &str,
+) -> Result<Option<(&'a CrateContext, &'a syn::ItemType)>> {
+    let mut aliases = ctx.type_aliases().filter(|alias| alias.ident == name);
+    match aliases.next() {
+        Some(alias) => {
+            // We can't know which alias was intended if names aren't unique.
+            if aliases.any(|other| other.ty != alias.ty) {
+                return Err(anyhow::anyhow!(
+                    "Crate wide unique name required for type alias {}",
+                    name
+                ));
+            }
+            Ok(Some((ctx, alias)))
+        }
+        None => {
+            for dep in ctx.dependencies() {
+                if let Some(alias) = type_alias(dep, name)? {
+                    return Ok(Some(alias));
+                }
+            }
+            Ok(None)
+        }
+    }
 }
 
-// Replace variable array lengths with values
-fn resolve_variable_array_length(ctx: &CrateContext, tts_string: String) -> String {
-    for constant in ctx.consts() {
-        if constant.ty.to_token_stream().to_string() == "usize"
-            && tts_string.contains(&constant.ident.to_string())
-        {
+// Finds the constant with the given name, in the crate or else in its
+// dependencies.
+pub(crate) fn find_const<'a>(
+    ctx: &'a CrateContext,
+    name: &str,
+) -> Result<Option<&'a syn::ItemConst>> {
+    let mut consts = ctx.consts().filter(|c| c.ident == name);
+    match consts.next() {
+        Some(constant) => {
             // Check for the existence of consts existing elsewhere in the
-            // crate which have the same name, are usize, and have a
-            // different value. We can't know which was intended for the
-            // array size from ctx.
-            if ctx.consts().any(|c| {
-                c != constant
-                    && c.ident == constant.ident
-                    && c.ty == constant.ty
-                    && c.expr != constant.expr
-            }) {
-                panic!("Crate wide unique name required for array size const.");
+            // crate which have the same name and a different value. We can't
+            // know which was intended from ctx.
+            if consts.any(|c| c.expr != constant.expr) {
+                return Err(anyhow::anyhow!(
+                    "Crate wide unique name required for const {}",
+                    name
+                ));
             }
-            return tts_string.replace(
-                &constant.ident.to_string(),
-                &constant.expr.to_token_stream().to_string(),
-            );
+            Ok(Some(constant))
+        }
+        None => {
+            for dep in ctx.dependencies() {
+                if let Some(constant) = find_const(dep, name)? {
+                    return Ok(Some(constant));
+                }
+            }
+            Ok(None)
         }
     }
-    tts_string
 }
 
-fn to_idl_type(f: &syn::Field) -> IdlType {
-    let mut tts = proc_macro2::TokenStream::new();
-    f.ty.to_tokens(&mut tts);
-    tts.to_string().parse().unwrap()
+// Resolves an array length given by a constant.
+fn array_length(ctx: &CrateContext, name: &str) -> Result<usize> {
+    let name = name.rsplit("::").next().unwrap();
+    let constant = find_const(ctx, name)?
+        .ok_or_else(|| anyhow::anyhow!("Could not resolve array length {}", name))?;
+    let mut value = parser::tts_to_string(&constant.expr);
+    value.retain(|c| !c.is_whitespace() && c != '_');
+    value
+        .trim_end_matches("usize")
+        .parse()
+        .map_err(|_| anyhow::anyhow!("Array size const {} must be an integer literal", name))
+}
+
+fn idl_generics(generics: &syn::Generics) -> Vec<IdlTypeDefinitionGeneric> {
+    generics
+        .params
+        .iter()
+        .filter_map(|param| match param {
+            syn::GenericParam::Type(param) => Some(IdlTypeDefinitionGeneric::Type {
+                name: param.ident.to_string(),
+            }),
+            syn::GenericParam::Const(param) => Some(IdlTypeDefinitionGeneric::Const {
+                name: param.ident.to_string(),
+                ty: parser::tts_to_string(&param.ty),
+            }),
+            syn::GenericParam::Lifetime(_) => None,
+        })
+        .collect()
+}
+
+fn generic_names(generics: &[IdlTypeDefinitionGeneric]) -> Vec<String> {
+    generics
+        .iter()
+        .map(|generic| match generic {
+            IdlTypeDefinitionGeneric::Type { name } => name.clone(),
+            IdlTypeDefinitionGeneric::Const { name, .. } => name.clone(),
+        })
+        .collect()
 }
 
 fn idl_accounts(
     ctx: &CrateContext,
     accounts: &AccountsStruct,
     global_accs: &HashMap<String, AccountsStruct>,
-    seeds_feature: bool,
 ) -> Vec<IdlAccountItem> {
     accounts
         .fields
@@ -516,7 +1176,7 @@ fn idl_accounts(
                 let accs_strct = global_accs
                     .get(&comp_f.symbol)
                     .expect("Could not resolve Accounts symbol");
-                let accounts = idl_accounts(ctx, accs_strct, global_accs, seeds_feature);
+                let accounts = idl_accounts(ctx, accs_strct, global_accs);
                 IdlAccountItem::IdlAccounts(IdlAccounts {
                     name: comp_f.ident.to_string().to_mixed_case(),
                     accounts,
@@ -529,8 +1189,242 @@ fn idl_accounts(
                     Ty::Signer => true,
                     _ => acc.constraints.is_signer(),
                 },
-                pda: pda::parse(ctx, accounts, acc, seeds_feature),
+                pda: pda::parse(ctx, accounts, acc),
+                relations: relations(acc),
+                address: acc
+                    .constraints
+                    .address
+                    .as_ref()
+                    .and_then(|c| static_pubkey(accounts, &c.address))
+                    .and_then(|path| resolve_pubkey(ctx, path)),
+                owner: acc
+                    .constraints
+                    .owner
+                    .as_ref()
+                    .and_then(|c| static_pubkey(accounts, &c.owner_address))
+                    .and_then(|path| resolve_pubkey(ctx, path)),
+                payer: payer(acc),
             }),
         })
         .collect::<Vec<_>>()
 }
+
+// Names of the accounts whose keys the account stores, given by its `has_one`
+// constraints.
+pub(crate) fn relations(acc: &Field) -> Vec<String> {
+    acc.constraints
+        .has_one
+        .iter()
+        .map(|c| parser::tts_to_string(&c.join_target).to_mixed_case())
+        .collect()
+}
+
+// Name of the account paying for the creation of the account.
+pub(crate) fn payer(acc: &Field) -> Option<String> {
+    match acc.constraints.init.as_ref()?.payer.as_ref()? {
+        syn::Expr::Path(payer) => payer
+            .path
+            .get_ident()
+            .map(|p| p.to_string().to_mixed_case()),
+        _ => None,
+    }
+}
+
+// The path of a pubkey given to an `address` or `owner` constraint, if it
+// names a constant, i.e. doesn't depend on the accounts, the instruction
+// arguments or any other local.
+pub(crate) fn static_pubkey<'a>(
+    accounts: &AccountsStruct,
+    expr: &'a syn::Expr,
+) -> Option<&'a syn::Path> {
+    let path = match expr {
+        syn::Expr::Path(expr) if expr.qself.is_none() => &expr.path,
+        _ => return None,
+    };
+    let args = accounts.instruction_args().unwrap_or_default();
+    match path.get_ident().map(|ident| ident.to_string()) {
+        Some(ident) if accounts.field_names().contains(&ident) => None,
+        Some(ident) if args.contains_key(&ident) => None,
+        // Constants are upper case, anything else is a local.
+        Some(ident) if ident != ident.to_uppercase() => None,
+        _ => Some(path),
+    }
+}
+
+// Resolves a constant pubkey, either an `ID` declared by `declare_id!` or a
+// constant defined with `pubkey!`.
+pub(crate) fn resolve_pubkey(ctx: &CrateContext, path: &syn::Path) -> Option<String> {
+    let mut segments = path.segments.iter().rev().map(|s| s.ident.to_string());
+    let name = segments.next()?;
+    if name == "ID" {
+        let module = match segments.next() {
+            None => ctx.root_module(),
+            Some(module) if module == "crate" => ctx.root_module(),
+            Some(module) => ctx.modules().find(|m| m.name() == module)?,
+        };
+        return module.items().find_map(|item| match item {
+            syn::Item::Macro(item) if is_macro(&item.mac, "declare_id") => item
+                .mac
+                .parse_body::<syn::LitStr>()
+                .ok()
+                .map(|id| id.value()),
+            _ => None,
+        });
+    }
+    match &find_const(ctx, &name).ok()??.expr.as_ref() {
+        syn::Expr::Macro(expr) if is_macro(&expr.mac, "pubkey") => expr
+            .mac
+            .parse_body::<syn::LitStr>()
+            .ok()
+            .map(|key| key.value()),
+        _ => None,
+    }
+}
+
+fn is_macro(mac: &syn::Macro, name: &str) -> bool {
+    mac.path.segments.last().map(|s| s.ident == name) == Some(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn evaluates_discriminants() {
+        let root = std::env::temp_dir().join(format!("anchor-idl-{}.rs", std::process::id()));
+        std::fs::write(&root, "pub const BASE: i8 = 1 << 4;").unwrap();
+        let ctx = CrateContext::parse(&root).unwrap();
+        std::fs::remove_file(&root).unwrap();
+
+        let enm: syn::ItemEnum = syn::parse_quote! {
+            #[repr(i8)]
+            enum Side { Bid, Ask = BASE + 2, Mid, Neg = -1 }
+        };
+        assert_eq!(
+            discriminants(&ctx, &enm),
+            vec![Some(0), Some(18), Some(19), Some(-1)]
+        );
+        let enm: syn::ItemEnum = syn::parse_quote! { enum Side { Bid, Ask } };
+        assert_eq!(discriminants(&ctx, &enm), vec![None, None]);
+
+        let enm: syn::ItemEnum = syn::parse_quote! {
+            enum Cast { Byte = 300 as u8, Neg = 255 as i8, Wide = -1 as u16, Big = (1 << 40) as u32 }
+        };
+        assert_eq!(
+            discriminants(&ctx, &enm),
+            vec![Some(44), Some(-1), Some(65535), Some(0)]
+        );
+        // Unsupported values don't fail the IDL.
+        let enm: syn::ItemEnum = syn::parse_quote! {
+            enum Other { A = Side::Ask as isize, B, C = 7, D = value() }
+        };
+        assert_eq!(discriminants(&ctx, &enm), vec![None, None, Some(7), None]);
+    }
+
+    #[test]
+    fn parses_account_constraints() {
+        let root = std::env::temp_dir().join(format!("anchor-idl-accs-{}.rs", std::process::id()));
+        std::fs::write(
+            &root,
+            r#"
+            declare_id!("Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS");
+            pub const ADMIN: Pubkey = pubkey!("9xQeWvG816bUx9EPjHmaT23yvVM2ZWbrrpZb9PusVFin");
+            "#,
+        )
+        .unwrap();
+        let ctx = CrateContext::parse(&root).unwrap();
+        std::fs::remove_file(&root).unwrap();
+
+        let strct: syn::ItemStruct = syn::parse_quote! {
+            #[derive(Accounts)]
+            #[instruction(expected: Pubkey)]
+            pub struct Initialize<'info> {
+                #[account(init, payer = payer, space = 8 + 32)]
+                pub data: Account<'info, Data>,
+                #[account(mut, has_one = authority, has_one = admin_key)]
+                pub config: Account<'info, Config>,
+                pub authority: Signer<'info>,
+                #[account(address = ADMIN)]
+                pub admin_key: AccountInfo<'info>,
+                #[account(address = expected)]
+                pub expected: AccountInfo<'info>,
+                #[account(owner = crate::ID, address = authority.key())]
+                pub owned: AccountInfo<'info>,
+                #[account(mut)]
+                pub payer: Signer<'info>,
+                pub system_program: Program<'info, System>,
+            }
+        };
+        let accs = accounts::parse(&strct).unwrap();
+        let idl = idl_accounts(&ctx, &accs, &HashMap::new());
+        let account = |name: &str| {
+            idl.iter()
+                .find_map(|acc| match acc {
+                    IdlAccountItem::IdlAccount(acc) if acc.name == name => Some(acc),
+                    _ => None,
+                })
+                .unwrap()
+        };
+
+        assert_eq!(account("data").payer.as_deref(), Some("payer"));
+        assert_eq!(account("config").relations, vec!["authority", "adminKey"]);
+        assert_eq!(
+            account("adminKey").address.as_deref(),
+            Some("9xQeWvG816bUx9EPjHmaT23yvVM2ZWbrrpZb9PusVFin")
+        );
+        // An instruction argument isn't known until the instruction is sent.
+        assert_eq!(account("expected").address, None);
+        assert_eq!(
+            account("owned").owner.as_deref(),
+            Some("Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS")
+        );
+        assert_eq!(account("owned").address, None);
+    }
+
+    #[test]
+    fn static_pubkeys() {
+        let strct: syn::ItemStruct = syn::parse_quote! {
+            #[derive(Accounts)]
+            #[instruction(expected: Pubkey)]
+            pub struct Check<'info> {
+                pub authority: Signer<'info>,
+            }
+        };
+        let accs = accounts::parse(&strct).unwrap();
+        let is_static = |expr: syn::Expr| static_pubkey(&accs, &expr).is_some();
+        assert!(is_static(syn::parse_quote!(ADMIN)));
+        assert!(is_static(syn::parse_quote!(crate::ID)));
+        assert!(is_static(syn::parse_quote!(spl_token::ID)));
+        assert!(!is_static(syn::parse_quote!(expected)));
+        assert!(!is_static(syn::parse_quote!(authority)));
+        assert!(!is_static(syn::parse_quote!(local_key)));
+        assert!(!is_static(syn::parse_quote!(authority.key())));
+    }
+
+    #[test]
+    fn parses_repr() {
+        let strct: syn::ItemStruct = syn::parse_quote! {
+            #[repr(C, packed(2), align(8))]
+            struct Data {}
+        };
+        assert_eq!(
+            serde_json::to_value(idl_repr(&strct.attrs)).unwrap(),
+            serde_json::json!({"kind": "c", "packed": 2, "align": 8})
+        );
+        let strct: syn::ItemStruct = syn::parse_quote! {
+            #[account(zero_copy)]
+            struct Data {}
+        };
+        assert_eq!(idl_repr(&strct.attrs).unwrap().kind, IdlReprKind::C);
+        assert!(is_zero_copy(&strct.attrs));
+        let strct: syn::ItemStruct = syn::parse_quote! {
+            #[account]
+            #[repr(C)]
+            struct Data {}
+        };
+        assert_eq!(idl_repr(&strct.attrs).unwrap().kind, IdlReprKind::C);
+        assert!(!is_zero_copy(&strct.attrs));
+        let strct: syn::ItemStruct = syn::parse_quote! { struct Data {} };
+        assert!(idl_repr(&strct.attrs).is_none());
+    }
+}
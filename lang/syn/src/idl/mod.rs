@@ -1,11 +1,24 @@
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
 
+pub mod build;
+pub mod diff;
 pub mod file;
 pub mod pda;
+pub mod size;
+pub mod spec;
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+// The JSON Schemas of the types make up the IDL spec, see `spec::schema`.
+// Unknown fields are rejected by the schema, though ignored when
+// deserializing.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, JsonSchema)]
+#[schemars(deny_unknown_fields)]
 pub struct Idl {
+    // Version of the IDL format, `spec::IDL_SPEC` when generated. Empty for
+    // IDLs from before it was recorded.
+    #[serde(default)]
+    pub spec: String,
     pub version: String,
     pub name: String,
     #[serde(skip_serializing_if = "Vec::is_empty", default)]
@@ -25,7 +38,8 @@ pub struct Idl {
     pub metadata: Option<JsonValue>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
+#[schemars(deny_unknown_fields)]
 pub struct IdlConst {
     pub name: String,
     #[serde(rename = "type")]
@@ -33,35 +47,43 @@ pub struct IdlConst {
     pub value: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
+#[schemars(deny_unknown_fields)]
 pub struct IdlState {
     #[serde(rename = "struct")]
     pub strct: IdlTypeDefinition,
     pub methods: Vec<IdlInstruction>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
+#[schemars(deny_unknown_fields)]
 pub struct IdlInstruction {
     pub name: String,
     pub accounts: Vec<IdlAccountItem>,
     pub args: Vec<IdlField>,
+    // Explicit discriminator, if not derived from the instruction name.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub discriminator: Option<Vec<u8>>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
+#[schemars(deny_unknown_fields)]
 #[serde(rename_all = "camelCase")]
 pub struct IdlAccounts {
     pub name: String,
     pub accounts: Vec<IdlAccountItem>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
 #[serde(untagged)]
 pub enum IdlAccountItem {
     IdlAccount(IdlAccount),
     IdlAccounts(IdlAccounts),
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
+#[schemars(deny_unknown_fields)]
 #[serde(rename_all = "camelCase")]
 pub struct IdlAccount {
     pub name: String,
@@ -69,9 +91,22 @@ pub struct IdlAccount {
     pub is_signer: bool,
     #[serde(skip_serializing_if = "Option::is_none", default)]
     pub pda: Option<IdlPda>,
+    // Accounts whose keys are stored in this account, by `has_one`.
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub relations: Vec<String>,
+    // Fixed address of the account, by an `address` constraint.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub address: Option<String>,
+    // Fixed owner of the account, by an `owner` constraint.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub owner: Option<String>,
+    // Account paying for the account's creation, by `init`.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub payer: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
+#[schemars(deny_unknown_fields)]
 #[serde(rename_all = "camelCase")]
 pub struct IdlPda {
     pub seeds: Vec<IdlSeed>,
@@ -79,7 +114,8 @@ pub struct IdlPda {
     pub program_id: Option<IdlSeed>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
+#[schemars(deny_unknown_fields)]
 #[serde(rename_all = "camelCase", tag = "kind")]
 pub enum IdlSeed {
     Const(IdlSeedConst),
@@ -87,7 +123,8 @@ pub enum IdlSeed {
     Account(IdlSeedAccount),
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
+#[schemars(deny_unknown_fields)]
 #[serde(rename_all = "camelCase")]
 pub struct IdlSeedAccount {
     #[serde(rename = "type")]
@@ -97,38 +134,58 @@ pub struct IdlSeedAccount {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub account: Option<String>,
     pub path: String,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub encoding: Option<IdlSeedEncoding>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
+#[schemars(deny_unknown_fields)]
 #[serde(rename_all = "camelCase")]
 pub struct IdlSeedArg {
     #[serde(rename = "type")]
     pub ty: IdlType,
     pub path: String,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub encoding: Option<IdlSeedEncoding>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
+#[schemars(deny_unknown_fields)]
 #[serde(rename_all = "camelCase")]
 pub struct IdlSeedConst {
     #[serde(rename = "type")]
     pub ty: IdlType,
     pub value: serde_json::Value,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub encoding: Option<IdlSeedEncoding>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+// Byte order of integer seeds.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum IdlSeedEncoding {
+    Le,
+    Be,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
+#[schemars(deny_unknown_fields)]
 pub struct IdlField {
     pub name: String,
     #[serde(rename = "type")]
     pub ty: IdlType,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
+#[schemars(deny_unknown_fields)]
 pub struct IdlEvent {
     pub name: String,
     pub fields: Vec<IdlEventField>,
+    pub discriminator: Vec<u8>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
+#[schemars(deny_unknown_fields)]
 pub struct IdlEventField {
     pub name: String,
     #[serde(rename = "type")]
@@ -136,35 +193,120 @@ pub struct IdlEventField {
     pub index: bool,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
+#[schemars(deny_unknown_fields)]
 pub struct IdlTypeDefinition {
     pub name: String,
+    // Generic parameters, substituted by `IdlType::DefinedWithTypeArgs`.
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub generics: Vec<IdlTypeDefinitionGeneric>,
     #[serde(rename = "type")]
     pub ty: IdlTypeDefinitionTy,
+    // Layout version of a versioned account, stored after the discriminator.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub version: Option<u8>,
+    // Previous layouts of a versioned account.
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub history: Vec<IdlTypeDefinitionVersion>,
+    // Discriminator of an account. Not set for other types.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub discriminator: Option<Vec<u8>>,
+    // Memory layout, if given by `#[repr(..)]` or implied by `#[zero_copy]`.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub repr: Option<IdlRepr>,
+    // Whether the type is stored as it's laid out in memory, by `#[zero_copy]`
+    // or `#[account(zero_copy)]`, rather than Borsh encoded.
+    #[serde(
+        rename = "zeroCopy",
+        skip_serializing_if = "std::ops::Not::not",
+        default
+    )]
+    pub zero_copy: bool,
+    // Size in bytes of the type, if fixed, as computed by `size::set_sizes`.
+    // For accounts, the length of the account data.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub size: Option<u64>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+// Memory layout of a type, which determines how it's stored in zero copy
+// accounts.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
+#[schemars(deny_unknown_fields)]
+pub struct IdlRepr {
+    pub kind: IdlReprKind,
+    // Integer type of an enum's discriminant, e.g. `u8` by `#[repr(u8)]`.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub int: Option<IdlType>,
+    // Maximum alignment of the fields, by `packed` or `packed(N)`.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub packed: Option<usize>,
+    // Minimum alignment of the type, by `align(N)`.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub align: Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum IdlReprKind {
+    Rust,
+    C,
+    Transparent,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
+#[schemars(deny_unknown_fields)]
+pub struct IdlTypeDefinitionVersion {
+    pub version: u8,
+    #[serde(rename = "type")]
+    pub ty: IdlTypeDefinitionTy,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
+#[schemars(deny_unknown_fields)]
+#[serde(rename_all = "lowercase", tag = "kind")]
+pub enum IdlTypeDefinitionGeneric {
+    Type {
+        name: String,
+    },
+    Const {
+        name: String,
+        #[serde(rename = "type")]
+        ty: String,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
+#[schemars(deny_unknown_fields)]
 #[serde(rename_all = "lowercase", tag = "kind")]
 pub enum IdlTypeDefinitionTy {
     Struct { fields: Vec<IdlField> },
+    // Tuple struct, e.g. `struct Price(u64)`.
+    Tuple { fields: Vec<IdlType> },
     Enum { variants: Vec<IdlEnumVariant> },
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
+#[schemars(deny_unknown_fields)]
 pub struct IdlEnumVariant {
     pub name: String,
     #[serde(skip_serializing_if = "Option::is_none", default)]
     pub fields: Option<EnumFields>,
+    // Value of the variant in memory, if any variant of the enum is given an
+    // explicit one, e.g. `A = 5`. Borsh encodes variants by their position
+    // regardless.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub discriminant: Option<i64>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
 #[serde(untagged)]
 pub enum EnumFields {
     Named(Vec<IdlField>),
     Tuple(Vec<IdlType>),
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
+#[schemars(deny_unknown_fields)]
 #[serde(rename_all = "camelCase")]
 pub enum IdlType {
     Bool,
@@ -184,9 +326,65 @@ pub enum IdlType {
     String,
     PublicKey,
     Defined(String),
+    // A generic type, e.g. `Wrapper<u64>`.
+    DefinedWithTypeArgs {
+        name: String,
+        args: Vec<IdlDefinedTypeArg>,
+    },
+    // A generic parameter of the enclosing type definition.
+    Generic(String),
     Option(Box<IdlType>),
     Vec(Box<IdlType>),
     Array(Box<IdlType>, usize),
+    // An array whose length is a const generic parameter.
+    GenericLenArray(Box<IdlType>, String),
+    HashMap(Box<IdlType>, Box<IdlType>),
+    BTreeMap(Box<IdlType>, Box<IdlType>),
+    HashSet(Box<IdlType>),
+    BTreeSet(Box<IdlType>),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum IdlDefinedTypeArg {
+    // A generic parameter of the enclosing type definition.
+    Generic(String),
+    // A const generic value, e.g. an array length.
+    Value(String),
+    Type(IdlType),
+}
+
+impl IdlType {
+    // Calls `f` on every type nested in `self`, including `self`, bottom up.
+    pub fn map(
+        self,
+        f: &mut impl FnMut(IdlType) -> anyhow::Result<IdlType>,
+    ) -> anyhow::Result<Self> {
+        let ty = match self {
+            IdlType::DefinedWithTypeArgs { name, args } => IdlType::DefinedWithTypeArgs {
+                name,
+                args: args
+                    .into_iter()
+                    .map(|arg| match arg {
+                        IdlDefinedTypeArg::Type(ty) => ty.map(f).map(IdlDefinedTypeArg::Type),
+                        _ => Ok(arg),
+                    })
+                    .collect::<anyhow::Result<_>>()?,
+            },
+            IdlType::Option(ty) => IdlType::Option(Box::new(ty.map(f)?)),
+            IdlType::Vec(ty) => IdlType::Vec(Box::new(ty.map(f)?)),
+            IdlType::Array(ty, len) => IdlType::Array(Box::new(ty.map(f)?), len),
+            IdlType::GenericLenArray(ty, len) => {
+                IdlType::GenericLenArray(Box::new(ty.map(f)?), len)
+            }
+            IdlType::HashMap(k, v) => IdlType::HashMap(Box::new(k.map(f)?), Box::new(v.map(f)?)),
+            IdlType::BTreeMap(k, v) => IdlType::BTreeMap(Box::new(k.map(f)?), Box::new(v.map(f)?)),
+            IdlType::HashSet(ty) => IdlType::HashSet(Box::new(ty.map(f)?)),
+            IdlType::BTreeSet(ty) => IdlType::BTreeSet(Box::new(ty.map(f)?)),
+            ty => ty,
+        };
+        f(ty)
+    }
 }
 
 impl std::str::FromStr for IdlType {
@@ -194,13 +392,17 @@ impl std::str::FromStr for IdlType {
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let mut s = s.to_string();
-        fn array_from_str(inner: &str) -> IdlType {
+        fn array_from_str(inner: &str) -> Result<IdlType, anyhow::Error> {
             match inner.strip_suffix(']') {
                 None => {
-                    let (raw_type, raw_length) = inner.rsplit_once(';').unwrap();
-                    let ty = IdlType::from_str(raw_type).unwrap();
-                    let len = raw_length.replace('_', "").parse::<usize>().unwrap();
-                    IdlType::Array(Box::new(ty), len)
+                    let (raw_type, raw_length) = inner
+                        .rsplit_once(';')
+                        .ok_or_else(|| anyhow::anyhow!("Invalid array"))?;
+                    let ty = Box::new(IdlType::from_str(raw_type)?);
+                    Ok(match raw_length.replace('_', "").parse::<usize>() {
+                        Ok(len) => IdlType::Array(ty, len),
+                        Err(_) => IdlType::GenericLenArray(ty, raw_length.to_string()),
+                    })
                 }
                 Some(nested_inner) => array_from_str(&nested_inner[1..]),
             }
@@ -224,31 +426,35 @@ impl std::str::FromStr for IdlType {
             "Vec<u8>" => IdlType::Bytes,
             "String" | "&str" => IdlType::String,
             "Pubkey" => IdlType::PublicKey,
-            _ => match s.to_string().strip_prefix("Option<") {
-                None => match s.to_string().strip_prefix("Vec<") {
-                    None => {
-                        if s.to_string().starts_with('[') {
-                            array_from_str(&s)
-                        } else {
-                            IdlType::Defined(s.to_string())
-                        }
-                    }
-                    Some(inner) => {
-                        let inner_ty = Self::from_str(
-                            inner
-                                .strip_suffix('>')
-                                .ok_or_else(|| anyhow::anyhow!("Invalid option"))?,
-                        )?;
-                        IdlType::Vec(Box::new(inner_ty))
+            _ if s.starts_with('[') => array_from_str(&s)?,
+            _ => match s.split_once('<') {
+                None => IdlType::Defined(strip_path(&s).to_string()),
+                Some((name, args)) => {
+                    let args = args
+                        .strip_suffix('>')
+                        .ok_or_else(|| anyhow::anyhow!("Invalid generic type: {}", s))?;
+                    let args = split_generic_args(args);
+                    let arg = |idx: usize| Self::from_str(args[idx]).map(Box::new);
+                    match (strip_path(name), args.len()) {
+                        ("Option", 1) => IdlType::Option(arg(0)?),
+                        ("Vec", 1) => IdlType::Vec(arg(0)?),
+                        // Boxes are serialized as their contents.
+                        ("Box", 1) => *arg(0)?,
+                        ("HashMap", 2) => IdlType::HashMap(arg(0)?, arg(1)?),
+                        ("BTreeMap", 2) => IdlType::BTreeMap(arg(0)?, arg(1)?),
+                        ("HashSet", 1) => IdlType::HashSet(arg(0)?),
+                        ("BTreeSet", 1) => IdlType::BTreeSet(arg(0)?),
+                        (name, _) => IdlType::DefinedWithTypeArgs {
+                            name: name.to_string(),
+                            args: args
+                                .iter()
+                                .map(|arg| match arg.replace('_', "").parse::<usize>() {
+                                    Ok(value) => Ok(IdlDefinedTypeArg::Value(value.to_string())),
+                                    Err(_) => Self::from_str(arg).map(IdlDefinedTypeArg::Type),
+                                })
+                                .collect::<Result<_, Self::Err>>()?,
+                        },
                     }
-                },
-                Some(inner) => {
-                    let inner_ty = Self::from_str(
-                        inner
-                            .strip_suffix('>')
-                            .ok_or_else(|| anyhow::anyhow!("Invalid option"))?,
-                    )?;
-                    IdlType::Option(Box::new(inner_ty))
                 }
             },
         };
@@ -256,7 +462,36 @@ impl std::str::FromStr for IdlType {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+// Strips the module path from a type name, e.g. `std::collections::HashMap`.
+fn strip_path(name: &str) -> &str {
+    name.rsplit("::").next().unwrap()
+}
+
+// Splits generic arguments on the commas that are not nested in another
+// generic, array or tuple type.
+fn split_generic_args(args: &str) -> Vec<&str> {
+    let mut split = vec![];
+    let mut depth = 0;
+    let mut start = 0;
+    for (idx, c) in args.char_indices() {
+        match c {
+            '<' | '[' | '(' => depth += 1,
+            '>' | ']' | ')' => depth -= 1,
+            ',' if depth == 0 => {
+                split.push(&args[start..idx]);
+                start = idx + 1;
+            }
+            _ => {}
+        }
+    }
+    if start < args.len() {
+        split.push(&args[start..]);
+    }
+    split
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
+#[schemars(deny_unknown_fields)]
 pub struct IdlErrorCode {
     pub code: u32,
     pub name: String,
@@ -266,7 +501,7 @@ pub struct IdlErrorCode {
 
 #[cfg(test)]
 mod tests {
-    use crate::idl::IdlType;
+    use crate::idl::{IdlDefinedTypeArg, IdlType};
     use std::str::FromStr;
 
     #[test]
@@ -308,4 +543,65 @@ mod tests {
             IdlType::Vec(Box::new(IdlType::Bool))
         )
     }
+
+    #[test]
+    fn boxed() {
+        assert_eq!(
+            IdlType::from_str("Box<Option<Pubkey>>").unwrap(),
+            IdlType::Option(Box::new(IdlType::PublicKey))
+        )
+    }
+
+    #[test]
+    fn maps_and_sets() {
+        assert_eq!(
+            IdlType::from_str("std::collections::BTreeMap<Pubkey, Vec<Pair<u8, u16>>>").unwrap(),
+            IdlType::BTreeMap(
+                Box::new(IdlType::PublicKey),
+                Box::new(IdlType::Vec(Box::new(IdlType::DefinedWithTypeArgs {
+                    name: "Pair".into(),
+                    args: vec![
+                        IdlDefinedTypeArg::Type(IdlType::U8),
+                        IdlDefinedTypeArg::Type(IdlType::U16),
+                    ]
+                })))
+            )
+        );
+        assert_eq!(
+            IdlType::from_str("HashMap<String, [u8; 2]>").unwrap(),
+            IdlType::HashMap(
+                Box::new(IdlType::String),
+                Box::new(IdlType::Array(Box::new(IdlType::U8), 2))
+            )
+        );
+        assert_eq!(
+            IdlType::from_str("HashSet<u8>").unwrap(),
+            IdlType::HashSet(Box::new(IdlType::U8))
+        );
+    }
+
+    #[test]
+    fn generics() {
+        assert_eq!(
+            IdlType::from_str("Wrapper<u64, Option<T>, 1_000>").unwrap(),
+            IdlType::DefinedWithTypeArgs {
+                name: "Wrapper".into(),
+                args: vec![
+                    IdlDefinedTypeArg::Type(IdlType::U64),
+                    IdlDefinedTypeArg::Type(IdlType::Option(Box::new(IdlType::Defined(
+                        "T".into()
+                    )))),
+                    IdlDefinedTypeArg::Value("1000".into()),
+                ]
+            }
+        );
+        assert_eq!(
+            IdlType::from_str("[u8; N]").unwrap(),
+            IdlType::GenericLenArray(Box::new(IdlType::U8), "N".into())
+        );
+        assert_eq!(
+            IdlType::from_str("[u8; MAX_SIZE]").unwrap(),
+            IdlType::GenericLenArray(Box::new(IdlType::U8), "MAX_SIZE".into())
+        );
+    }
 }
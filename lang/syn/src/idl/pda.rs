@@ -1,3 +1,4 @@
+use crate::idl::file::{find_const, resolve_pubkey};
 use crate::idl::*;
 use crate::parser;
 use crate::parser::context::CrateContext;
@@ -5,46 +6,38 @@ use crate::ConstraintSeedsGroup;
 use crate::{AccountsStruct, Field};
 use std::collections::HashMap;
 use std::str::FromStr;
-use syn::Expr;
+use syn::{Expr, Lit};
 
 // Parses a seeds constraint, extracting the IdlSeed types.
 //
 // Note: This implementation makes assumptions about the types that can be used
 //       (e.g., no program-defined function calls in seeds).
 //
-//       This probably doesn't cover all cases. If you see a warning log, you
-//       can add a new case here. In the worst case, we miss a seed and
-//       the parser will treat the given seeds as empty and so clients will
-//       simply fail to automatically populate the PDA accounts.
+//       This probably doesn't cover all cases. If a seed can't be parsed, the
+//       PDA is omitted from the IDL and so clients will simply fail to
+//       automatically populate the PDA account.
 //
-// Seed Assumptions: Seeds must be of one of the following forms:
+// Seed Assumptions: Seeds must be conversions to bytes (e.g. `as_ref()`,
+// `as_bytes()`, `to_le_bytes()`, `to_be_bytes()` or `&[..]`) of one of the
+// following forms:
 //
-// - instruction argument.
+// - instruction argument, or a field of one.
 // - account context field pubkey.
 // - account data, where the account is defined in the current program.
 //   We make an exception for the SPL token program, since it is so common
 //   and sometimes convenient to use fields as a seed (e.g. Auction house
 //   program). In the case of nested structs/account data, all nested structs
 //   must be defined in the current program as well.
-// - byte string literal (e.g. b"MY_SEED").
-// - byte string literal constant  (e.g. `pub const MY_SEED: [u8; 2] = *b"hi";`).
-// - array constants.
+// - string or byte string literal (e.g. b"MY_SEED").
+// - string, byte string, integer or array constant, in any module of the
+//   crate or its dependencies (e.g. `pub const MY_SEED: [u8; 2] = *b"hi";`).
 //
-pub fn parse(
-    ctx: &CrateContext,
-    accounts: &AccountsStruct,
-    acc: &Field,
-    seeds_feature: bool,
-) -> Option<IdlPda> {
-    if !seeds_feature {
-        return None;
-    }
+pub fn parse(ctx: &CrateContext, accounts: &AccountsStruct, acc: &Field) -> Option<IdlPda> {
     let pda_parser = PdaParser::new(ctx, accounts);
     acc.constraints
         .seeds
         .as_ref()
-        .map(|s| pda_parser.parse(s))
-        .unwrap_or(None)
+        .and_then(|s| pda_parser.parse(s))
 }
 
 struct PdaParser<'a> {
@@ -54,8 +47,6 @@ struct PdaParser<'a> {
     // Maps var name to var type. These are the instruction arguments in a
     // given accounts context.
     ix_args: HashMap<String, String>,
-    // Constants available in the crate.
-    const_names: Vec<String>,
     // All field names of the accounts in the accounts context.
     account_field_names: Vec<String>,
 }
@@ -64,14 +55,12 @@ impl<'a> PdaParser<'a> {
     fn new(ctx: &'a CrateContext, accounts: &'a AccountsStruct) -> Self {
         // All the available sources of seeds.
         let ix_args = accounts.instruction_args().unwrap_or_default();
-        let const_names: Vec<String> = ctx.consts().map(|c| c.ident.to_string()).collect();
         let account_field_names = accounts.field_names();
 
         Self {
             ctx,
             accounts,
             ix_args,
-            const_names,
             account_field_names,
         }
     }
@@ -85,238 +74,538 @@ impl<'a> PdaParser<'a> {
             .collect::<Option<Vec<_>>>()?;
 
         // Parse the program id from the constraints.
-        let program_id = seeds_grp
-            .program_seed
-            .as_ref()
-            .map(|pid| self.parse_seed(pid))
-            .unwrap_or_default();
+        let program_id = match &seeds_grp.program_seed {
+            Some(pid) => Some(self.parse_seed(pid)?),
+            None => None,
+        };
 
         // Done.
         Some(IdlPda { seeds, program_id })
     }
 
     fn parse_seed(&self, seed: &Expr) -> Option<IdlSeed> {
-        match seed {
-            Expr::MethodCall(_) => {
-                let seed_path = parse_seed_path(seed)?;
-
-                if self.is_instruction(&seed_path) {
-                    self.parse_instruction(&seed_path)
-                } else if self.is_const(&seed_path) {
-                    self.parse_const(&seed_path)
-                } else if self.is_account(&seed_path) {
-                    self.parse_account(&seed_path)
-                } else if self.is_str_literal(&seed_path) {
-                    self.parse_str_literal(&seed_path)
+        let (value, encoding) = parse_seed_value(seed)?;
+        let mut seed = match value {
+            SeedValue::Lit(lit) => parse_lit(lit),
+            SeedValue::Const(path) => self.parse_const(path),
+            SeedValue::Var(name, path) => {
+                if self.ix_args.contains_key(&name) {
+                    self.parse_instruction(&name, &path)
+                } else if self.account_field_names.contains(&name) {
+                    self.parse_account(&name, path)
+                } else if path.is_empty() {
+                    let ident = syn::Ident::new(&name, proc_macro2::Span::call_site());
+                    self.parse_const(&ident.into())
                 } else {
-                    println!("WARNING: unexpected seed category for var: {:?}", seed_path);
                     None
                 }
             }
-            Expr::Reference(expr_reference) => self.parse_seed(&expr_reference.expr),
-            Expr::Index(_) => {
-                println!("WARNING: auto pda derivation not currently supported for slice literals");
-                None
-            }
-            // Unknown type. Please file an issue.
-            _ => {
-                println!("WARNING: unexpected seed: {:?}", seed);
-                None
-            }
-        }
+        }?;
+        set_encoding(&mut seed, encoding);
+        Some(seed)
     }
 
-    fn parse_instruction(&self, seed_path: &SeedPath) -> Option<IdlSeed> {
-        let idl_ty = IdlType::from_str(self.ix_args.get(&seed_path.name()).unwrap()).ok()?;
+    fn parse_instruction(&self, name: &str, path: &[String]) -> Option<IdlSeed> {
+        let ty_str = self.ix_args.get(name)?;
+        let ty = match path.is_empty() {
+            true => IdlType::from_str(ty_str).ok()?,
+            false => {
+                // Get the rust representation of the argument's struct.
+                let strct = self.ctx.structs().find(|s| s.ident == ty_str)?;
+                parse_field_path(self.ctx, strct, path)?
+            }
+        };
         Some(IdlSeed::Arg(IdlSeedArg {
-            ty: idl_ty,
-            path: seed_path.path(),
+            ty,
+            path: seed_path(name, path),
+            encoding: None,
         }))
     }
 
-    fn parse_const(&self, seed_path: &SeedPath) -> Option<IdlSeed> {
+    fn parse_const(&self, path: &syn::Path) -> Option<IdlSeed> {
+        // Program ids, e.g. `crate::ID`, and `pubkey!` constants.
+        if let Some(pubkey) = resolve_pubkey(self.ctx, path) {
+            return Some(IdlSeed::Const(IdlSeedConst {
+                ty: IdlType::PublicKey,
+                value: pubkey.into(),
+                encoding: None,
+            }));
+        }
+
         // Pull in the constant value directly into the IDL.
-        assert!(seed_path.components().is_empty());
-        let const_item = self
-            .ctx
-            .consts()
-            .find(|c| c.ident == seed_path.name())
-            .unwrap();
-        let idl_ty = IdlType::from_str(&parser::tts_to_string(&const_item.ty)).ok()?;
-        let mut idl_ty_value = parser::tts_to_string(&const_item.expr);
-
-        if let IdlType::Array(_ty, _size) = &idl_ty {
-            // Convert str literal to array.
-            if idl_ty_value.contains("b\"") {
-                let components: Vec<&str> = idl_ty_value.split('b').collect();
-                assert!(components.len() == 2);
-                let mut str_lit = components[1].to_string();
-                str_lit.retain(|c| c != '"');
-                idl_ty_value = format!("{:?}", str_lit.as_bytes());
-            }
+        let name = path.segments.last()?.ident.to_string();
+        let const_item = find_const(self.ctx, &name).ok()??;
+        let declared_ty = IdlType::from_str(&parser::tts_to_string(&const_item.ty));
+
+        // Array literals are taken as is.
+        if let Expr::Array(_) = &*const_item.expr {
+            return Some(IdlSeed::Const(IdlSeedConst {
+                ty: declared_ty.ok()?,
+                value: serde_json::from_str(&parser::tts_to_string(&const_item.expr)).ok()?,
+                encoding: None,
+            }));
         }
 
-        Some(IdlSeed::Const(IdlSeedConst {
-            ty: idl_ty,
-            value: serde_json::from_str(&idl_ty_value).unwrap(),
-        }))
+        match parse_seed_value(&const_item.expr)?.0 {
+            // Byte strings are arrays of their declared length, or else
+            // slices, e.g. `&[u8]`.
+            SeedValue::Lit(Lit::ByteStr(lit)) => Some(IdlSeed::Const(IdlSeedConst {
+                ty: match declared_ty {
+                    Ok(ty @ IdlType::Array(_, _)) => ty,
+                    _ => IdlType::Bytes,
+                },
+                value: lit.value().into(),
+                encoding: None,
+            })),
+            SeedValue::Lit(Lit::Int(lit)) => Some(IdlSeed::Const(IdlSeedConst {
+                ty: declared_ty.ok()?,
+                value: serde_json::from_str(lit.base10_digits()).ok()?,
+                encoding: None,
+            })),
+            SeedValue::Lit(lit) => parse_lit(lit),
+            _ => None,
+        }
     }
 
-    fn parse_account(&self, seed_path: &SeedPath) -> Option<IdlSeed> {
+    fn parse_account(&self, name: &str, mut path: Vec<String>) -> Option<IdlSeed> {
         // Get the anchor account field from the derive accounts struct.
         let account_field = self
             .accounts
             .fields
             .iter()
-            .find(|field| *field.ident() == seed_path.name())
-            .unwrap();
+            .find(|field| *field.ident() == name)?;
+
+        // The `key` field of an `AccountInfo` is its pubkey.
+        if path == ["key"] {
+            path.clear();
+        }
 
         // Follow the path to find the seed type.
-        let ty = {
-            let mut path = seed_path.components();
-            match path.len() {
-                0 => IdlType::PublicKey,
-                1 => {
-                    // Name of the account struct.
-                    let account = account_field.ty_name()?;
-                    if account == "TokenAccount" {
-                        assert!(path.len() == 1);
-                        match path[0].as_str() {
-                            "mint" => IdlType::PublicKey,
-                            "amount" => IdlType::U64,
-                            "authority" => IdlType::PublicKey,
-                            "delegated_amount" => IdlType::U64,
-                            _ => {
-                                println!("WARNING: token field isn't supported: {}", &path[0]);
-                                return None;
-                            }
-                        }
-                    } else {
-                        // Get the rust representation of the field's struct.
-                        let strct = self.ctx.structs().find(|s| s.ident == account).unwrap();
-                        parse_field_path(self.ctx, strct, &mut path)
+        let ty = match path.is_empty() {
+            true => IdlType::PublicKey,
+            false => {
+                // Name of the account struct.
+                let account = account_field.ty_name()?;
+                let account = account.rsplit("::").next()?.trim();
+                if account == "TokenAccount" {
+                    match path.as_slice() {
+                        [field] if field == "mint" || field == "authority" => IdlType::PublicKey,
+                        [field] if field == "amount" || field == "delegated_amount" => IdlType::U64,
+                        _ => return None,
                     }
+                } else {
+                    // Get the rust representation of the field's struct.
+                    let strct = self.ctx.structs().find(|s| s.ident == account)?;
+                    parse_field_path(self.ctx, strct, &path)?
                 }
-                _ => panic!("invariant violation"),
             }
         };
 
         Some(IdlSeed::Account(IdlSeedAccount {
             ty,
             account: account_field.ty_name(),
-            path: seed_path.path(),
+            path: seed_path(name, &path),
+            encoding: None,
         }))
     }
-
-    fn parse_str_literal(&self, seed_path: &SeedPath) -> Option<IdlSeed> {
-        let mut var_name = seed_path.name();
-        // Remove the byte `b` prefix if the string is of the form `b"seed".
-        if var_name.starts_with("b\"") {
-            var_name.remove(0);
-        }
-        let value_string: String = var_name.chars().filter(|c| *c != '"').collect();
-        Some(IdlSeed::Const(IdlSeedConst {
-            value: serde_json::Value::String(value_string),
-            ty: IdlType::String,
-        }))
-    }
-
-    fn is_instruction(&self, seed_path: &SeedPath) -> bool {
-        self.ix_args.contains_key(&seed_path.name())
-    }
-
-    fn is_const(&self, seed_path: &SeedPath) -> bool {
-        self.const_names.contains(&seed_path.name())
-    }
-
-    fn is_account(&self, seed_path: &SeedPath) -> bool {
-        self.account_field_names.contains(&seed_path.name())
-    }
-
-    fn is_str_literal(&self, seed_path: &SeedPath) -> bool {
-        seed_path.components().is_empty() && seed_path.name().contains('"')
-    }
 }
 
-// SeedPath represents the deconstructed syntax of a single pda seed,
-// consisting of a variable name and a vec of all the sub fields accessed
-// on that variable name. For example, if a seed is `my_field.my_data.as_ref()`,
-// then the field name is `my_field` and the vec of sub fields is `[my_data]`.
+// The value a seed is made of, stripped of its conversion to bytes.
 #[derive(Debug)]
-struct SeedPath(String, Vec<String>);
+enum SeedValue<'a> {
+    Lit(&'a Lit),
+    // An instruction argument, account or constant, and the fields accessed
+    // on it. For example, if a seed is `my_field.my_data.as_ref()`, then the
+    // name is `my_field` and the fields are `[my_data]`.
+    Var(String, Vec<String>),
+    // A constant given by its path, e.g. `seeds::MY_SEED`.
+    Const(&'a syn::Path),
+}
 
-impl SeedPath {
-    fn name(&self) -> String {
-        self.0.clone()
+// Strips the conversions to bytes off a seed, returning the value it's made of
+// and the byte order given by `to_le_bytes()` or `to_be_bytes()`, if any.
+fn parse_seed_value(seed: &Expr) -> Option<(SeedValue<'_>, Option<IdlSeedEncoding>)> {
+    let mut seed = seed;
+    let mut encoding = None;
+    loop {
+        seed = match seed {
+            Expr::Reference(expr) => &expr.expr,
+            Expr::Paren(expr) => &expr.expr,
+            Expr::Group(expr) => &expr.expr,
+            Expr::Unary(expr) if matches!(expr.op, syn::UnOp::Deref(_)) => &expr.expr,
+            // Full range slices, e.g. `&MY_SEED[..]`.
+            Expr::Index(expr) => match &*expr.index {
+                Expr::Range(range) if range.from.is_none() && range.to.is_none() => &expr.expr,
+                _ => return None,
+            },
+            // A single byte, e.g. `&[bump]`.
+            Expr::Array(expr) if expr.elems.len() == 1 => &expr.elems[0],
+            Expr::MethodCall(call) if call.args.is_empty() => {
+                match call.method.to_string().as_str() {
+                    "to_le_bytes" => encoding = encoding.or(Some(IdlSeedEncoding::Le)),
+                    "to_be_bytes" => encoding = encoding.or(Some(IdlSeedEncoding::Be)),
+                    "as_ref" | "as_bytes" | "as_slice" | "to_bytes" | "to_vec" | "key"
+                    | "to_account_info" | "borrow" | "clone" => {}
+                    _ => return None,
+                }
+                &call.receiver
+            }
+            _ => break,
+        };
     }
 
-    // Full path to the data this seed represents.
-    fn path(&self) -> String {
-        match self.1.len() {
-            0 => self.0.clone(),
-            _ => format!("{}.{}", self.name(), self.components().join(".")),
+    let value = match seed {
+        Expr::Lit(expr) => SeedValue::Lit(&expr.lit),
+        Expr::Path(expr) if expr.qself.is_none() => match expr.path.get_ident() {
+            Some(ident) => SeedValue::Var(ident.to_string(), vec![]),
+            None => SeedValue::Const(&expr.path),
+        },
+        Expr::Field(_) => {
+            let (name, path) = parse_field_access(seed)?;
+            SeedValue::Var(name, path)
         }
-    }
+        _ => return None,
+    };
+    Some((value, encoding))
+}
 
-    // All path components for the subfields accessed on this seed.
-    fn components(&self) -> &[String] {
-        &self.1
+// Splits a chain of field accesses, e.g. `base.data.key`, into the variable
+// and the fields accessed on it.
+fn parse_field_access(expr: &Expr) -> Option<(String, Vec<String>)> {
+    match expr {
+        Expr::Path(expr) => Some((expr.path.get_ident()?.to_string(), vec![])),
+        Expr::Field(expr) => {
+            let (name, mut path) = parse_field_access(&expr.base)?;
+            match &expr.member {
+                syn::Member::Named(member) => path.push(member.to_string()),
+                syn::Member::Unnamed(_) => return None,
+            }
+            Some((name, path))
+        }
+        // Accessors on accounts, e.g. `base.to_account_info().key`.
+        Expr::MethodCall(call) if call.args.is_empty() && call.method == "to_account_info" => {
+            parse_field_access(&call.receiver)
+        }
+        _ => None,
     }
 }
 
-// Extracts the seed path from a single seed expression.
-fn parse_seed_path(seed: &Expr) -> Option<SeedPath> {
-    // Convert the seed into the raw string representation.
-    let seed_str = parser::tts_to_string(&seed);
+fn parse_lit(lit: &Lit) -> Option<IdlSeed> {
+    let (ty, value) = match lit {
+        Lit::Str(lit) => (IdlType::String, lit.value().into()),
+        Lit::ByteStr(lit) => match String::from_utf8(lit.value()) {
+            Ok(value) => (IdlType::String, value.into()),
+            Err(e) => (IdlType::Bytes, e.into_bytes().into()),
+        },
+        Lit::Byte(lit) => (IdlType::U8, lit.value().into()),
+        Lit::Int(lit) => {
+            let ty = match lit.suffix() {
+                "" => IdlType::U8,
+                suffix => IdlType::from_str(suffix).ok()?,
+            };
+            (ty, serde_json::from_str(lit.base10_digits()).ok()?)
+        }
+        _ => return None,
+    };
+    Some(IdlSeed::Const(IdlSeedConst {
+        ty,
+        value,
+        encoding: None,
+    }))
+}
 
-    // Break up the seed into each sub field component.
-    let mut components: Vec<&str> = seed_str.split(" . ").collect();
-    if components.len() <= 1 {
-        println!("WARNING: seeds are in an unexpected format: {:?}", seed);
-        return None;
+// Integer seeds are little endian unless converted with `to_be_bytes()`.
+fn set_encoding(seed: &mut IdlSeed, encoding: Option<IdlSeedEncoding>) {
+    let (ty, seed_encoding) = match seed {
+        IdlSeed::Const(seed) => (&seed.ty, &mut seed.encoding),
+        IdlSeed::Arg(seed) => (&seed.ty, &mut seed.encoding),
+        IdlSeed::Account(seed) => (&seed.ty, &mut seed.encoding),
+    };
+    let is_integer = matches!(
+        ty,
+        IdlType::U8
+            | IdlType::I8
+            | IdlType::U16
+            | IdlType::I16
+            | IdlType::U32
+            | IdlType::I32
+            | IdlType::U64
+            | IdlType::I64
+            | IdlType::U128
+            | IdlType::I128
+    );
+    if is_integer {
+        *seed_encoding = Some(encoding.unwrap_or(IdlSeedEncoding::Le));
     }
+}
 
-    // The name of the variable (or field).
-    let name = components.remove(0).to_string();
-
-    // The path to the seed (only if the `name` type is a struct).
-    let mut path = Vec::new();
-    while !components.is_empty() {
-        let c = components.remove(0);
-        if c.contains("()") {
-            break;
-        }
-        path.push(c.to_string());
-    }
-    if path.len() == 1 && (path[0] == "key" || path[0] == "key()") {
-        path = Vec::new();
+// Full path to the data a seed represents.
+fn seed_path(name: &str, path: &[String]) -> String {
+    match path.len() {
+        0 => name.to_string(),
+        _ => format!("{}.{}", name, path.join(".")),
     }
-
-    Some(SeedPath(name, path))
 }
 
-fn parse_field_path(ctx: &CrateContext, strct: &syn::ItemStruct, path: &mut &[String]) -> IdlType {
-    let field_name = &path[0];
-    *path = &path[1..];
+fn parse_field_path(
+    ctx: &CrateContext,
+    strct: &syn::ItemStruct,
+    path: &[String],
+) -> Option<IdlType> {
+    let (field_name, path) = path.split_first()?;
 
     // Get the type name for the field.
     let next_field = strct
         .fields
         .iter()
-        .find(|f| &f.ident.clone().unwrap().to_string() == field_name)
-        .unwrap();
+        .find(|f| f.ident.as_ref().map(|ident| ident == field_name) == Some(true))?;
     let next_field_ty_str = parser::tts_to_string(&next_field.ty);
 
     // The path is empty so this must be a primitive type.
     if path.is_empty() {
-        return next_field_ty_str.parse().unwrap();
+        return next_field_ty_str.parse().ok();
     }
 
-    // Get the rust representation of hte field's struct.
-    let strct = ctx
-        .structs()
-        .find(|s| s.ident == next_field_ty_str)
-        .unwrap();
+    // Get the rust representation of the field's struct.
+    let strct = ctx.structs().find(|s| s.ident == next_field_ty_str)?;
 
     parse_field_path(ctx, strct, path)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn seed_value(seed: &str) -> Option<(String, Option<IdlSeedEncoding>)> {
+        let seed: Expr = syn::parse_str(seed).unwrap();
+        parse_seed_value(&seed).map(|(value, encoding)| {
+            let value = match value {
+                SeedValue::Lit(lit) => parser::tts_to_string(lit),
+                SeedValue::Var(name, path) => seed_path(&name, &path),
+                SeedValue::Const(path) => format!("const {}", parser::tts_to_string(path)),
+            };
+            (value, encoding)
+        })
+    }
+
+    #[test]
+    fn strips_conversions_to_bytes() {
+        assert_eq!(seed_value("b\"vault\""), Some(("b\"vault\"".into(), None)));
+        assert_eq!(
+            seed_value("authority.key().as_ref()"),
+            Some(("authority".into(), None))
+        );
+        assert_eq!(
+            seed_value("base.to_account_info().key.as_ref()"),
+            Some(("base.key".into(), None))
+        );
+        assert_eq!(
+            seed_value("state.config.authority.as_ref()"),
+            Some(("state.config.authority".into(), None))
+        );
+        assert_eq!(seed_value("&[bump]"), Some(("bump".into(), None)));
+        assert_eq!(
+            seed_value("amount.to_be_bytes().as_ref()"),
+            Some(("amount".into(), Some(IdlSeedEncoding::Be)))
+        );
+        assert_eq!(
+            seed_value("&crate::seeds::VAULT[..]"),
+            Some(("const crate :: seeds :: VAULT".into(), None))
+        );
+        assert_eq!(seed_value("authority.key().to_string().as_bytes()"), None);
+        assert_eq!(seed_value("&[bump, 1]"), None);
+    }
+
+    // Runs a test against the parser of an accounts struct, in a crate written
+    // to a temporary file named after the test.
+    fn with_parser(name: &str, test: impl FnOnce(&PdaParser, &AccountsStruct)) {
+        let file = format!("anchor-idl-pda-{}-{}.rs", name, std::process::id());
+        let root = std::env::temp_dir().join(file);
+        std::fs::write(
+            &root,
+            r#"
+            declare_id!("Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS");
+            pub const ADMIN: Pubkey = pubkey!("9xQeWvG816bUx9EPjHmaT23yvVM2ZWbrrpZb9PusVFin");
+
+            pub mod seeds {
+                pub const VAULT: &[u8] = b"vault";
+                pub const TAG: [u8; 2] = [1, 2];
+                pub const INDEX: u16 = 7;
+            }
+
+            #[account]
+            pub struct Config {
+                pub settings: Settings,
+            }
+
+            #[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+            pub struct Settings {
+                pub authority: Pubkey,
+                pub nonce: u64,
+            }
+
+            #[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+            pub struct Params {
+                pub id: u64,
+                pub owner: Pubkey,
+            }
+            "#,
+        )
+        .unwrap();
+        let ctx = CrateContext::parse(&root).unwrap();
+        std::fs::remove_file(&root).unwrap();
+
+        let strct: syn::ItemStruct = syn::parse_quote! {
+            #[derive(Accounts)]
+            #[instruction(params: Params, name: String)]
+            pub struct Initialize<'info> {
+                #[account(
+                    seeds = [seeds::VAULT, config.settings.authority.as_ref(), params.id.to_le_bytes().as_ref()],
+                    bump,
+                )]
+                pub vault: AccountInfo<'info>,
+                pub config: Account<'info, Config>,
+                pub token: Account<'info, TokenAccount>,
+            }
+        };
+        let accounts = parser::accounts::parse(&strct).unwrap();
+        test(&PdaParser::new(&ctx, &accounts), &accounts);
+    }
+
+    fn constant(ty: IdlType, value: serde_json::Value) -> Option<IdlSeed> {
+        Some(IdlSeed::Const(IdlSeedConst {
+            ty,
+            value,
+            encoding: None,
+        }))
+    }
+
+    #[test]
+    fn parses_constant_seeds() {
+        with_parser("constants", |parser, _| {
+            let parse = |path: syn::Path| parser.parse_const(&path);
+            assert_eq!(
+                parse(syn::parse_quote!(seeds::VAULT)),
+                constant(IdlType::Bytes, b"vault".to_vec().into())
+            );
+            assert_eq!(
+                parse(syn::parse_quote!(crate::seeds::TAG)),
+                constant(IdlType::Array(Box::new(IdlType::U8), 2), vec![1, 2].into())
+            );
+            assert_eq!(
+                parse(syn::parse_quote!(seeds::INDEX)),
+                constant(IdlType::U16, 7.into())
+            );
+            assert_eq!(
+                parse(syn::parse_quote!(ADMIN)),
+                constant(
+                    IdlType::PublicKey,
+                    "9xQeWvG816bUx9EPjHmaT23yvVM2ZWbrrpZb9PusVFin".into()
+                )
+            );
+            assert_eq!(
+                parse(syn::parse_quote!(crate::ID)),
+                constant(
+                    IdlType::PublicKey,
+                    "Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS".into()
+                )
+            );
+            assert_eq!(parse(syn::parse_quote!(seeds::MISSING)), None);
+        });
+    }
+
+    #[test]
+    fn parses_account_seeds() {
+        with_parser("accounts", |parser, _| {
+            let parse = |name: &str, path: &[&str]| {
+                let path = path.iter().map(|field| field.to_string()).collect();
+                match parser.parse_account(name, path)? {
+                    IdlSeed::Account(seed) => Some((seed.ty, seed.account, seed.path)),
+                    _ => None,
+                }
+            };
+            let config = Some("Config".to_string());
+            assert_eq!(
+                parse("config", &["key"]),
+                Some((IdlType::PublicKey, config.clone(), "config".into()))
+            );
+            assert_eq!(
+                parse("config", &["settings", "authority"]),
+                Some((
+                    IdlType::PublicKey,
+                    config.clone(),
+                    "config.settings.authority".into()
+                ))
+            );
+            assert_eq!(
+                parse("config", &["settings", "nonce"]),
+                Some((IdlType::U64, config, "config.settings.nonce".into()))
+            );
+            assert_eq!(
+                parse("token", &["amount"]),
+                Some((
+                    IdlType::U64,
+                    Some("TokenAccount".into()),
+                    "token.amount".into()
+                ))
+            );
+            assert_eq!(parse("config", &["settings", "missing"]), None);
+            assert_eq!(parse("token", &["close_authority"]), None);
+            assert_eq!(parse("missing", &[]), None);
+        });
+    }
+
+    #[test]
+    fn parses_instruction_seeds() {
+        with_parser("instructions", |parser, _| {
+            let parse = |name: &str, path: &[&str]| {
+                let path: Vec<String> = path.iter().map(|field| field.to_string()).collect();
+                match parser.parse_instruction(name, &path)? {
+                    IdlSeed::Arg(seed) => Some((seed.ty, seed.path)),
+                    _ => None,
+                }
+            };
+            assert_eq!(parse("name", &[]), Some((IdlType::String, "name".into())));
+            assert_eq!(
+                parse("params", &["id"]),
+                Some((IdlType::U64, "params.id".into()))
+            );
+            assert_eq!(
+                parse("params", &["owner"]),
+                Some((IdlType::PublicKey, "params.owner".into()))
+            );
+            assert_eq!(parse("params", &["missing"]), None);
+            assert_eq!(parse("missing", &[]), None);
+        });
+    }
+
+    #[test]
+    fn parses_seeds_constraints() {
+        with_parser("seeds", |parser, accounts| {
+            let vault = match &accounts.fields[0] {
+                crate::AccountField::Field(field) => field,
+                _ => unreachable!(),
+            };
+            let seeds = vault.constraints.seeds.as_ref().unwrap();
+            let pda = parser.parse(seeds).unwrap();
+            assert_eq!(
+                pda.seeds,
+                vec![
+                    constant(IdlType::Bytes, b"vault".to_vec().into()).unwrap(),
+                    IdlSeed::Account(IdlSeedAccount {
+                        ty: IdlType::PublicKey,
+                        account: Some("Config".into()),
+                        path: "config.settings.authority".into(),
+                        encoding: None,
+                    }),
+                    IdlSeed::Arg(IdlSeedArg {
+                        ty: IdlType::U64,
+                        path: "params.id".into(),
+                        encoding: Some(IdlSeedEncoding::Le),
+                    }),
+                ]
+            );
+            assert_eq!(pda.program_id, None);
+        });
+    }
+}
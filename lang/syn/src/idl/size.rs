@@ -0,0 +1,328 @@
+//! Sizes of the types and accounts of an IDL, for those stored in a fixed
+//! number of bytes.
+//!
+//! Types are measured as they're stored: `#[zero_copy]` types by their size in
+//! memory, including padding, and others by their Borsh encoding, whatever
+//! their `#[repr(..)]`. Types of variable
+//! length, such as `Vec`s, `String`s and `Option`s, and generic types have no
+//! size.
+
+use crate::idl::*;
+
+// Alignment of `u128` and `i128` on the BPF target, unlike most 64 bit hosts.
+const INT128_ALIGN: u64 = 8;
+
+// Nesting of user defined types beyond which a type is deemed recursive.
+const MAX_DEPTH: usize = 64;
+
+// Sets the `size` of the accounts and types of the IDL. The size of an
+// account is the length of its data, i.e. it includes the discriminator and
+// the layout version.
+pub fn set_sizes(idl: &mut Idl) {
+    let sizes = Sizes { idl };
+    let account_sizes: Vec<Option<u64>> = idl
+        .accounts
+        .iter()
+        .map(|account| {
+            let header = account.discriminator.as_ref()?.len() as u64
+                + account.version.map(|_| 1).unwrap_or(0);
+            Some(header + sizes.ty_def_size(account)?)
+        })
+        .collect();
+    let type_sizes: Vec<Option<u64>> = idl
+        .types
+        .iter()
+        .map(|ty_def| sizes.ty_def_size(ty_def))
+        .collect();
+
+    for (account, size) in idl.accounts.iter_mut().zip(account_sizes) {
+        account.size = size;
+    }
+    for (ty_def, size) in idl.types.iter_mut().zip(type_sizes) {
+        ty_def.size = size;
+    }
+}
+
+// Size and alignment of a type in memory.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Layout {
+    size: u64,
+    align: u64,
+}
+
+struct Sizes<'a> {
+    idl: &'a Idl,
+}
+
+impl<'a> Sizes<'a> {
+    fn ty_def_size(&self, ty_def: &IdlTypeDefinition) -> Option<u64> {
+        if ty_def.zero_copy {
+            self.ty_def_layout(ty_def, 0).map(|layout| layout.size)
+        } else {
+            self.ty_def_encoded_size(ty_def, 0)
+        }
+    }
+
+    fn find(&self, name: &str, depth: usize) -> Option<&'a IdlTypeDefinition> {
+        // Recursive types, e.g. by `Box<Self>`, have no fixed size.
+        if depth > MAX_DEPTH {
+            return None;
+        }
+        self.idl
+            .types
+            .iter()
+            .chain(&self.idl.accounts)
+            .find(|ty_def| ty_def.name == name)
+    }
+
+    // Length of the Borsh encoding of a type definition.
+    fn ty_def_encoded_size(&self, ty_def: &IdlTypeDefinition, depth: usize) -> Option<u64> {
+        if !ty_def.generics.is_empty() {
+            return None;
+        }
+        match &ty_def.ty {
+            IdlTypeDefinitionTy::Struct { fields } => {
+                fields.iter().map(|f| self.encoded_size(&f.ty, depth)).sum()
+            }
+            IdlTypeDefinitionTy::Tuple { fields } => {
+                fields.iter().map(|ty| self.encoded_size(ty, depth)).sum()
+            }
+            // Only enums whose variants all have the same size are fixed.
+            IdlTypeDefinitionTy::Enum { variants } => {
+                let mut sizes = variants.iter().map(|v| match &v.fields {
+                    None => Some(0),
+                    Some(EnumFields::Named(fields)) => {
+                        fields.iter().map(|f| self.encoded_size(&f.ty, depth)).sum()
+                    }
+                    Some(EnumFields::Tuple(fields)) => {
+                        fields.iter().map(|ty| self.encoded_size(ty, depth)).sum()
+                    }
+                });
+                let first = sizes.next().unwrap_or(Some(0))?;
+                match sizes.all(|size| size == Some(first)) {
+                    true => Some(1 + first),
+                    false => None,
+                }
+            }
+        }
+    }
+
+    fn encoded_size(&self, ty: &IdlType, depth: usize) -> Option<u64> {
+        let size = match ty {
+            IdlType::Defined(name) => {
+                return self.ty_def_encoded_size(self.find(name, depth)?, depth + 1)
+            }
+            IdlType::Array(ty, len) => return Some(self.encoded_size(ty, depth)? * *len as u64),
+            ty => primitive_layout(ty)?.size,
+        };
+        Some(size)
+    }
+
+    // Layout of a type definition in memory, if it's defined by `#[repr(..)]`.
+    fn ty_def_layout(&self, ty_def: &IdlTypeDefinition, depth: usize) -> Option<Layout> {
+        let repr = ty_def.repr.as_ref()?;
+        if !ty_def.generics.is_empty() {
+            return None;
+        }
+        let fields: Vec<&IdlType> = match &ty_def.ty {
+            IdlTypeDefinitionTy::Struct { .. } | IdlTypeDefinitionTy::Tuple { .. }
+                if repr.kind == IdlReprKind::Rust =>
+            {
+                return None
+            }
+            IdlTypeDefinitionTy::Struct { fields } => fields.iter().map(|f| &f.ty).collect(),
+            IdlTypeDefinitionTy::Tuple { fields } => fields.iter().collect(),
+            // Only fieldless enums, stored as their discriminant. `C` enums
+            // are stored as a C `int`.
+            IdlTypeDefinitionTy::Enum { variants } => {
+                if variants.iter().any(|v| v.fields.is_some()) {
+                    return None;
+                }
+                return match (&repr.int, repr.kind) {
+                    (Some(int), _) => primitive_layout(int),
+                    (None, IdlReprKind::C) => Some(Layout { size: 4, align: 4 }),
+                    (None, _) => None,
+                };
+            }
+        };
+
+        let mut size = 0;
+        let mut align = 1;
+        for ty in fields {
+            let mut field = self.layout(ty, depth)?;
+            if let Some(packed) = repr.packed {
+                field.align = field.align.min(packed as u64);
+            }
+            size = align_up(size, field.align) + field.size;
+            align = align.max(field.align);
+        }
+        if let Some(min_align) = repr.align {
+            align = align.max(min_align as u64);
+        }
+        Some(Layout {
+            size: align_up(size, align),
+            align,
+        })
+    }
+
+    fn layout(&self, ty: &IdlType, depth: usize) -> Option<Layout> {
+        match ty {
+            IdlType::Defined(name) => self.ty_def_layout(self.find(name, depth)?, depth + 1),
+            IdlType::Array(ty, len) => {
+                let elem = self.layout(ty, depth)?;
+                Some(Layout {
+                    size: elem.size * *len as u64,
+                    align: elem.align,
+                })
+            }
+            ty => primitive_layout(ty),
+        }
+    }
+}
+
+fn primitive_layout(ty: &IdlType) -> Option<Layout> {
+    let (size, align) = match ty {
+        IdlType::Bool | IdlType::U8 | IdlType::I8 => (1, 1),
+        IdlType::U16 | IdlType::I16 => (2, 2),
+        IdlType::U32 | IdlType::I32 | IdlType::F32 => (4, 4),
+        IdlType::U64 | IdlType::I64 | IdlType::F64 => (8, 8),
+        IdlType::U128 | IdlType::I128 => (16, INT128_ALIGN),
+        IdlType::PublicKey => (32, 1),
+        _ => return None,
+    };
+    Some(Layout { size, align })
+}
+
+fn align_up(offset: u64, align: u64) -> u64 {
+    match offset % align {
+        0 => offset,
+        rem => offset + align - rem,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sizes(json: serde_json::Value) -> Vec<(String, Option<u64>)> {
+        let mut idl: Idl = serde_json::from_value(json).unwrap();
+        set_sizes(&mut idl);
+        idl.accounts
+            .iter()
+            .chain(&idl.types)
+            .map(|ty_def| (ty_def.name.clone(), ty_def.size))
+            .collect()
+    }
+
+    #[test]
+    fn measures_stored_types() {
+        let idl = serde_json::json!({
+            "version": "0.1.0",
+            "name": "example",
+            "instructions": [],
+            "accounts": [
+                {
+                    "name": "Vault",
+                    "type": {
+                        "kind": "struct",
+                        "fields": [
+                            { "name": "authority", "type": "publicKey" },
+                            { "name": "side", "type": { "defined": "Side" } },
+                            { "name": "amount", "type": "u64" }
+                        ]
+                    },
+                    "version": 2,
+                    "discriminator": [1, 2, 3, 4, 5, 6, 7, 8]
+                },
+                {
+                    "name": "Market",
+                    "type": {
+                        "kind": "struct",
+                        "fields": [
+                            { "name": "bump", "type": "u8" },
+                            { "name": "orders", "type": { "array": [{ "defined": "Order" }, 2] } },
+                            { "name": "flag", "type": "bool" }
+                        ]
+                    },
+                    "discriminator": [1, 2, 3, 4, 5, 6, 7, 8],
+                    "repr": { "kind": "c" },
+                    "zeroCopy": true
+                },
+                {
+                    "name": "Config",
+                    "type": {
+                        "kind": "struct",
+                        "fields": [
+                            { "name": "bump", "type": "u8" },
+                            { "name": "order", "type": { "defined": "Order" } }
+                        ]
+                    },
+                    "discriminator": [1, 2, 3, 4, 5, 6, 7, 8],
+                    "repr": { "kind": "c" }
+                },
+                {
+                    "name": "Registry",
+                    "type": {
+                        "kind": "struct",
+                        "fields": [{ "name": "names", "type": { "vec": "string" } }]
+                    },
+                    "discriminator": [1, 2, 3, 4, 5, 6, 7, 8]
+                }
+            ],
+            "types": [
+                {
+                    "name": "Side",
+                    "type": { "kind": "enum", "variants": [{ "name": "Bid" }, { "name": "Ask" }] }
+                },
+                {
+                    "name": "Order",
+                    "type": {
+                        "kind": "struct",
+                        "fields": [
+                            { "name": "flag", "type": "u8" },
+                            { "name": "price", "type": "u128" }
+                        ]
+                    },
+                    "repr": { "kind": "c" },
+                    "zeroCopy": true
+                },
+                {
+                    "name": "Packed",
+                    "type": {
+                        "kind": "struct",
+                        "fields": [
+                            { "name": "flag", "type": "u8" },
+                            { "name": "amount", "type": "u64" }
+                        ]
+                    },
+                    "repr": { "kind": "c", "packed": 1, "align": 4 },
+                    "zeroCopy": true
+                },
+                {
+                    "name": "Maybe",
+                    "type": {
+                        "kind": "enum",
+                        "variants": [
+                            { "name": "None" },
+                            { "name": "Some", "fields": ["u64"] }
+                        ]
+                    }
+                }
+            ]
+        });
+        assert_eq!(
+            sizes(idl),
+            vec![
+                ("Vault".into(), Some(8 + 1 + 32 + 1 + 8)),
+                ("Market".into(), Some(8 + 8 + 2 * 24 + 8)),
+                // Borsh accounts aren't padded, whatever their `repr`.
+                ("Config".into(), Some(8 + 1 + 17)),
+                ("Registry".into(), None),
+                ("Side".into(), Some(1)),
+                ("Order".into(), Some(24)),
+                ("Packed".into(), Some(12)),
+                ("Maybe".into(), None),
+            ]
+        );
+    }
+}
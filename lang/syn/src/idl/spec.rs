@@ -0,0 +1,299 @@
+//! Versioning of the IDL format itself, as opposed to the program's version.
+//!
+//! Every IDL records the `spec` it was generated for. The spec is described
+//! by a JSON Schema derived from the types of the `idl` module, which IDL
+//! files are validated against, and IDLs of older specs, or from before the
+//! spec was recorded, can be upgraded to the current one.
+
+use crate::codegen::program::common::sighash;
+use crate::idl::{size, Idl};
+use anyhow::{anyhow, bail, Result};
+use schemars::gen::SchemaSettings;
+use serde_json::{json, Value as JsonValue};
+
+// Version of the IDL format. Bumped on every change of the format, with an
+// upgrade from the previous version added to `upgrade`.
+pub const IDL_SPEC: &str = "0.3.0";
+
+// The JSON Schema of the current IDL spec, derived from the `idl` types.
+pub fn schema() -> JsonValue {
+    let settings = SchemaSettings::draft07().with(|settings| {
+        // Optional fields are left out rather than given as `null`.
+        settings.option_add_null_type = false;
+    });
+    let schema = settings.into_generator().into_root_schema_for::<Idl>();
+    let mut schema = serde_json::to_value(schema).expect("Schemas serialize to JSON");
+    schema["title"] = json!("Anchor IDL");
+    schema["description"] = json!(format!(
+        "Interface of an Anchor program, spec {}.",
+        IDL_SPEC
+    ));
+    // Older specs are upgraded rather than validated, so the spec is always
+    // the current one.
+    schema["properties"]["spec"] = json!({ "const": IDL_SPEC });
+    schema["required"]
+        .as_array_mut()
+        .expect("Idl has required fields")
+        .insert(0, json!("spec"));
+    schema
+}
+
+// Upgrades an IDL of an older spec, or from before the spec was recorded, to
+// the current spec. The result isn't validated, so that IDLs that are still
+// readable keep working.
+pub fn upgrade(mut idl: JsonValue) -> Result<JsonValue> {
+    loop {
+        let spec = match idl.get("spec") {
+            None => String::new(),
+            Some(JsonValue::String(spec)) => spec.clone(),
+            Some(spec) => bail!("Invalid IDL spec: {}", spec),
+        };
+        match spec.as_str() {
+            IDL_SPEC => break,
+            "" => upgrade_unversioned(&mut idl)?,
+            "0.1.0" => set_spec(&mut idl, "0.2.0"),
+            "0.2.0" => idl = upgrade_discriminators(idl)?,
+            _ => bail!(
+                "Unknown IDL spec {}, the current spec is {}",
+                spec,
+                IDL_SPEC
+            ),
+        }
+    }
+    Ok(idl)
+}
+
+// IDLs from before the spec was recorded. Hand written and third party IDLs
+// of that time may give the optional sections as `null` and leave out the
+// `index` of event fields.
+fn upgrade_unversioned(idl: &mut JsonValue) -> Result<()> {
+    let idl = idl
+        .as_object_mut()
+        .ok_or_else(|| anyhow!("IDL must be a JSON object"))?;
+    idl.retain(|_, section| !section.is_null());
+    if let Some(events) = idl.get_mut("events").and_then(JsonValue::as_array_mut) {
+        let fields = events
+            .iter_mut()
+            .filter_map(|event| event.get_mut("fields"))
+            .filter_map(JsonValue::as_array_mut)
+            .flatten()
+            .filter_map(JsonValue::as_object_mut);
+        for field in fields {
+            field.entry("index").or_insert(JsonValue::Bool(false));
+        }
+    }
+    idl.insert("spec".to_string(), JsonValue::String("0.1.0".to_string()));
+    Ok(())
+}
+
+// For upgrades only adding optional fields, e.g. 0.1.0 to 0.2.0 adding the
+// memory layout of types.
+fn set_spec(idl: &mut JsonValue, spec: &str) {
+    idl["spec"] = JsonValue::String(spec.to_string());
+}
+
+// 0.3.0 always gives the discriminators of accounts and events, and the sizes
+// of accounts and types. Accounts of older IDLs are assumed to be in the
+// default namespace.
+fn upgrade_discriminators(mut idl: JsonValue) -> Result<JsonValue> {
+    for (section, namespace) in [("accounts", "account"), ("events", "event")] {
+        let items = idl
+            .get_mut(section)
+            .and_then(JsonValue::as_array_mut)
+            .into_iter()
+            .flatten()
+            .filter_map(JsonValue::as_object_mut);
+        for item in items {
+            let name = match item.get("name") {
+                Some(JsonValue::String(name)) => name.clone(),
+                _ => bail!("Missing name in {}", section),
+            };
+            item.entry("discriminator")
+                .or_insert_with(|| json!(sighash(namespace, &name)));
+        }
+    }
+    set_spec(&mut idl, "0.3.0");
+
+    let mut idl: Idl = serde_json::from_value(idl)?;
+    size::set_sizes(&mut idl);
+    Ok(serde_json::to_value(idl)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jsonschema::{Draft, JSONSchema};
+
+    // Paths to the parts of an IDL violating the schema.
+    fn violations(idl: &JsonValue) -> Vec<String> {
+        let schema = schema();
+        let schema = JSONSchema::options()
+            .with_draft(Draft::Draft7)
+            .compile(&schema)
+            .unwrap();
+        let mut violations: Vec<String> = match schema.validate(idl) {
+            Ok(()) => vec![],
+            Err(errors) => errors.map(|err| err.instance_path.to_string()).collect(),
+        };
+        violations.sort();
+        violations.dedup();
+        violations
+    }
+
+    // An IDL using every part of the format.
+    fn full_idl() -> JsonValue {
+        let ty = json!({ "definedWithTypeArgs": {
+            "name": "Wrapper",
+            "args": [
+                { "type": { "hashMap": ["string", { "vec": { "array": ["u8", 32] } }] } },
+                { "value": "4" },
+                { "generic": "T" },
+            ],
+        }});
+        json!({
+            "spec": IDL_SPEC,
+            "version": "0.1.0",
+            "name": "example",
+            "constants": [{ "name": "SEED", "type": "bytes", "value": "b\"seed\"" }],
+            "instructions": [{
+                "name": "initialize",
+                "accounts": [
+                    {
+                        "name": "vault",
+                        "isMut": true,
+                        "isSigner": false,
+                        "pda": {
+                            "seeds": [
+                                { "kind": "const", "type": "bytes", "value": [115, 101, 101, 100] },
+                                { "kind": "arg", "type": "u64", "path": "id", "encoding": "le" },
+                                {
+                                    "kind": "account",
+                                    "type": "publicKey",
+                                    "account": "Vault",
+                                    "path": "authority",
+                                },
+                            ],
+                            "programId": { "kind": "arg", "type": "publicKey", "path": "program" },
+                        },
+                        "relations": ["authority"],
+                        "owner": "11111111111111111111111111111111",
+                        "payer": "authority",
+                    },
+                    {
+                        "name": "nested",
+                        "accounts": [{ "name": "authority", "isMut": true, "isSigner": true }],
+                    },
+                ],
+                "args": [{ "name": "data", "type": ty }],
+                "discriminator": [1, 2],
+            }],
+            "state": {
+                "struct": { "name": "State", "type": { "kind": "struct", "fields": [] } },
+                "methods": [],
+            },
+            "accounts": [{
+                "name": "Vault",
+                "type": { "kind": "struct", "fields": [{ "name": "authority", "type": "publicKey" }] },
+                "version": 2,
+                "history": [{ "version": 1, "type": { "kind": "tuple", "fields": ["u8"] } }],
+                "discriminator": [1],
+                "size": 34,
+            }],
+            "types": [{
+                "name": "Wrapper",
+                "generics": [{ "kind": "type", "name": "T" }, { "kind": "const", "name": "N", "type": "usize" }],
+                "repr": { "kind": "c", "int": "u16", "packed": 1, "align": 8 },
+                "zeroCopy": true,
+                "type": { "kind": "enum", "variants": [
+                    { "name": "Unit", "discriminant": -1 },
+                    { "name": "Named", "fields": [{ "name": "inner", "type": { "generic": "T" } }] },
+                    { "name": "Tuple", "fields": [{ "genericLenArray": ["u8", "N"] }, { "option": { "bTreeSet": "i128" } }] },
+                    { "name": "Map", "fields": [{ "bTreeMap": [{ "defined": "Unit" }, { "hashSet": "bool" }] }] },
+                ]},
+            }],
+            "events": [{
+                "name": "Deposited",
+                "fields": [{ "name": "amount", "type": "u64", "index": false }],
+                "discriminator": [9, 9],
+            }],
+            "errors": [{ "code": 6000, "name": "Overflow", "msg": "Overflow" }],
+            "metadata": { "address": "11111111111111111111111111111111" },
+        })
+    }
+
+    #[test]
+    fn schema_covers_the_format() {
+        let value = full_idl();
+        assert!(violations(&value).is_empty(), "{:?}", violations(&value));
+
+        // Every field of the Rust types is described by the schema.
+        let idl: Idl = serde_json::from_value(value.clone()).unwrap();
+        assert_eq!(serde_json::to_value(&idl).unwrap(), value);
+    }
+
+    #[test]
+    fn reports_every_violation() {
+        let mut value = full_idl();
+        value["instructions"][0]["accounts"][0]["isMut"] = json!("yes");
+        value["instructions"][0]["args"][0]["type"] = json!("u256");
+        value["accounts"][0]["version"] = json!(-1);
+        value["events"][0]["fields"][0]
+            .as_object_mut()
+            .unwrap()
+            .remove("index");
+        value["errors"][0]["hint"] = json!("");
+
+        assert_eq!(
+            violations(&value),
+            vec![
+                "/accounts/0/version",
+                "/errors/0",
+                "/events/0/fields/0",
+                "/instructions/0/accounts/0",
+                "/instructions/0/args/0/type",
+            ]
+        );
+    }
+
+    #[test]
+    fn upgrades_unversioned_idls() {
+        let idl = upgrade(json!({
+            "version": "0.1.0",
+            "name": "example",
+            "instructions": [],
+            "state": null,
+            "accounts": [{
+                "name": "Vault",
+                "type": { "kind": "struct", "fields": [{ "name": "amount", "type": "u64" }] },
+            }],
+            "events": [{ "name": "Deposited", "fields": [{ "name": "amount", "type": "u64" }] }],
+            "errors": null,
+        }))
+        .unwrap();
+        assert!(violations(&idl).is_empty());
+        let idl: Idl = serde_json::from_value(idl).unwrap();
+        assert_eq!(idl.spec, IDL_SPEC);
+        assert_eq!(
+            idl.accounts[0].discriminator,
+            Some(sighash("account", "Vault").to_vec())
+        );
+        assert_eq!(idl.accounts[0].size, Some(16));
+        let event = &idl.events.unwrap()[0];
+        assert_eq!(event.discriminator, sighash("event", "Deposited").to_vec());
+        assert!(!event.fields[0].index);
+        assert_eq!(idl.errors, None);
+
+        assert!(upgrade(json!({ "spec": "9.9.9" })).is_err());
+    }
+
+    #[test]
+    fn schema_is_published() {
+        let path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../../docs/src/idl.schema.json"
+        );
+        let published: JsonValue =
+            serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+        assert_eq!(published, schema(), "Regenerate with `anchor idl schema`");
+    }
+}
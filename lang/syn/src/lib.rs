@@ -87,6 +87,8 @@ pub struct Ix {
     pub args: Vec<IxArg>,
     // The ident for the struct deriving Accounts.
     pub anchor_ident: Ident,
+    // Explicit discriminator given via `#[instruction(discriminator = ..)]`.
+    pub discriminator: Option<DiscriminatorArg>,
 }
 
 #[derive(Debug)]
@@ -563,12 +565,259 @@ pub struct ErrorCode {
     pub msg: Option<String>,
 }
 
+// Arguments to the `#[account(..)]` attribute.
+#[derive(Debug, Default)]
+pub struct AccountArgs {
+    // True if `zero_copy` was given.
+    pub zero_copy: bool,
+    // Namespace for the discriminator preimage, e.g. `#[account("internal")]`.
+    pub namespace: Option<String>,
+    // Layout version stored directly after the discriminator.
+    pub version: Option<AccountVersion>,
+    // Previous layouts of a versioned account, i.e. `migrate(1 = DataV1)`.
+    pub migrations: Vec<AccountMigration>,
+    // Explicit discriminator replacing the namespaced sighash.
+    pub discriminator: Option<DiscriminatorArg>,
+}
+
+#[derive(Debug)]
+pub struct AccountVersion {
+    pub span: Span,
+    pub version: u8,
+}
+
+#[derive(Debug)]
+pub struct AccountMigration {
+    pub version: u8,
+    pub layout: syn::Path,
+}
+
+impl Parse for AccountArgs {
+    fn parse(stream: ParseStream) -> ParseResult<Self> {
+        let mut args = AccountArgs::default();
+        while !stream.is_empty() {
+            if stream.peek(LitStr) {
+                let ns: LitStr = stream.parse()?;
+                args.namespace = Some(ns.value());
+            } else {
+                let kw_span = stream.span();
+                let kw = stream.call(Ident::parse_any)?.to_string();
+                match kw.as_str() {
+                    "zero_copy" => args.zero_copy = true,
+                    "version" => {
+                        stream.parse::<Token![=]>()?;
+                        let lit: LitInt = stream.parse()?;
+                        args.version = Some(AccountVersion {
+                            span: lit.span(),
+                            version: lit.base10_parse()?,
+                        });
+                    }
+                    "discriminator" => {
+                        stream.parse::<Token![=]>()?;
+                        args.discriminator = Some(stream.parse()?);
+                    }
+                    "migrate" => {
+                        let content;
+                        syn::parenthesized!(content in stream);
+                        while !content.is_empty() {
+                            let lit: LitInt = content.parse()?;
+                            content.parse::<Token![=]>()?;
+                            args.migrations.push(AccountMigration {
+                                version: lit.base10_parse()?,
+                                layout: content.parse()?,
+                            });
+                            if !content.is_empty() {
+                                content.parse::<Token![,]>()?;
+                            }
+                        }
+                    }
+                    _ => return Err(ParseError::new(kw_span, "unexpected account argument")),
+                }
+            }
+            if !stream.is_empty() {
+                stream.parse::<Token![,]>()?;
+            }
+        }
+
+        if let Some(discriminator) = &args.discriminator {
+            if args.namespace.is_some() {
+                return Err(ParseError::new(
+                    discriminator.span,
+                    "a namespace can't be combined with an explicit discriminator",
+                ));
+            }
+            // Zero copy data is reinterpreted in place, so it must stay aligned.
+            if args.zero_copy && discriminator.bytes.len() % 8 != 0 {
+                return Err(ParseError::new(
+                    discriminator.span,
+                    "zero copy account discriminators must be a multiple of 8 bytes",
+                ));
+            }
+        }
+
+        if let Some(version) = &args.version {
+            if args.zero_copy {
+                return Err(ParseError::new(
+                    version.span,
+                    "zero copy accounts can't be versioned",
+                ));
+            }
+            if let Some(m) = args
+                .migrations
+                .iter()
+                .find(|m| m.version >= version.version)
+            {
+                return Err(ParseError::new_spanned(
+                    &m.layout,
+                    "migrations must be from a version lower than the current one",
+                ));
+            }
+            for (i, m) in args.migrations.iter().enumerate() {
+                if args.migrations[..i].iter().any(|p| p.version == m.version) {
+                    return Err(ParseError::new_spanned(
+                        &m.layout,
+                        "duplicate migration version",
+                    ));
+                }
+            }
+        } else if let Some(m) = args.migrations.first() {
+            return Err(ParseError::new_spanned(
+                &m.layout,
+                "migrate requires a version, e.g. #[account(version = 2, migrate(1 = DataV1))]",
+            ));
+        }
+
+        Ok(args)
+    }
+}
+
+// Arguments to the `#[native_account(..)]` attribute.
+#[derive(Debug)]
+pub struct NativeAccountArgs {
+    // Program owning the wrapped account, e.g. `owner = spl_token::ID`.
+    pub owner: Expr,
+    // True if the wrapped type is Borsh encoded instead of `Pack`ed.
+    pub borsh: bool,
+}
+
+impl Parse for NativeAccountArgs {
+    fn parse(stream: ParseStream) -> ParseResult<Self> {
+        let mut owner = None;
+        let mut borsh = false;
+        while !stream.is_empty() {
+            let kw_span = stream.span();
+            let kw = stream.call(Ident::parse_any)?.to_string();
+            match kw.as_str() {
+                "owner" => {
+                    stream.parse::<Token![=]>()?;
+                    owner = Some(stream.parse()?);
+                }
+                "borsh" => borsh = true,
+                _ => {
+                    return Err(ParseError::new(
+                        kw_span,
+                        "unexpected native account argument",
+                    ))
+                }
+            }
+            if !stream.is_empty() {
+                stream.parse::<Token![,]>()?;
+            }
+        }
+        Ok(NativeAccountArgs {
+            owner: owner
+                .ok_or_else(|| stream.error("expected owner, e.g. owner = spl_token::ID"))?,
+            borsh,
+        })
+    }
+}
+
+// Arguments to the `#[instruction(..)]` attribute on a program handler.
+#[derive(Debug, Default)]
+pub struct InstructionArgs {
+    pub discriminator: Option<DiscriminatorArg>,
+}
+
+impl Parse for InstructionArgs {
+    fn parse(stream: ParseStream) -> ParseResult<Self> {
+        Ok(InstructionArgs {
+            discriminator: parse_discriminator_only(stream)?,
+        })
+    }
+}
+
+// Arguments to the `#[event(..)]` attribute.
+#[derive(Debug, Default)]
+pub struct EventArgs {
+    pub discriminator: Option<DiscriminatorArg>,
+}
+
+impl Parse for EventArgs {
+    fn parse(stream: ParseStream) -> ParseResult<Self> {
+        Ok(EventArgs {
+            discriminator: parse_discriminator_only(stream)?,
+        })
+    }
+}
+
+// Parses an optional, lone `discriminator = <value>` argument.
+fn parse_discriminator_only(stream: ParseStream) -> ParseResult<Option<DiscriminatorArg>> {
+    if stream.is_empty() {
+        return Ok(None);
+    }
+    let kw_span = stream.span();
+    let kw = stream.call(Ident::parse_any)?;
+    if kw != "discriminator" {
+        return Err(ParseError::new(kw_span, "expected keyword discriminator"));
+    }
+    stream.parse::<Token![=]>()?;
+    let discriminator = stream.parse()?;
+    if !stream.is_empty() {
+        stream.parse::<Token![,]>()?;
+    }
+    if !stream.is_empty() {
+        return Err(stream.error("unexpected argument"));
+    }
+    Ok(Some(discriminator))
+}
+
+// An explicit discriminator, given as a byte array, e.g. `[1, 2, 3]`, a byte
+// string, e.g. `b"vault"`, or a single byte, e.g. `7`.
+#[derive(Debug, Clone)]
+pub struct DiscriminatorArg {
+    pub span: Span,
+    pub bytes: Vec<u8>,
+}
+
+impl Parse for DiscriminatorArg {
+    fn parse(stream: ParseStream) -> ParseResult<Self> {
+        let span = stream.span();
+        let bytes = if stream.peek(syn::token::Bracket) {
+            let content;
+            syn::bracketed!(content in stream);
+            Punctuated::<LitInt, Comma>::parse_terminated(&content)?
+                .iter()
+                .map(|lit| lit.base10_parse::<u8>())
+                .collect::<ParseResult<Vec<u8>>>()?
+        } else if stream.peek(syn::LitByteStr) {
+            stream.parse::<syn::LitByteStr>()?.value()
+        } else {
+            vec![stream.parse::<LitInt>()?.base10_parse::<u8>()?]
+        };
+        if bytes.is_empty() {
+            return Err(ParseError::new(span, "discriminator can't be empty"));
+        }
+        Ok(DiscriminatorArg { span, bytes })
+    }
+}
+
 // All well formed constraints on a single `Accounts` field.
 #[derive(Debug, Default, Clone)]
 pub struct ConstraintGroup {
     init: Option<ConstraintInitGroup>,
     zeroed: Option<ConstraintZeroed>,
     mutable: Option<ConstraintMut>,
+    dup: Option<ConstraintDup>,
     signer: Option<ConstraintSigner>,
     owner: Option<ConstraintOwner>,
     rent_exempt: Option<ConstraintRentExempt>,
@@ -592,6 +841,10 @@ impl ConstraintGroup {
         self.mutable.is_some()
     }
 
+    pub fn is_dup(&self) -> bool {
+        self.dup.is_some()
+    }
+
     pub fn is_signer(&self) -> bool {
         self.signer.is_some()
     }
@@ -631,6 +884,7 @@ pub enum ConstraintToken {
     Init(Context<ConstraintInit>),
     Zeroed(Context<ConstraintZeroed>),
     Mut(Context<ConstraintMut>),
+    Dup(Context<ConstraintDup>),
     Signer(Context<ConstraintSigner>),
     HasOne(Context<ConstraintHasOne>),
     Literal(Context<ConstraintLiteral>),
@@ -677,6 +931,10 @@ pub struct ConstraintMut {
     pub error: Option<Expr>,
 }
 
+// Allows a mutable account to alias other mutable accounts.
+#[derive(Debug, Clone)]
+pub struct ConstraintDup {}
+
 #[derive(Debug, Clone)]
 pub struct ConstraintSigner {
     pub error: Option<Expr>,
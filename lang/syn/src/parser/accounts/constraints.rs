@@ -75,6 +75,7 @@ pub fn parse_token(stream: ParseStream) -> ParseResult<ConstraintToken> {
                 error: parse_optional_custom_error(&stream)?,
             },
         )),
+        "dup" => ConstraintToken::Dup(Context::new(ident.span(), ConstraintDup {})),
         "signer" => ConstraintToken::Signer(Context::new(
             ident.span(),
             ConstraintSigner {
@@ -314,6 +315,7 @@ pub struct ConstraintGroupBuilder<'ty> {
     pub init: Option<Context<ConstraintInit>>,
     pub zeroed: Option<Context<ConstraintZeroed>>,
     pub mutable: Option<Context<ConstraintMut>>,
+    pub dup: Option<Context<ConstraintDup>>,
     pub signer: Option<Context<ConstraintSigner>>,
     pub has_one: Vec<Context<ConstraintHasOne>>,
     pub literal: Vec<Context<ConstraintLiteral>>,
@@ -345,6 +347,7 @@ impl<'ty> ConstraintGroupBuilder<'ty> {
             init: None,
             zeroed: None,
             mutable: None,
+            dup: None,
             signer: None,
             has_one: Vec::new(),
             literal: Vec::new(),
@@ -446,6 +449,16 @@ impl<'ty> ConstraintGroupBuilder<'ty> {
             }
         }
 
+        // Dup.
+        if let Some(d) = &self.dup {
+            if self.f_ty.is_some() && self.mutable.is_none() {
+                return Err(ParseError::new(
+                    d.span(),
+                    "dup can only be provided with mut",
+                ));
+            }
+        }
+
         // Seeds.
         if let Some(i) = &self.seeds {
             if self.init.is_some() && self.payer.is_none() {
@@ -523,6 +536,7 @@ impl<'ty> ConstraintGroupBuilder<'ty> {
             init,
             zeroed,
             mutable,
+            dup,
             signer,
             has_one,
             literal,
@@ -631,6 +645,7 @@ impl<'ty> ConstraintGroupBuilder<'ty> {
             })).transpose()?,
             zeroed: into_inner!(zeroed),
             mutable: into_inner!(mutable),
+            dup: into_inner!(dup),
             signer: into_inner!(signer),
             has_one: into_inner_vec!(has_one),
             literal: into_inner_vec!(literal),
@@ -651,6 +666,7 @@ impl<'ty> ConstraintGroupBuilder<'ty> {
             ConstraintToken::Init(c) => self.add_init(c),
             ConstraintToken::Zeroed(c) => self.add_zeroed(c),
             ConstraintToken::Mut(c) => self.add_mut(c),
+            ConstraintToken::Dup(c) => self.add_dup(c),
             ConstraintToken::Signer(c) => self.add_signer(c),
             ConstraintToken::HasOne(c) => self.add_has_one(c),
             ConstraintToken::Literal(c) => self.add_literal(c),
@@ -898,6 +914,14 @@ impl<'ty> ConstraintGroupBuilder<'ty> {
         Ok(())
     }
 
+    fn add_dup(&mut self, c: Context<ConstraintDup>) -> ParseResult<()> {
+        if self.dup.is_some() {
+            return Err(ParseError::new(c.span(), "dup already provided"));
+        }
+        self.dup.replace(c);
+        Ok(())
+    }
+
     fn add_signer(&mut self, c: Context<ConstraintSigner>) -> ParseResult<()> {
         if self.signer.is_some() {
             return Err(ParseError::new(c.span(), "signer already provided"));
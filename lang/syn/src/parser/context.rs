@@ -1,13 +1,15 @@
-use anyhow::anyhow;
+use anyhow::{anyhow, Context};
 use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
 use syn::parse::{Error as ParseError, Result as ParseResult};
 
 /// Crate parse context
 ///
-/// Keeps track of modules defined within a crate.
+/// Keeps track of modules defined within a crate, and of the crates it
+/// depends on.
 pub struct CrateContext {
     modules: BTreeMap<String, ParsedModule>,
+    dependencies: Vec<CrateContext>,
 }
 
 impl CrateContext {
@@ -23,6 +25,10 @@ impl CrateContext {
         self.modules.iter().flat_map(|(_, ctx)| ctx.enums())
     }
 
+    pub fn type_aliases(&self) -> impl Iterator<Item = &syn::ItemType> {
+        self.modules.values().flat_map(|ctx| ctx.type_aliases())
+    }
+
     pub fn modules(&self) -> impl Iterator<Item = ModuleContext> {
         self.modules
             .iter()
@@ -35,12 +41,35 @@ impl CrateContext {
         }
     }
 
+    pub fn dependencies(&self) -> &[CrateContext] {
+        &self.dependencies
+    }
+
     pub fn parse(root: impl AsRef<Path>) -> Result<Self, anyhow::Error> {
         Ok(CrateContext {
             modules: ParsedModule::parse_recursive(root.as_ref())?,
+            dependencies: vec![],
         })
     }
 
+    /// Parses the crate along with the crates it depends on, given by their
+    /// root files. Dependencies are only used to resolve the items imported by
+    /// the crate.
+    pub fn parse_with_dependencies(
+        root: impl AsRef<Path>,
+        dependencies: &[PathBuf],
+    ) -> Result<Self, anyhow::Error> {
+        let mut ctx = Self::parse(root)?;
+        ctx.dependencies = dependencies
+            .iter()
+            .map(|dep| {
+                Self::parse(dep)
+                    .with_context(|| format!("Failed to parse the dependency at {}", dep.display()))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(ctx)
+    }
+
     // Perform Anchor safety checks on the parsed create
     pub fn safety_checks(&self) -> Result<(), anyhow::Error> {
         // Check all structs for unsafe field types, i.e. AccountInfo and UncheckedAccount.
@@ -89,6 +118,15 @@ impl<'krate> ModuleContext<'krate> {
     pub fn items(&self) -> impl Iterator<Item = &syn::Item> {
         self.detail.items.iter()
     }
+
+    pub fn file(&self) -> &'krate Path {
+        &self.detail.file
+    }
+
+    /// The module's name, empty for the crate root.
+    pub fn name(&self) -> &'krate str {
+        &self.detail.name
+    }
 }
 struct ParsedModule {
     name: String,
@@ -244,4 +282,11 @@ impl ParsedModule {
             _ => None,
         })
     }
+
+    fn type_aliases(&self) -> impl Iterator<Item = &syn::ItemType> {
+        self.items.iter().filter_map(|i| match i {
+            syn::Item::Type(item) => Some(item),
+            _ => None,
+        })
+    }
 }
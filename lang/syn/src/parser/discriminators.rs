@@ -0,0 +1,184 @@
+use crate::codegen::program::common::{
+    ix_discriminator, sighash, sighash_ctor, IDL_IX_TAG, SIGHASH_GLOBAL_NAMESPACE,
+    SIGHASH_STATE_NAMESPACE,
+};
+use crate::Program;
+use heck::MixedCase;
+use proc_macro2::Span;
+use syn::parse::{Error as ParseError, Result as ParseResult};
+
+// An item identified by its discriminator, e.g. an instruction or an account.
+#[derive(Debug)]
+pub struct Discriminated {
+    // Description used in diagnostics, e.g. "instruction `initialize`".
+    pub desc: String,
+    // Namespaced name as seen by clients, i.e. after case conversion.
+    pub client_name: String,
+    pub bytes: Vec<u8>,
+    pub span: Span,
+}
+
+// All instructions dispatched by the program, including the IDL instructions
+// injected into every program.
+pub fn instructions(program: &Program) -> Vec<Discriminated> {
+    let mut ixs = vec![Discriminated {
+        desc: "the IDL instruction tag".to_string(),
+        client_name: "anchor:idl".to_string(),
+        bytes: IDL_IX_TAG.to_le_bytes().to_vec(),
+        span: program.name.span(),
+    }];
+
+    if let Some(state) = &program.state {
+        if let Some((ctor, _)) = &state.ctor_and_anchor {
+            ixs.push(Discriminated {
+                desc: "state constructor `new`".to_string(),
+                client_name: format!("{}:new", SIGHASH_STATE_NAMESPACE),
+                bytes: sighash_ctor().to_vec(),
+                span: ctor.sig.ident.span(),
+            });
+        }
+        if let Some((_, methods)) = &state.impl_block_and_methods {
+            for method in methods {
+                let name = method.ident.to_string();
+                ixs.push(Discriminated {
+                    desc: format!("state method `{}`", name),
+                    client_name: format!("{}:{}", SIGHASH_STATE_NAMESPACE, name.to_mixed_case()),
+                    bytes: sighash(SIGHASH_STATE_NAMESPACE, &name).to_vec(),
+                    span: method.ident.span(),
+                });
+            }
+        }
+        for iface in state.interfaces.iter().flatten() {
+            for method in &iface.methods {
+                let name = method.ident.to_string();
+                ixs.push(Discriminated {
+                    desc: format!("interface method `{}::{}`", iface.trait_name, name),
+                    client_name: format!("{}:{}", iface.trait_name, name.to_mixed_case()),
+                    bytes: sighash(&iface.trait_name, &name).to_vec(),
+                    span: method.ident.span(),
+                });
+            }
+        }
+    }
+
+    for ix in &program.ixs {
+        let name = ix.raw_method.sig.ident.to_string();
+        ixs.push(Discriminated {
+            desc: format!("instruction `{}`", name),
+            client_name: format!("{}:{}", SIGHASH_GLOBAL_NAMESPACE, name.to_mixed_case()),
+            bytes: ix_discriminator(ix),
+            span: ix
+                .discriminator
+                .as_ref()
+                .map(|d| d.span)
+                .unwrap_or_else(|| ix.raw_method.sig.ident.span()),
+        });
+    }
+
+    ixs
+}
+
+// Returns the index of the first item whose discriminator is equal to, or a
+// prefix of, a previous one, along with a description of the collision.
+//
+// Names that are identical after case conversion collide as well, since
+// clients derive discriminators from the converted names in the IDL.
+pub fn find_collision(items: &[Discriminated]) -> Option<(usize, String)> {
+    for (idx, item) in items.iter().enumerate() {
+        for prev in &items[..idx] {
+            if item.client_name == prev.client_name {
+                return Some((
+                    idx,
+                    format!(
+                        "{} and {} have the same name after case conversion",
+                        item.desc, prev.desc
+                    ),
+                ));
+            }
+            if item.bytes.starts_with(&prev.bytes) || prev.bytes.starts_with(&item.bytes) {
+                return Some((
+                    idx,
+                    format!(
+                        "discriminator of {} collides with {}: {:?} and {:?}",
+                        item.desc, prev.desc, item.bytes, prev.bytes
+                    ),
+                ));
+            }
+        }
+    }
+    None
+}
+
+pub fn check(items: &[Discriminated]) -> ParseResult<()> {
+    match find_collision(items) {
+        None => Ok(()),
+        Some((idx, msg)) => Err(ParseError::new(items[idx].span, msg)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Program;
+
+    fn parse_err(program: &str) -> Option<String> {
+        syn::parse_str::<Program>(program)
+            .err()
+            .map(|e| e.to_string())
+    }
+
+    #[test]
+    fn distinct_instructions() {
+        assert_eq!(
+            parse_err(
+                "mod p {
+                    pub fn a(ctx: Context<A>) -> Result<()> { Ok(()) }
+                    #[instruction(discriminator = [1])]
+                    pub fn b(ctx: Context<B>) -> Result<()> { Ok(()) }
+                }"
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn prefix_collision() {
+        let err = parse_err(
+            "mod p {
+                #[instruction(discriminator = [1])]
+                pub fn a(ctx: Context<A>) -> Result<()> { Ok(()) }
+                #[instruction(discriminator = [1, 2])]
+                pub fn b(ctx: Context<B>) -> Result<()> { Ok(()) }
+            }",
+        )
+        .unwrap();
+        assert!(err.starts_with("discriminator of instruction `b` collides with instruction `a`"));
+    }
+
+    #[test]
+    fn idl_tag_collision() {
+        let err = parse_err(
+            "mod p {
+                #[instruction(discriminator = [64, 244, 188, 120, 167, 233, 105, 10])]
+                pub fn a(ctx: Context<A>) -> Result<()> { Ok(()) }
+            }",
+        )
+        .unwrap();
+        assert!(err.contains("collides with the IDL instruction tag"));
+    }
+
+    #[test]
+    fn case_conversion_collision() {
+        let err = parse_err(
+            "mod p {
+                pub fn foo_bar(ctx: Context<A>) -> Result<()> { Ok(()) }
+                #[allow(non_snake_case)]
+                pub fn fooBar(ctx: Context<B>) -> Result<()> { Ok(()) }
+            }",
+        )
+        .unwrap();
+        assert_eq!(
+            err,
+            "instruction `fooBar` and instruction `foo_bar` have the same name after case conversion"
+        );
+    }
+}
@@ -1,5 +1,5 @@
 use crate::parser::program::ctx_accounts_ident;
-use crate::{FallbackFn, Ix, IxArg};
+use crate::{FallbackFn, InstructionArgs, Ix, IxArg};
 use syn::parse::{Error as ParseError, Result as ParseResult};
 use syn::spanned::Spanned;
 
@@ -24,11 +24,15 @@ pub fn parse(program_mod: &syn::ItemMod) -> ParseResult<(Vec<Ix>, Option<Fallbac
         .map(|method: &syn::ItemFn| {
             let (ctx, args) = parse_args(method)?;
             let anchor_ident = ctx_accounts_ident(&ctx.raw_arg)?;
+            let discriminator = parse_ix_attr(method)?.discriminator;
+            let mut raw_method = method.clone();
+            raw_method.attrs.retain(|attr| !is_ix_attr(attr));
             Ok(Ix {
-                raw_method: method.clone(),
+                raw_method,
                 ident: method.sig.ident.clone(),
                 args,
                 anchor_ident,
+                discriminator,
             })
         })
         .collect::<ParseResult<Vec<Ix>>>()?;
@@ -63,6 +67,38 @@ pub fn parse(program_mod: &syn::ItemMod) -> ParseResult<(Vec<Ix>, Option<Fallbac
     Ok((ixs, fallback_fn))
 }
 
+// Parse the optional `#[instruction(..)]` attribute on a handler.
+fn parse_ix_attr(method: &syn::ItemFn) -> ParseResult<InstructionArgs> {
+    let mut attrs = method.attrs.iter().filter(|attr| is_ix_attr(attr));
+    let args = match attrs.next() {
+        None => InstructionArgs::default(),
+        Some(attr) => attr.parse_args()?,
+    };
+    if let Some(attr) = attrs.next() {
+        return Err(ParseError::new(
+            attr.span(),
+            "only one instruction attribute is allowed",
+        ));
+    }
+    Ok(args)
+}
+
+fn is_ix_attr(attr: &syn::Attribute) -> bool {
+    attr.path.is_ident("instruction")
+}
+
+// The `#[instruction(..)]` attribute is inert, so it's removed from the
+// handlers before the program mod is emitted.
+pub fn strip_ix_attrs(program_mod: &mut syn::ItemMod) {
+    if let Some((_, items)) = &mut program_mod.content {
+        for item in items.iter_mut() {
+            if let syn::Item::Fn(item_fn) = item {
+                item_fn.attrs.retain(|attr| !is_ix_attr(attr));
+            }
+        }
+    }
+}
+
 pub fn parse_args(method: &syn::ItemFn) -> ParseResult<(IxArg, Vec<IxArg>)> {
     let mut args: Vec<IxArg> = method
         .sig
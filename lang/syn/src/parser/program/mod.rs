@@ -1,3 +1,4 @@
+use crate::parser::discriminators;
 use crate::Program;
 use syn::parse::{Error as ParseError, Result as ParseResult};
 use syn::spanned::Spanned;
@@ -5,16 +6,19 @@ use syn::spanned::Spanned;
 mod instructions;
 mod state;
 
-pub fn parse(program_mod: syn::ItemMod) -> ParseResult<Program> {
+pub fn parse(mut program_mod: syn::ItemMod) -> ParseResult<Program> {
     let state = state::parse(&program_mod)?;
     let (ixs, fallback_fn) = instructions::parse(&program_mod)?;
-    Ok(Program {
+    instructions::strip_ix_attrs(&mut program_mod);
+    let program = Program {
         state,
         ixs,
         name: program_mod.ident.clone(),
         program_mod,
         fallback_fn,
-    })
+    };
+    discriminators::check(&discriminators::instructions(&program))?;
+    Ok(program)
 }
 
 fn ctx_accounts_ident(path_ty: &syn::PatType) -> ParseResult<proc_macro2::Ident> {
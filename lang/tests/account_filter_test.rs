@@ -0,0 +1,89 @@
+use anchor_lang::prelude::*;
+use anchor_lang::{AccountFilter, AccountLayout};
+
+// Needed to declare accounts.
+declare_id!("Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS");
+
+#[account]
+pub struct Vault {
+    pub authority: Pubkey,
+    pub amount: u64,
+    pub bumps: [u8; 2],
+}
+
+#[account(discriminator = [1, 2], version = 1)]
+pub struct Profile {
+    pub owner: Pubkey,
+    pub name: String,
+    pub score: u64,
+}
+
+#[account(zero_copy)]
+pub struct Book {
+    pub open: u8,
+    pub market: Pubkey,
+    pub size: u64,
+}
+
+#[test]
+fn field_offsets() {
+    assert_eq!(
+        Vault::FIELD_OFFSETS,
+        &[("authority", 8), ("amount", 40), ("bumps", 48)]
+    );
+    assert_eq!(Vault::DATA_SIZE, Some(50));
+
+    // The version byte follows the discriminator, and fields after the first
+    // of variable size aren't at a fixed offset.
+    assert_eq!(Profile::FIELD_OFFSETS, &[("owner", 3)]);
+    assert_eq!(Profile::DATA_SIZE, None);
+    assert_eq!(Profile::field_offset("score"), None);
+
+    // Zero copy fields are aligned.
+    assert_eq!(
+        Book::FIELD_OFFSETS,
+        &[("open", 8), ("market", 9), ("size", 48)]
+    );
+    assert_eq!(Book::DATA_SIZE, Some(8 + std::mem::size_of::<Book>()));
+}
+
+#[test]
+fn filter_builder() {
+    let authority = Pubkey::new_unique();
+    let filters: Vec<AccountFilter> = Vault::filter().authority_eq(authority).amount_eq(5).into();
+    assert_eq!(
+        filters,
+        vec![
+            AccountFilter::DataSize(50),
+            AccountFilter::Memcmp {
+                offset: 8,
+                bytes: authority.to_bytes().to_vec(),
+            },
+            AccountFilter::Memcmp {
+                offset: 40,
+                bytes: 5u64.to_le_bytes().to_vec(),
+            },
+        ]
+    );
+
+    let owner = Pubkey::new_unique();
+    assert_eq!(
+        Profile::filter().owner_eq(owner).into_filters(),
+        vec![AccountFilter::Memcmp {
+            offset: 3,
+            bytes: owner.to_bytes().to_vec(),
+        }]
+    );
+
+    let data_size = 8 + std::mem::size_of::<Book>() as u64;
+    assert_eq!(
+        Book::filter().size_eq(7).into_filters(),
+        vec![
+            AccountFilter::DataSize(data_size),
+            AccountFilter::Memcmp {
+                offset: 48,
+                bytes: 7u64.to_le_bytes().to_vec(),
+            },
+        ]
+    );
+}
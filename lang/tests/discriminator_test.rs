@@ -0,0 +1,115 @@
+use anchor_lang::prelude::*;
+use anchor_lang::{Discriminator, Event, InstructionData};
+
+// Needed to declare accounts.
+declare_id!("Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS");
+
+#[program]
+pub mod discriminators {
+    use super::*;
+
+    #[instruction(discriminator = [7])]
+    pub fn short(_ctx: Context<Empty>, amount: u64) -> Result<()> {
+        msg!("{}", amount);
+        Ok(())
+    }
+
+    pub fn default(_ctx: Context<Empty>) -> Result<()> {
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct Empty {}
+
+#[account(discriminator = [1, 2])]
+pub struct Short {
+    pub authority: Pubkey,
+}
+
+#[account(discriminator = b"vault", version = 2, migrate(1 = ShortV1))]
+pub struct Versioned {
+    pub authority: Pubkey,
+    pub amount: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct ShortV1 {
+    pub authority: Pubkey,
+}
+
+impl Migrate<ShortV1> for Versioned {
+    fn migrate(prev: ShortV1) -> Self {
+        Self {
+            authority: prev.authority,
+            amount: 0,
+        }
+    }
+}
+
+#[event(discriminator = 0xff)]
+pub struct Happened {
+    pub value: u8,
+}
+
+#[test]
+fn account_discriminator() {
+    assert_eq!(Short::DISCRIMINATOR, &[1, 2]);
+
+    let short = Short {
+        authority: Pubkey::new_unique(),
+    };
+    let mut buf = Vec::new();
+    short.try_serialize(&mut buf).unwrap();
+    assert_eq!(&buf[..2], &[1, 2]);
+    assert_eq!(&buf[2..], short.authority.as_ref());
+
+    let de = Short::try_deserialize(&mut &buf[..]).unwrap();
+    assert_eq!(de.authority, short.authority);
+
+    buf[1] = 3;
+    assert!(Short::try_deserialize(&mut &buf[..]).is_err());
+    assert!(Short::try_deserialize(&mut &[1u8][..]).is_err());
+}
+
+#[test]
+fn versioned_account_discriminator() {
+    let authority = Pubkey::new_unique();
+    let mut buf = b"vault".to_vec();
+    buf.push(1);
+    buf.extend_from_slice(authority.as_ref());
+
+    let de = Versioned::try_deserialize(&mut &buf[..]).unwrap();
+    assert_eq!(de.authority, authority);
+
+    let mut out = Vec::new();
+    de.try_serialize(&mut out).unwrap();
+    assert_eq!(&out[..5], b"vault");
+    assert_eq!(out[5], 2);
+}
+
+#[test]
+fn event_discriminator() {
+    assert_eq!(Happened::DISCRIMINATOR, &[0xff]);
+    assert_eq!(Happened { value: 3 }.data(), vec![0xff, 3]);
+}
+
+#[test]
+fn instruction_discriminator() {
+    assert_eq!(instruction::Short::DISCRIMINATOR, &[7]);
+    assert_eq!(
+        instruction::Short { amount: 1 }.data(),
+        vec![7, 1, 0, 0, 0, 0, 0, 0, 0]
+    );
+    assert_eq!(instruction::Default::DISCRIMINATOR.len(), 8);
+}
+
+#[test]
+fn idl_instruction_tag() {
+    // The parser checks instruction discriminators against its own copy of
+    // the tag.
+    assert_eq!(
+        anchor_syn::codegen::program::common::IDL_IX_TAG,
+        anchor_lang::idl::IDL_IX_TAG
+    );
+}
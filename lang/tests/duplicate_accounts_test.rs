@@ -0,0 +1,127 @@
+use anchor_lang::error::ErrorCode;
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::clock::Epoch;
+use std::collections::BTreeMap;
+
+declare_id!("Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS");
+
+#[derive(Accounts)]
+pub struct Transfer<'info> {
+    #[account(mut)]
+    pub from: AccountInfo<'info>,
+    #[account(mut)]
+    pub to: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct Aliased<'info> {
+    #[account(mut)]
+    pub from: AccountInfo<'info>,
+    #[account(mut, dup)]
+    pub to: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ReadOnly<'info> {
+    pub a: AccountInfo<'info>,
+    pub b: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct Nested<'info> {
+    #[account(mut)]
+    pub fee_payer: AccountInfo<'info>,
+    pub transfer: Transfer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct NestedAliased<'info> {
+    #[account(mut)]
+    pub fee_payer: AccountInfo<'info>,
+    #[account(dup)]
+    pub transfer: Transfer<'info>,
+}
+
+struct Keys(Vec<(Pubkey, u64, Vec<u8>)>);
+
+impl Keys {
+    fn new(keys: &[Pubkey]) -> Self {
+        Keys(keys.iter().map(|k| (*k, 0, vec![])).collect())
+    }
+
+    fn infos<'a>(&'a mut self, owner: &'a Pubkey) -> Vec<AccountInfo<'a>> {
+        self.0
+            .iter_mut()
+            .map(|(key, lamports, data)| {
+                AccountInfo::new(
+                    key,
+                    false,
+                    true,
+                    lamports,
+                    data,
+                    owner,
+                    false,
+                    Epoch::default(),
+                )
+            })
+            .collect()
+    }
+}
+
+fn try_accounts<'info, T: Accounts<'info>>(infos: &[AccountInfo<'info>]) -> Result<T> {
+    let mut accounts = infos;
+    T::try_accounts(&crate::ID, &mut accounts, &[], &mut BTreeMap::new())
+}
+
+fn duplicate_error<T>(res: Result<T>) -> Option<String> {
+    match res {
+        Err(Error::AnchorError(e))
+            if e.error_code_number == ErrorCode::ConstraintDuplicateMutableAccount as u32 =>
+        {
+            e.account_name
+        }
+        _ => None,
+    }
+}
+
+#[test]
+fn rejects_duplicate_mutable_accounts() {
+    let owner = Pubkey::default();
+    let a = Pubkey::new_unique();
+    let b = Pubkey::new_unique();
+
+    let mut distinct = Keys::new(&[a, b]);
+    assert!(try_accounts::<Transfer>(&distinct.infos(&owner)).is_ok());
+
+    let mut same = Keys::new(&[a, a]);
+    assert_eq!(
+        duplicate_error(try_accounts::<Transfer>(&same.infos(&owner))),
+        Some("to".to_string())
+    );
+    assert!(try_accounts::<Aliased>(&same.infos(&owner)).is_ok());
+    assert!(try_accounts::<ReadOnly>(&same.infos(&owner)).is_ok());
+}
+
+#[test]
+fn rejects_duplicates_across_nested_accounts() {
+    let owner = Pubkey::default();
+    let a = Pubkey::new_unique();
+    let b = Pubkey::new_unique();
+
+    let mut distinct = Keys::new(&[a, b, Pubkey::new_unique()]);
+    let nested = try_accounts::<Nested>(&distinct.infos(&owner)).unwrap();
+    assert_eq!(nested.mutable_keys().len(), 3);
+
+    let mut aliased = Keys::new(&[a, b, a]);
+    assert_eq!(
+        duplicate_error(try_accounts::<Nested>(&aliased.infos(&owner))),
+        Some("transfer".to_string())
+    );
+    assert!(try_accounts::<NestedAliased>(&aliased.infos(&owner)).is_ok());
+
+    let mut inner = Keys::new(&[a, b, b]);
+    assert_eq!(
+        duplicate_error(try_accounts::<Nested>(&inner.infos(&owner))),
+        Some("to".to_string())
+    );
+}
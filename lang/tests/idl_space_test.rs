@@ -0,0 +1,28 @@
+use anchor_lang::error::ErrorCode;
+use anchor_lang::idl::IdlAccount;
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::system_instruction::MAX_PERMITTED_DATA_LENGTH;
+
+fn error_code(res: Result<usize>) -> Option<u32> {
+    match res {
+        Err(Error::AnchorError(e)) => Some(e.error_code_number),
+        _ => None,
+    }
+}
+
+#[test]
+fn checks_requested_idl_space() {
+    assert_eq!(IdlAccount::try_space(100).unwrap(), IdlAccount::space(100));
+
+    let max = MAX_PERMITTED_DATA_LENGTH - IdlAccount::space(0) as u64;
+    assert_eq!(
+        IdlAccount::try_space(max).unwrap(),
+        MAX_PERMITTED_DATA_LENGTH as usize
+    );
+    for data_len in [max + 1, u64::MAX] {
+        assert_eq!(
+            error_code(IdlAccount::try_space(data_len)),
+            Some(ErrorCode::IdlAccountTooLarge as u32)
+        );
+    }
+}
@@ -0,0 +1,113 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program_pack::{IsInitialized, Pack, Sealed};
+
+// Needed to declare accounts.
+declare_id!("Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS");
+
+#[derive(Clone, Copy, Default)]
+pub struct Counter {
+    pub initialized: bool,
+    pub count: u32,
+}
+
+impl Sealed for Counter {}
+
+impl IsInitialized for Counter {
+    fn is_initialized(&self) -> bool {
+        self.initialized
+    }
+}
+
+impl Pack for Counter {
+    const LEN: usize = 5;
+
+    fn pack_into_slice(&self, dst: &mut [u8]) {
+        dst[0] = self.initialized as u8;
+        dst[1..].copy_from_slice(&self.count.to_le_bytes());
+    }
+
+    fn unpack_from_slice(src: &[u8]) -> std::result::Result<Self, ProgramError> {
+        let mut count = [0u8; 4];
+        count.copy_from_slice(&src[1..]);
+        Ok(Counter {
+            initialized: src[0] == 1,
+            count: u32::from_le_bytes(count),
+        })
+    }
+}
+
+#[native_account(owner = crate::ID)]
+#[derive(Clone)]
+pub struct CounterAccount(Counter);
+
+#[derive(Clone, AnchorSerialize, AnchorDeserialize)]
+pub struct Record {
+    pub initialized: bool,
+    pub weight: u64,
+}
+
+impl IsInitialized for Record {
+    fn is_initialized(&self) -> bool {
+        self.initialized
+    }
+}
+
+macro_rules! record {
+    ($id:expr) => {
+        #[native_account(owner = $id, borsh)]
+        #[derive(Clone)]
+        pub struct RecordAccount(Record);
+    };
+}
+
+record!(Pubkey::new_from_array([1; 32]));
+
+#[test]
+fn pack_account() {
+    assert_eq!(CounterAccount::LEN, 5);
+    assert_eq!(CounterAccount::owner(), crate::ID);
+
+    let mut buf = vec![1, 7, 0, 0, 0];
+    let mut account = CounterAccount::try_deserialize(&mut &buf[..]).unwrap();
+    assert_eq!(account.count, 7);
+
+    account.count += 1;
+    buf.clear();
+    account.try_serialize(&mut buf).unwrap();
+    assert_eq!(buf, vec![1, 8, 0, 0, 0]);
+
+    let uninitialized = [0u8; 5];
+    assert!(CounterAccount::try_deserialize(&mut &uninitialized[..]).is_err());
+    assert!(CounterAccount::try_deserialize_unchecked(&mut &uninitialized[..]).is_ok());
+}
+
+#[test]
+fn borsh_account() {
+    assert_eq!(RecordAccount::owner(), Pubkey::new_from_array([1; 32]));
+
+    let record = Record {
+        initialized: true,
+        weight: 3,
+    };
+    let buf = record.try_to_vec().unwrap();
+    let mut account = RecordAccount::try_deserialize(&mut &buf[..]).unwrap();
+    assert_eq!(account.weight, 3);
+
+    account.weight = 4;
+    let mut out = Vec::new();
+    account.try_serialize(&mut out).unwrap();
+    assert_eq!(
+        RecordAccount::try_deserialize(&mut &out[..])
+            .unwrap()
+            .weight,
+        4
+    );
+
+    let uninitialized = Record {
+        initialized: false,
+        weight: 0,
+    }
+    .try_to_vec()
+    .unwrap();
+    assert!(RecordAccount::try_deserialize(&mut &uninitialized[..]).is_err());
+}
@@ -0,0 +1,205 @@
+use anchor_lang::error::ErrorCode;
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::clock::Epoch;
+use anchor_lang::solana_program::program_stubs::{set_syscall_stubs, SyscallStubs};
+use anchor_lang::solana_program::rent::Rent;
+use anchor_lang::{AccountsExit, Discriminator};
+
+// Needed to declare accounts.
+declare_id!("Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS");
+
+#[account(version = 3, migrate(1 = DataV1, 2 = DataV2))]
+pub struct Data {
+    pub authority: Pubkey,
+    pub amount: u64,
+    pub label: String,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct DataV1 {
+    pub authority: Pubkey,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct DataV2 {
+    pub authority: Pubkey,
+    pub amount: u64,
+}
+
+impl Migrate<DataV1> for Data {
+    fn migrate(prev: DataV1) -> Self {
+        Self {
+            authority: prev.authority,
+            amount: 0,
+            label: String::new(),
+        }
+    }
+}
+
+impl Migrate<DataV2> for Data {
+    fn migrate(prev: DataV2) -> Self {
+        Self {
+            authority: prev.authority,
+            amount: prev.amount,
+            label: String::new(),
+        }
+    }
+}
+
+fn stored<T: AnchorSerialize>(version: u8, layout: &T) -> Vec<u8> {
+    let mut data = Data::discriminator().to_vec();
+    data.push(version);
+    data.append(&mut layout.try_to_vec().unwrap());
+    data
+}
+
+#[test]
+fn serializes_version_after_discriminator() {
+    let data = Data {
+        authority: Pubkey::new_unique(),
+        amount: 7,
+        label: "vault".to_string(),
+    };
+    let mut buf = Vec::new();
+    data.try_serialize(&mut buf).unwrap();
+    assert_eq!(&buf[..8], Data::DISCRIMINATOR);
+    assert_eq!(buf[8], 3);
+    assert!(data.realloc_on_exit());
+
+    let de = Data::try_deserialize(&mut &buf[..]).unwrap();
+    assert_eq!(de.authority, data.authority);
+    assert_eq!(de.amount, 7);
+    assert_eq!(de.label, "vault");
+}
+
+#[test]
+fn migrates_previous_layouts() {
+    let authority = Pubkey::new_unique();
+
+    let v1 = stored(1, &DataV1 { authority });
+    let de = Data::try_deserialize(&mut &v1[..]).unwrap();
+    assert_eq!(de.authority, authority);
+    assert_eq!(de.amount, 0);
+
+    let v2 = stored(
+        2,
+        &DataV2 {
+            authority,
+            amount: 42,
+        },
+    );
+    let de = Data::try_deserialize(&mut &v2[..]).unwrap();
+    assert_eq!(de.authority, authority);
+    assert_eq!(de.amount, 42);
+}
+
+#[test]
+fn rejects_unknown_versions() {
+    let v4 = stored(
+        4,
+        &DataV1 {
+            authority: Pubkey::new_unique(),
+        },
+    );
+    assert!(Data::try_deserialize(&mut &v4[..]).is_err());
+
+    let missing = Data::discriminator().to_vec();
+    assert!(Data::try_deserialize(&mut &missing[..]).is_err());
+}
+
+// An account laid out as the runtime serializes it, which `realloc` relies
+// on: the original data length before the key, the data length before the
+// data, and room for the data to grow.
+#[repr(C)]
+struct SerializedAccount {
+    original_data_len: u32,
+    key: Pubkey,
+    owner: Pubkey,
+    lamports: u64,
+    data_len: u64,
+    data: [u8; 256],
+}
+
+impl SerializedAccount {
+    fn new(data: &[u8], lamports: u64) -> Box<Self> {
+        let mut account = Box::new(Self {
+            original_data_len: data.len() as u32,
+            key: Pubkey::new_unique(),
+            owner: crate::ID,
+            lamports,
+            data_len: data.len() as u64,
+            data: [0; 256],
+        });
+        account.data[..data.len()].copy_from_slice(data);
+        account
+    }
+
+    fn info(&mut self) -> AccountInfo<'_> {
+        let len = self.data_len as usize;
+        AccountInfo::new(
+            &self.key,
+            false,
+            true,
+            &mut self.lamports,
+            &mut self.data[..len],
+            &self.owner,
+            false,
+            Epoch::default(),
+        )
+    }
+}
+
+// Provides the rent sysvar off chain.
+struct RentStubs;
+
+impl SyscallStubs for RentStubs {
+    fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+        unsafe { *(var_addr as *mut Rent) = Rent::default() };
+        0
+    }
+}
+
+#[test]
+fn reallocates_migrated_accounts_on_exit() {
+    set_syscall_stubs(Box::new(RentStubs));
+    let authority = Pubkey::new_unique();
+    let v1 = stored(1, &DataV1 { authority });
+    let new_len = 8 + 1 + 32 + 8 + 4;
+    let mut account = SerializedAccount::new(&v1, Rent::default().minimum_balance(new_len));
+    let info = account.info();
+
+    let data = Account::<Data>::try_from(&info).unwrap();
+    data.exit(&crate::ID).unwrap();
+    assert_eq!(info.data_len(), new_len);
+    let stored = info.try_borrow_data().unwrap();
+    assert_eq!(stored[8], 3);
+    let de = Data::try_deserialize(&mut &stored[..]).unwrap();
+    assert_eq!(de.authority, authority);
+    assert_eq!(de.amount, 0);
+    drop(stored);
+    drop(info);
+    assert_eq!(account.data_len, new_len as u64);
+}
+
+#[test]
+fn fails_to_reallocate_without_rent() {
+    set_syscall_stubs(Box::new(RentStubs));
+    let v1 = stored(
+        1,
+        &DataV1 {
+            authority: Pubkey::new_unique(),
+        },
+    );
+    let mut account = SerializedAccount::new(&v1, Rent::default().minimum_balance(v1.len()));
+    let info = account.info();
+
+    let data = Account::<Data>::try_from(&info).unwrap();
+    match data.exit(&crate::ID) {
+        Err(Error::AnchorError(e)) => assert_eq!(
+            e.error_code_number,
+            ErrorCode::AccountReallocNotRentExempt as u32
+        ),
+        res => panic!("unexpected result {:?}", res),
+    }
+    assert_eq!(info.data_len(), v1.len());
+}
@@ -1,10 +1,8 @@
 use anchor_lang::solana_program::account_info::AccountInfo;
 
-use anchor_lang::solana_program::program_pack::Pack;
 use anchor_lang::solana_program::pubkey::Pubkey;
-use anchor_lang::{context::CpiContext, Accounts};
+use anchor_lang::{context::CpiContext, native_account, Accounts};
 use anchor_lang::{solana_program, Result};
-use std::ops::Deref;
 
 pub use spl_token::ID;
 
@@ -311,68 +309,14 @@ pub struct SetAuthority<'info> {
     pub account_or_mint: AccountInfo<'info>,
 }
 
+#[native_account(owner = ID)]
 #[derive(Clone)]
 pub struct TokenAccount(spl_token::state::Account);
 
-impl TokenAccount {
-    pub const LEN: usize = spl_token::state::Account::LEN;
-}
-
-impl anchor_lang::AccountDeserialize for TokenAccount {
-    fn try_deserialize_unchecked(buf: &mut &[u8]) -> anchor_lang::Result<Self> {
-        spl_token::state::Account::unpack(buf)
-            .map(TokenAccount)
-            .map_err(Into::into)
-    }
-}
-
-impl anchor_lang::AccountSerialize for TokenAccount {}
-
-impl anchor_lang::Owner for TokenAccount {
-    fn owner() -> Pubkey {
-        ID
-    }
-}
-
-impl Deref for TokenAccount {
-    type Target = spl_token::state::Account;
-
-    fn deref(&self) -> &Self::Target {
-        &self.0
-    }
-}
-
+#[native_account(owner = ID)]
 #[derive(Clone)]
 pub struct Mint(spl_token::state::Mint);
 
-impl Mint {
-    pub const LEN: usize = spl_token::state::Mint::LEN;
-}
-
-impl anchor_lang::AccountDeserialize for Mint {
-    fn try_deserialize_unchecked(buf: &mut &[u8]) -> anchor_lang::Result<Self> {
-        spl_token::state::Mint::unpack(buf)
-            .map(Mint)
-            .map_err(Into::into)
-    }
-}
-
-impl anchor_lang::AccountSerialize for Mint {}
-
-impl anchor_lang::Owner for Mint {
-    fn owner() -> Pubkey {
-        ID
-    }
-}
-
-impl Deref for Mint {
-    type Target = spl_token::state::Mint;
-
-    fn deref(&self) -> &Self::Target {
-        &self.0
-    }
-}
-
 #[derive(Clone)]
 pub struct Token;
 
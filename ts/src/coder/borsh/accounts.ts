@@ -9,7 +9,7 @@ import { AccountsCoder } from "../index.js";
 import { accountSize } from "../common.js";
 
 /**
- * Number of bytes of the account discriminator.
+ * Number of bytes of the account discriminator, unless given explicitly.
  */
 export const ACCOUNT_DISCRIMINATOR_SIZE = 8;
 
@@ -23,6 +23,12 @@ export class BorshAccountsCoder<A extends string = string>
    */
   private accountLayouts: Map<A, Layout>;
 
+  /**
+   * Maps account type identifier to the layouts of a versioned account,
+   * keyed by version.
+   */
+  private versionLayouts: Map<A, Map<number, Layout>>;
+
   /**
    * IDL whose acconts will be coded.
    */
@@ -31,13 +37,34 @@ export class BorshAccountsCoder<A extends string = string>
   public constructor(idl: Idl) {
     if (idl.accounts === undefined) {
       this.accountLayouts = new Map();
+      this.versionLayouts = new Map();
       return;
     }
     const layouts: [A, Layout][] = idl.accounts.map((acc) => {
       return [acc.name as A, IdlCoder.typeDefLayout(acc, idl.types)];
     });
+    const versionLayouts: [A, Map<number, Layout>][] = idl.accounts
+      .filter((acc) => acc.version !== undefined)
+      .map((acc) => {
+        const versions = new Map<number, Layout>();
+        versions.set(
+          acc.version as number,
+          IdlCoder.typeDefLayout(acc, idl.types)
+        );
+        (acc.history ?? []).forEach((prev) => {
+          versions.set(
+            prev.version,
+            IdlCoder.typeDefLayout(
+              { name: acc.name, type: prev.type },
+              idl.types
+            )
+          );
+        });
+        return [acc.name as A, versions];
+      });
 
     this.accountLayouts = new Map(layouts);
+    this.versionLayouts = new Map(versionLayouts);
     this.idl = idl;
   }
 
@@ -49,22 +76,43 @@ export class BorshAccountsCoder<A extends string = string>
     }
     const len = layout.encode(account, buffer);
     let accountData = buffer.slice(0, len);
-    let discriminator = BorshAccountsCoder.accountDiscriminator(accountName);
+    let discriminator = this.discriminator(accountName);
+    const version = this.accountVersion(accountName);
+    if (version !== undefined) {
+      return Buffer.concat([
+        discriminator,
+        Buffer.from([version]),
+        accountData,
+      ]);
+    }
     return Buffer.concat([discriminator, accountData]);
   }
 
   public decode<T = any>(accountName: A, data: Buffer): T {
     // Assert the account discriminator is correct.
-    const discriminator = BorshAccountsCoder.accountDiscriminator(accountName);
-    if (discriminator.compare(data.slice(0, 8))) {
+    const discriminator = this.discriminator(accountName);
+    if (discriminator.compare(data.slice(0, discriminator.length))) {
       throw new Error("Invalid account discriminator");
     }
     return this.decodeUnchecked(accountName, data);
   }
 
   public decodeUnchecked<T = any>(accountName: A, ix: Buffer): T {
+    const discriminatorSize = this.discriminator(accountName).length;
+    // Versioned accounts store the layout version after the discriminator.
+    const versions = this.versionLayouts.get(accountName);
+    if (versions) {
+      const version = ix[discriminatorSize];
+      const versionLayout = versions.get(version);
+      if (!versionLayout) {
+        throw new Error(
+          `Unknown version ${version} of account: ${accountName}`
+        );
+      }
+      return versionLayout.decode(ix.slice(discriminatorSize + 1));
+    }
     // Chop off the discriminator before decoding.
-    const data = ix.slice(ACCOUNT_DISCRIMINATOR_SIZE);
+    const data = ix.slice(discriminatorSize);
     const layout = this.accountLayouts.get(accountName);
     if (!layout) {
       throw new Error(`Unknown account: ${accountName}`);
@@ -73,7 +121,7 @@ export class BorshAccountsCoder<A extends string = string>
   }
 
   public memcmp(accountName: A, appendData?: Buffer): any {
-    const discriminator = BorshAccountsCoder.accountDiscriminator(accountName);
+    const discriminator = this.discriminator(accountName);
     return {
       offset: 0,
       bytes: bs58.encode(
@@ -83,11 +131,34 @@ export class BorshAccountsCoder<A extends string = string>
   }
 
   public size(idlAccount: IdlTypeDef): number {
+    if (idlAccount.size !== undefined) {
+      return idlAccount.size;
+    }
     return (
-      ACCOUNT_DISCRIMINATOR_SIZE + (accountSize(this.idl, idlAccount) ?? 0)
+      (idlAccount.discriminator?.length ?? ACCOUNT_DISCRIMINATOR_SIZE) +
+      (idlAccount.version !== undefined ? 1 : 0) +
+      (accountSize(this.idl, idlAccount) ?? 0)
     );
   }
 
+  /**
+   * Returns the discriminator of the given account, which is either given
+   * explicitly by the IDL or derived from the account name.
+   */
+  private discriminator(accountName: A): Buffer {
+    const discriminator = this.idl?.accounts?.find(
+      (acc) => acc.name === accountName
+    )?.discriminator;
+    return discriminator
+      ? Buffer.from(discriminator)
+      : BorshAccountsCoder.accountDiscriminator(accountName);
+  }
+
+  private accountVersion(accountName: A): number | undefined {
+    return this.idl?.accounts?.find((acc) => acc.name === accountName)
+      ?.version;
+  }
+
   /**
    * Calculates and returns a unique 8 byte discriminator prepended to all anchor accounts.
    *
@@ -14,13 +14,14 @@ export class BorshEventCoder implements EventCoder {
   private layouts: Map<string, Layout>;
 
   /**
-   * Maps base64 encoded event discriminator to event name.
+   * Event discriminators, each mapped to the event name.
    */
-  private discriminators: Map<string, string>;
+  private discriminators: { discriminator: Buffer; name: string }[];
 
   public constructor(idl: Idl) {
     if (idl.events === undefined) {
       this.layouts = new Map();
+      this.discriminators = [];
       return;
     }
     const layouts: [string, Layout<any>][] = idl.events.map((event) => {
@@ -37,14 +38,14 @@ export class BorshEventCoder implements EventCoder {
     });
     this.layouts = new Map(layouts);
 
-    this.discriminators = new Map<string, string>(
-      idl.events === undefined
-        ? []
-        : idl.events.map((e) => [
-            base64.fromByteArray(eventDiscriminator(e.name)),
-            e.name,
-          ])
-    );
+    this.discriminators = idl.events.map((e) => {
+      return {
+        discriminator: e.discriminator
+          ? Buffer.from(e.discriminator)
+          : eventDiscriminator(e.name),
+        name: e.name,
+      };
+    });
   }
 
   public decode<E extends IdlEvent = IdlEvent, T = Record<string, never>>(
@@ -57,19 +58,23 @@ export class BorshEventCoder implements EventCoder {
     } catch (e) {
       return null;
     }
-    const disc = base64.fromByteArray(logArr.slice(0, 8));
-
     // Only deserialize if the discriminator implies a proper event.
-    const eventName = this.discriminators.get(disc);
-    if (eventName === undefined) {
+    const event = this.discriminators.find(
+      ({ discriminator }) =>
+        discriminator.compare(logArr.slice(0, discriminator.length)) === 0
+    );
+    if (event === undefined) {
       return null;
     }
+    const eventName = event.name;
 
     const layout = this.layouts.get(eventName);
     if (!layout) {
       throw new Error(`Unknown event: ${eventName}`);
     }
-    const data = layout.decode(logArr.slice(8)) as EventData<
+    const data = layout.decode(
+      logArr.slice(event.discriminator.length)
+    ) as EventData<
       E["fields"][number],
       T
     >;
@@ -1,9 +1,39 @@
 import camelCase from "camelcase";
 import { Layout } from "buffer-layout";
 import * as borsh from "@project-serum/borsh";
-import { IdlField, IdlTypeDef, IdlEnumVariant, IdlType } from "../../idl.js";
+import {
+  IdlField,
+  IdlTypeDef,
+  IdlEnumVariant,
+  IdlType,
+  instantiateTypeDef,
+} from "../../idl.js";
 import { IdlError } from "../../error.js";
 
+// Wraps a layout, converting values when decoding and encoding.
+class MappedLayout<T, U> extends Layout<U> {
+  constructor(
+    private layout: Layout<T>,
+    private decoder: (value: T) => U,
+    private encoder: (value: U) => T,
+    property?: string
+  ) {
+    super(layout.span, property);
+  }
+
+  decode(b: Buffer, offset?: number): U {
+    return this.decoder(this.layout.decode(b, offset));
+  }
+
+  encode(src: U, b: Buffer, offset?: number): number {
+    return this.layout.encode(this.encoder(src), b, offset);
+  }
+
+  getSpan(b: Buffer, offset?: number): number {
+    return this.layout.getSpan(b, offset);
+  }
+}
+
 export class IdlCoder {
   public static fieldLayout(
     field: { name?: string } & Pick<IdlField, "type">,
@@ -94,6 +124,51 @@ export class IdlCoder {
             throw new IdlError(`Type not found: ${JSON.stringify(field)}`);
           }
           return IdlCoder.typeDefLayout(filtered[0], types, fieldName);
+        } else if ("definedWithTypeArgs" in field.type) {
+          const { name, args } = field.type.definedWithTypeArgs;
+          // User defined generic type.
+          if (types === undefined) {
+            throw new IdlError("User defined types not provided");
+          }
+          const filtered = types.filter((t) => t.name === name);
+          if (filtered.length !== 1) {
+            throw new IdlError(`Type not found: ${JSON.stringify(field)}`);
+          }
+          return IdlCoder.typeDefLayout(
+            instantiateTypeDef(filtered[0], args),
+            types,
+            fieldName
+          );
+        } else if ("hashMap" in field.type || "bTreeMap" in field.type) {
+          const [keyTy, valueTy] =
+            "hashMap" in field.type ? field.type.hashMap : field.type.bTreeMap;
+          const entry = borsh.struct([
+            IdlCoder.fieldLayout({ name: "key", type: keyTy }, types),
+            IdlCoder.fieldLayout({ name: "value", type: valueTy }, types),
+          ]);
+          return new MappedLayout(
+            borsh.vec(entry),
+            (entries: { key: any; value: any }[]) =>
+              new Map(entries.map(({ key, value }) => [key, value])),
+            (map: Map<any, any>) =>
+              Array.from(map.entries()).map(([key, value]) => ({ key, value })),
+            fieldName
+          );
+        } else if ("hashSet" in field.type || "bTreeSet" in field.type) {
+          // Sets are encoded like vectors.
+          return borsh.vec(
+            IdlCoder.fieldLayout(
+              {
+                name: undefined,
+                type:
+                  "hashSet" in field.type
+                    ? field.type.hashSet
+                    : field.type.bTreeSet,
+              },
+              types
+            ),
+            fieldName
+          );
         } else if ("array" in field.type) {
           let arrayTy = field.type.array[0];
           let arrayLen = field.type.array[1];
@@ -123,6 +198,18 @@ export class IdlCoder {
         return x;
       });
       return borsh.struct(fieldLayouts, name);
+    } else if (typeDef.type.kind === "tuple") {
+      // Tuple structs are decoded as arrays.
+      const fieldLayouts = typeDef.type.fields.map((type, idx) =>
+        IdlCoder.fieldLayout({ name: idx.toString(), type }, types)
+      );
+      return new MappedLayout(
+        borsh.struct(fieldLayouts),
+        (fields: Record<string, any>) =>
+          fieldLayouts.map((_, idx) => fields[idx.toString()]),
+        (fields: any[]) => Object.assign({}, fields),
+        name
+      );
     } else if (typeDef.type.kind === "enum") {
       let variants = typeDef.type.variants.map((variant: IdlEnumVariant) => {
         const name = camelCase(variant.name);
@@ -40,25 +40,28 @@ export class BorshInstructionCoder implements InstructionCoder {
   // Instruction args layout. Maps namespaced method
   private ixLayout: Map<string, Layout>;
 
-  // Base58 encoded sighash to instruction layout.
-  private sighashLayouts: Map<string, { layout: Layout; name: string }>;
+  // Instruction discriminator to instruction layout.
+  private sighashLayouts: {
+    discriminator: Buffer;
+    layout: Layout;
+    name: string;
+  }[];
 
   public constructor(private idl: Idl) {
     this.ixLayout = BorshInstructionCoder.parseIxLayout(idl);
 
-    const sighashLayouts = new Map();
-    idl.instructions.forEach((ix) => {
-      const sh = sighash(SIGHASH_GLOBAL_NAMESPACE, ix.name);
-      sighashLayouts.set(bs58.encode(sh), {
-        layout: this.ixLayout.get(ix.name),
+    const sighashLayouts = idl.instructions.map((ix) => {
+      return {
+        discriminator: this.discriminator(SIGHASH_GLOBAL_NAMESPACE, ix.name),
+        layout: this.ixLayout.get(ix.name) as Layout,
         name: ix.name,
-      });
+      };
     });
 
     if (idl.state) {
-      idl.state.methods.map((ix) => {
-        const sh = sighash(SIGHASH_STATE_NAMESPACE, ix.name);
-        sighashLayouts.set(bs58.encode(sh), {
+      idl.state.methods.forEach((ix) => {
+        sighashLayouts.push({
+          discriminator: sighash(SIGHASH_STATE_NAMESPACE, ix.name),
           layout: this.ixLayout.get(ix.name) as Layout,
           name: ix.name,
         });
@@ -91,7 +94,21 @@ export class BorshInstructionCoder implements InstructionCoder {
     }
     const len = layout.encode(ix, buffer);
     const data = buffer.slice(0, len);
-    return Buffer.concat([sighash(nameSpace, ixName), data]);
+    return Buffer.concat([this.discriminator(nameSpace, methodName), data]);
+  }
+
+  /**
+   * Returns the discriminator of the given instruction, which is either given
+   * explicitly by the IDL or derived from the instruction name.
+   */
+  private discriminator(nameSpace: string, ixName: string): Buffer {
+    const idlIx =
+      nameSpace === SIGHASH_GLOBAL_NAMESPACE
+        ? this.idl.instructions.find((ix) => ix.name === ixName)
+        : undefined;
+    return idlIx?.discriminator
+      ? Buffer.from(idlIx.discriminator)
+      : sighash(nameSpace, ixName);
   }
 
   private static parseIxLayout(idl: Idl): Map<string, Layout> {
@@ -130,15 +147,20 @@ export class BorshInstructionCoder implements InstructionCoder {
     ix: Buffer | string,
     encoding: "hex" | "base58" = "hex"
   ): Instruction | null {
-    if (typeof ix === "string") {
-      ix = encoding === "hex" ? Buffer.from(ix, "hex") : bs58.decode(ix);
-    }
-    let sighash = bs58.encode(ix.slice(0, 8));
-    let data = ix.slice(8);
-    const decoder = this.sighashLayouts.get(sighash);
+    const ixData: Buffer =
+      typeof ix !== "string"
+        ? ix
+        : encoding === "hex"
+        ? Buffer.from(ix, "hex")
+        : bs58.decode(ix);
+    const decoder = this.sighashLayouts.find(
+      ({ discriminator }) =>
+        discriminator.compare(ixData.slice(0, discriminator.length)) === 0
+    );
     if (!decoder) {
       return null;
     }
+    const data = ixData.slice(decoder.discriminator.length);
     return {
       data: decoder.layout.decode(data),
       name: decoder.name,
@@ -238,6 +260,32 @@ class InstructionFormatter {
     if ("array" in idlType) {
       return `Array<${idlType.array[0]}; ${idlType.array[1]}>`;
     }
+    if ("definedWithTypeArgs" in idlType) {
+      const args = idlType.definedWithTypeArgs.args.map((arg) =>
+        "type" in arg
+          ? this.formatIdlType(arg.type)
+          : "value" in arg
+          ? arg.value
+          : arg.generic
+      );
+      return `${idlType.definedWithTypeArgs.name}<${args.join(", ")}>`;
+    }
+    if ("hashMap" in idlType) {
+      return `HashMap<${this.formatIdlType(
+        idlType.hashMap[0]
+      )}, ${this.formatIdlType(idlType.hashMap[1])}>`;
+    }
+    if ("bTreeMap" in idlType) {
+      return `BTreeMap<${this.formatIdlType(
+        idlType.bTreeMap[0]
+      )}, ${this.formatIdlType(idlType.bTreeMap[1])}>`;
+    }
+    if ("hashSet" in idlType) {
+      return `HashSet<${this.formatIdlType(idlType.hashSet)}>`;
+    }
+    if ("bTreeSet" in idlType) {
+      return `BTreeSet<${this.formatIdlType(idlType.bTreeSet)}>`;
+    }
 
     throw new Error(`Unknown IDL type: ${idlType}`);
   }
@@ -313,6 +361,18 @@ class InstructionFormatter {
         })
         .join(", ");
       return "{ " + fields + " }";
+    } else if (typeDef.type.kind === "tuple") {
+      const tuple = typeDef.type;
+      const fields = (<Array<Object>>data)
+        .map((d, idx) =>
+          InstructionFormatter.formatIdlData(
+            { name: "", type: tuple.fields[idx] },
+            d,
+            types
+          )
+        )
+        .join(", ");
+      return "(" + fields + ")";
     } else {
       if (typeDef.type.variants.length === 0) {
         return "{}";
@@ -1,4 +1,11 @@
-import { Idl, IdlField, IdlTypeDef, IdlEnumVariant, IdlType } from "../idl.js";
+import {
+  Idl,
+  IdlField,
+  IdlTypeDef,
+  IdlEnumVariant,
+  IdlType,
+  instantiateTypeDef,
+} from "../idl.js";
 import { IdlError } from "../error.js";
 
 export function accountSize(idl: Idl, idlAccount: IdlTypeDef): number {
@@ -20,6 +27,11 @@ export function accountSize(idl: Idl, idlAccount: IdlTypeDef): number {
     );
     return Math.max(...variantSizes) + 1;
   }
+  if (idlAccount.type.kind === "tuple") {
+    return idlAccount.type.fields
+      .map((ty) => typeSize(idl, ty))
+      .reduce((a, b) => a + b, 0);
+  }
   if (idlAccount.type.fields === undefined) {
     return 0;
   }
@@ -83,11 +95,27 @@ function typeSize(idl: Idl, ty: IdlType): number {
 
         return accountSize(idl, typeDef);
       }
+      if ("definedWithTypeArgs" in ty) {
+        const { name, args } = ty.definedWithTypeArgs;
+        const filtered = idl.types?.filter((t) => t.name === name) ?? [];
+        if (filtered.length !== 1) {
+          throw new IdlError(`Type not found: ${JSON.stringify(ty)}`);
+        }
+        return accountSize(idl, instantiateTypeDef(filtered[0], args));
+      }
       if ("array" in ty) {
         let arrayTy = ty.array[0];
         let arraySize = ty.array[1];
         return typeSize(idl, arrayTy) * arraySize;
       }
+      if (
+        "hashMap" in ty ||
+        "bTreeMap" in ty ||
+        "hashSet" in ty ||
+        "bTreeSet" in ty
+      ) {
+        return 1;
+      }
       throw new Error(`Invalid type ${JSON.stringify(ty)}`);
   }
 }
@@ -72,6 +72,8 @@ const LangErrorCode = {
   // IDL instructions.
   IdlInstructionStub: 1000,
   IdlInstructionInvalidProgram: 1001,
+  IdlAccountTooSmall: 1002,
+  IdlAccountTooLarge: 1003,
 
   // Constraints.
   ConstraintMut: 2000,
@@ -94,6 +96,7 @@ const LangErrorCode = {
   ConstraintMintFreezeAuthority: 2017,
   ConstraintMintDecimals: 2018,
   ConstraintSpace: 2019,
+  ConstraintDuplicateMutableAccount: 2020,
 
   // Accounts.
   AccountDiscriminatorAlreadySet: 3000,
@@ -111,6 +114,8 @@ const LangErrorCode = {
   AccountNotInitialized: 3012,
   AccountNotProgramData: 3013,
   AccountNotAssociatedTokenAccount: 3014,
+  AccountVersionUnsupported: 3015,
+  AccountReallocNotRentExempt: 3016,
   // State.
   StateInvalidAddress: 4000,
 
@@ -149,6 +154,14 @@ const LangErrorMessage = new Map([
     LangErrorCode.IdlInstructionInvalidProgram,
     "The transaction was given an invalid program for the IDL instruction",
   ],
+  [
+    LangErrorCode.IdlAccountTooSmall,
+    "The IDL account can't be resized below the size of its data",
+  ],
+  [
+    LangErrorCode.IdlAccountTooLarge,
+    "The IDL account can't be larger than the maximum account size",
+  ],
 
   // Constraints.
   [LangErrorCode.ConstraintMut, "A mut constraint was violated"],
@@ -183,6 +196,10 @@ const LangErrorMessage = new Map([
     "A mint decimals constraint was violated",
   ],
   [LangErrorCode.ConstraintSpace, "A space constraint was violated"],
+  [
+    LangErrorCode.ConstraintDuplicateMutableAccount,
+    "The same account was given for multiple mutable accounts",
+  ],
 
   // Accounts.
   [
@@ -227,6 +244,14 @@ const LangErrorMessage = new Map([
     LangErrorCode.AccountNotAssociatedTokenAccount,
     "The given account is not the associated token account",
   ],
+  [
+    LangErrorCode.AccountVersionUnsupported,
+    "The account version is not supported by the program",
+  ],
+  [
+    LangErrorCode.AccountReallocNotRentExempt,
+    "The account doesn't hold enough lamports to be rent exempt once reallocated",
+  ],
 
   // State.
   [
@@ -1,8 +1,10 @@
 import { Buffer } from "buffer";
 import { PublicKey } from "@solana/web3.js";
 import * as borsh from "@project-serum/borsh";
+import { IdlError } from "./error.js";
 
 export type Idl = {
+  spec?: string;
   version: string;
   name: string;
   instructions: IdlInstruction[];
@@ -26,6 +28,7 @@ export type IdlConstant = {
 export type IdlEvent = {
   name: string;
   fields: IdlEventField[];
+  discriminator?: number[];
 };
 
 export type IdlEventField = {
@@ -38,6 +41,7 @@ export type IdlInstruction = {
   name: string;
   accounts: IdlAccountItem[];
   args: IdlField[];
+  discriminator?: number[];
 };
 
 export type IdlState = {
@@ -54,6 +58,10 @@ export type IdlAccount = {
   isMut: boolean;
   isSigner: boolean;
   pda?: IdlPda;
+  relations?: string[];
+  address?: string;
+  owner?: string;
+  payer?: string;
 };
 
 export type IdlPda = {
@@ -76,9 +84,37 @@ export type IdlField = {
 
 export type IdlTypeDef = {
   name: string;
+  generics?: IdlTypeDefGeneric[];
   type: IdlTypeDefTy;
+  version?: number;
+  history?: IdlTypeDefVersion[];
+  discriminator?: number[];
+  repr?: IdlRepr;
+  // Whether the type is stored as it's laid out in memory, by `#[zero_copy]`,
+  // rather than Borsh encoded.
+  zeroCopy?: boolean;
+  // Size in bytes, if fixed. For accounts, the length of the account data.
+  size?: number;
 };
 
+// The memory layout of a type, given by `#[repr(..)]`.
+export type IdlRepr = {
+  kind: "rust" | "c" | "transparent";
+  int?: IdlType;
+  packed?: number;
+  align?: number;
+};
+
+// A previous layout of a versioned account.
+export type IdlTypeDefVersion = {
+  version: number;
+  type: IdlTypeDefTy;
+};
+
+export type IdlTypeDefGeneric =
+  | { kind: "type"; name: string }
+  | { kind: "const"; name: string; type: string };
+
 export type IdlTypeDefTyStruct = {
   kind: "struct";
   fields: IdlTypeDefStruct;
@@ -89,7 +125,13 @@ export type IdlTypeDefTyEnum = {
   variants: IdlEnumVariant[];
 };
 
-type IdlTypeDefTy = IdlTypeDefTyEnum | IdlTypeDefTyStruct;
+// A tuple struct.
+export type IdlTypeDefTyTuple = {
+  kind: "tuple";
+  fields: IdlType[];
+};
+
+type IdlTypeDefTy = IdlTypeDefTyEnum | IdlTypeDefTyStruct | IdlTypeDefTyTuple;
 
 type IdlTypeDefStruct = Array<IdlField>;
 
@@ -111,16 +153,38 @@ export type IdlType =
   | "string"
   | "publicKey"
   | IdlTypeDefined
+  | IdlTypeDefinedWithTypeArgs
+  | IdlTypeGeneric
   | IdlTypeOption
   | IdlTypeCOption
   | IdlTypeVec
-  | IdlTypeArray;
+  | IdlTypeArray
+  | IdlTypeGenericLenArray
+  | IdlTypeHashMap
+  | IdlTypeBTreeMap
+  | IdlTypeHashSet
+  | IdlTypeBTreeSet;
 
 // User defined type.
 export type IdlTypeDefined = {
   defined: string;
 };
 
+// User defined generic type.
+export type IdlTypeDefinedWithTypeArgs = {
+  definedWithTypeArgs: { name: string; args: IdlDefinedTypeArg[] };
+};
+
+export type IdlDefinedTypeArg =
+  | { generic: string }
+  | { value: string }
+  | { type: IdlType };
+
+// Generic parameter of the enclosing type definition.
+export type IdlTypeGeneric = {
+  generic: string;
+};
+
 export type IdlTypeOption = {
   option: IdlType;
 };
@@ -137,9 +201,30 @@ export type IdlTypeArray = {
   array: [idlType: IdlType, size: number];
 };
 
+export type IdlTypeGenericLenArray = {
+  genericLenArray: [idlType: IdlType, size: string];
+};
+
+export type IdlTypeHashMap = {
+  hashMap: [key: IdlType, value: IdlType];
+};
+
+export type IdlTypeBTreeMap = {
+  bTreeMap: [key: IdlType, value: IdlType];
+};
+
+export type IdlTypeHashSet = {
+  hashSet: IdlType;
+};
+
+export type IdlTypeBTreeSet = {
+  bTreeSet: IdlType;
+};
+
 export type IdlEnumVariant = {
   name: string;
   fields?: IdlEnumFields;
+  discriminant?: number;
 };
 
 type IdlEnumFields = IdlEnumFieldsNamed | IdlEnumFieldsTuple;
@@ -154,6 +239,114 @@ export type IdlErrorCode = {
   msg?: string;
 };
 
+// Returns the type definition of a generic type, with its generic parameters
+// replaced by the given arguments.
+export function instantiateTypeDef(
+  typeDef: IdlTypeDef,
+  args: IdlDefinedTypeArg[]
+): IdlTypeDef {
+  const generics = typeDef.generics ?? [];
+  if (generics.length !== args.length) {
+    throw new IdlError(
+      `Expected ${generics.length} generic arguments for ${typeDef.name}`
+    );
+  }
+  const env: Record<string, IdlDefinedTypeArg> = {};
+  generics.forEach((g, idx) => (env[g.name] = args[idx]));
+  const sub = (ty: IdlType) => substituteGenerics(ty, env);
+
+  const name = typeDef.name;
+  if (typeDef.type.kind === "struct") {
+    const fields = typeDef.type.fields.map((f) => ({
+      ...f,
+      type: sub(f.type),
+    }));
+    return { name, type: { kind: "struct", fields } };
+  }
+  if (typeDef.type.kind === "tuple") {
+    const fields = typeDef.type.fields.map(sub);
+    return { name, type: { kind: "tuple", fields } };
+  }
+  const variants = typeDef.type.variants.map((v) => {
+    const fields = v.fields as (IdlField | IdlType)[] | undefined;
+    return {
+      ...v,
+      fields: fields?.map((f) =>
+        typeof f === "object" && "name" in f
+          ? { ...f, type: sub(f.type) }
+          : sub(f)
+      ) as IdlEnumFields | undefined,
+    };
+  });
+  return { name, type: { kind: "enum", variants } };
+}
+
+function substituteGenerics(
+  ty: IdlType,
+  env: Record<string, IdlDefinedTypeArg>
+): IdlType {
+  if (typeof ty === "string") {
+    return ty;
+  }
+  const sub = (ty: IdlType) => substituteGenerics(ty, env);
+  if ("generic" in ty) {
+    const arg = env[ty.generic];
+    if (arg === undefined || !("type" in arg)) {
+      throw new IdlError(`Expected a type for generic ${ty.generic}`);
+    }
+    return arg.type;
+  }
+  if ("genericLenArray" in ty) {
+    const [inner, len] = ty.genericLenArray;
+    const arg = env[len];
+    if (arg === undefined || !("value" in arg)) {
+      throw new IdlError(`Expected a value for generic ${len}`);
+    }
+    return { array: [sub(inner), parseInt(arg.value)] };
+  }
+  if ("definedWithTypeArgs" in ty) {
+    return {
+      definedWithTypeArgs: {
+        name: ty.definedWithTypeArgs.name,
+        args: ty.definedWithTypeArgs.args.map((arg) => {
+          if ("generic" in arg) {
+            return env[arg.generic] ?? arg;
+          }
+          if ("type" in arg) {
+            return { type: sub(arg.type) };
+          }
+          return arg;
+        }),
+      },
+    };
+  }
+  if ("option" in ty) {
+    return { option: sub(ty.option) };
+  }
+  if ("coption" in ty) {
+    return { coption: sub(ty.coption) };
+  }
+  if ("vec" in ty) {
+    return { vec: sub(ty.vec) };
+  }
+  if ("array" in ty) {
+    return { array: [sub(ty.array[0]), ty.array[1]] };
+  }
+  if ("hashMap" in ty) {
+    return { hashMap: [sub(ty.hashMap[0]), sub(ty.hashMap[1])] };
+  }
+  if ("bTreeMap" in ty) {
+    return { bTreeMap: [sub(ty.bTreeMap[0]), sub(ty.bTreeMap[1])] };
+  }
+  if ("hashSet" in ty) {
+    return { hashSet: sub(ty.hashSet) };
+  }
+  if ("bTreeSet" in ty) {
+    return { bTreeSet: sub(ty.bTreeSet) };
+  }
+  return ty;
+}
+
 // Deterministic IDL address as a function of the program id.
 export async function idlAddress(programId: PublicKey): Promise<PublicKey> {
   const base = (await PublicKey.findProgramAddress([], programId))[0];
@@ -1,4 +1,6 @@
 import camelCase from "camelcase";
+import BN from "bn.js";
+import bs58 from "bs58";
 import { PublicKey, SystemProgram, SYSVAR_RENT_PUBKEY } from "@solana/web3.js";
 import { Idl, IdlSeed, IdlAccount } from "../idl.js";
 import * as utf8 from "../utils/bytes/utf8.js";
@@ -33,12 +35,24 @@ export class AccountsResolver<IDL extends Idl, I extends AllInstructions<IDL>> {
   //       dependency graph and resolve automatically.
   //
   public async resolve() {
+    await this.resolveRelations();
+
     for (let k = 0; k < this._idlIx.accounts.length; k += 1) {
       // Cast is ok because only a non-nested IdlAccount can have a seeds
       // cosntraint.
       const accountDesc = this._idlIx.accounts[k] as IdlAccount;
       const accountDescName = camelCase(accountDesc.name);
 
+      // Fixed address given by an `address` constraint.
+      if (accountDesc.address) {
+        if (this._accounts[accountDescName] === undefined) {
+          this._accounts[accountDescName] = new PublicKey(
+            accountDesc.address
+          );
+        }
+        continue;
+      }
+
       // PDA derived from IDL seeds.
       if (accountDesc.pda && accountDesc.pda.seeds.length > 0) {
         if (this._accounts[accountDescName] === undefined) {
@@ -78,6 +92,30 @@ export class AccountsResolver<IDL extends Idl, I extends AllInstructions<IDL>> {
     }
   }
 
+  // Populates the accounts whose keys are stored in the given accounts by
+  // `has_one` constraints, e.g. `vault` from `state.vault`.
+  private async resolveRelations() {
+    for (const accountDesc of this._idlIx.accounts as IdlAccount[]) {
+      const pubkey = this._accounts[camelCase(accountDesc.name)];
+      const relations = (accountDesc.relations ?? []).filter(
+        (relation) => this._accounts[camelCase(relation)] === undefined
+      );
+      if (pubkey === undefined || relations.length === 0) {
+        continue;
+      }
+      const account = await this._accountStore.fetchAnyAccount(pubkey);
+      if (account === null) {
+        continue;
+      }
+      for (const relation of relations) {
+        const value = account[camelCase(relation)];
+        if (value instanceof PublicKey) {
+          this._accounts[camelCase(relation)] = value;
+        }
+      }
+    }
+  }
+
   private async autoPopulatePda(accountDesc: IdlAccount) {
     if (!accountDesc.pda || !accountDesc.pda.seeds)
       throw new Error("Must have seeds");
@@ -126,12 +164,16 @@ export class AccountsResolver<IDL extends Idl, I extends AllInstructions<IDL>> {
   }
 
   private toBufferConst(seedDesc: IdlSeed): Buffer {
-    return this.toBufferValue(seedDesc.type, seedDesc.value);
+    return this.toBufferValue(
+      seedDesc.type,
+      seedDesc.value,
+      seedDesc.encoding
+    );
   }
 
   private async toBufferArg(seedDesc: IdlSeed): Promise<Buffer> {
     const argValue = this.argValue(seedDesc);
-    return this.toBufferValue(seedDesc.type, argValue);
+    return this.toBufferValue(seedDesc.type, argValue, seedDesc.encoding);
   }
 
   private argValue(seedDesc: IdlSeed): any {
@@ -149,7 +191,7 @@ export class AccountsResolver<IDL extends Idl, I extends AllInstructions<IDL>> {
 
   private async toBufferAccount(seedDesc: IdlSeed): Promise<Buffer> {
     const accountValue = await this.accountValue(seedDesc);
-    return this.toBufferValue(seedDesc.type, accountValue);
+    return this.toBufferValue(seedDesc.type, accountValue, seedDesc.encoding);
   }
 
   private async accountValue(seedDesc: IdlSeed): Promise<any> {
@@ -187,29 +229,28 @@ export class AccountsResolver<IDL extends Idl, I extends AllInstructions<IDL>> {
   }
 
   // Converts the given idl valaue into a Buffer. The values here must be
-  // primitives. E.g. no structs.
+  // primitives. E.g. no structs. Integers are little endian unless the
+  // encoding is "be".
   //
   // TODO: add more types here as needed.
-  private toBufferValue(type: string | any, value: any): Buffer {
+  private toBufferValue(
+    type: string | any,
+    value: any,
+    encoding?: "le" | "be"
+  ): Buffer {
+    const intSize = INT_SIZES[type];
+    if (intSize !== undefined) {
+      return new BN(value)
+        .toTwos(intSize * 8)
+        .toArrayLike(Buffer, encoding ?? "le", intSize);
+    }
     switch (type) {
-      case "u8":
-        return Buffer.from([value]);
-      case "u16":
-        let b = Buffer.alloc(2);
-        b.writeUInt16LE(value);
-        return b;
-      case "u32":
-        let buf = Buffer.alloc(4);
-        buf.writeUInt32LE(value);
-        return buf;
-      case "u64":
-        let bU64 = Buffer.alloc(8);
-        bU64.writeBigUInt64LE(BigInt(value));
-        return bU64;
       case "string":
         return Buffer.from(utf8.encode(value));
+      case "bytes":
+        return Buffer.from(value);
       case "publicKey":
-        return value.toBuffer();
+        return new PublicKey(value).toBuffer();
       default:
         if (type.array) {
           return Buffer.from(value);
@@ -219,6 +260,20 @@ export class AccountsResolver<IDL extends Idl, I extends AllInstructions<IDL>> {
   }
 }
 
+// Sizes in bytes of the integer types.
+const INT_SIZES: { [type: string]: number } = {
+  u8: 1,
+  i8: 1,
+  u16: 2,
+  i16: 2,
+  u32: 4,
+  i32: 4,
+  u64: 8,
+  i64: 8,
+  u128: 16,
+  i128: 16,
+};
+
 // TODO: this should be configureable to avoid unnecessary requests.
 export class AccountStore<IDL extends Idl> {
   private _cache = new Map<string, any>();
@@ -251,4 +306,42 @@ export class AccountStore<IDL extends Idl> {
     }
     return this._cache.get(address);
   }
+
+  // Fetches an account of any type of the program, identified by its
+  // discriminator. Returns null if the account doesn't exist, isn't owned
+  // by the program or has no known discriminator, and throws if it can't be
+  // decoded.
+  public async fetchAnyAccount<T = any>(
+    publicKey: PublicKey
+  ): Promise<T | null> {
+    const address = publicKey.toString();
+    if (this._cache.get(address) === undefined) {
+      const accountInfo = await this._provider.connection.getAccountInfo(
+        publicKey
+      );
+      if (accountInfo === null) {
+        return null;
+      }
+      const client = Object.values(this._accounts).find((client) => {
+        if (!accountInfo.owner.equals(client.programId)) {
+          return false;
+        }
+        const discriminator = bs58.decode(
+          client.coder.accounts.memcmp(client.idlAccount.name).bytes
+        );
+        return discriminator.equals(
+          accountInfo.data.slice(0, discriminator.length)
+        );
+      });
+      if (client === undefined) {
+        return null;
+      }
+      const data = client.coder.accounts.decode(
+        client.idlAccount.name,
+        accountInfo.data
+      );
+      this._cache.set(address, data);
+    }
+    return this._cache.get(address);
+  }
 }
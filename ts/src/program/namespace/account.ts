@@ -108,6 +108,12 @@ export class AccountClient<
   }
   private _coder: Coder;
 
+  /**
+   * Returns the IDL definition of the account.
+   */
+  get idlAccount(): A {
+    return this._idlAccount;
+  }
   private _idlAccount: A;
 
   constructor(